@@ -0,0 +1,416 @@
+//! Derive macros for `history_stack`, enabled by its `derive` feature
+
+#![forbid(unsafe_code)]
+#![warn(clippy::pedantic, clippy::cargo)]
+#![warn(missing_docs, clippy::missing_docs_in_private_items)]
+
+extern crate proc_macro;
+
+use core::fmt::Write;
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// A named field of a struct
+struct Field {
+    /// The contents of every outer attribute of the field
+    attrs: Vec<String>,
+    /// The visibility of the field, empty if private
+    vis: String,
+    /// The name of the field
+    name: String,
+    /// The type of the field
+    ty: String,
+}
+
+/// A struct with named fields
+struct Struct {
+    /// The visibility of the struct, empty if private
+    vis: String,
+    /// The name of the struct
+    name: String,
+    /// The fields of the struct
+    fields: Vec<Field>,
+}
+
+/// Skips outer attributes starting at `*i`, returning their contents
+fn parse_attrs(tokens: &[TokenTree], i: &mut usize) -> Vec<String> {
+    let mut attrs = Vec::new();
+
+    while let (Some(TokenTree::Punct(p)), Some(TokenTree::Group(g))) =
+        (tokens.get(*i), tokens.get(*i + 1))
+    {
+        if p.as_char() != '#' || g.delimiter() != Delimiter::Bracket {
+            break;
+        }
+
+        attrs.push(g.stream().to_string());
+        *i += 2;
+    }
+
+    attrs
+}
+
+/// Parses a visibility starting at `*i`, returning an empty string if there is none
+fn parse_vis(tokens: &[TokenTree], i: &mut usize) -> String {
+    let Some(TokenTree::Ident(ident)) = tokens.get(*i) else {
+        return String::new();
+    };
+
+    if ident.to_string() != "pub" {
+        return String::new();
+    }
+
+    *i += 1;
+
+    match tokens.get(*i) {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+            *i += 1;
+            format!("pub {g}")
+        }
+        _ => String::from("pub"),
+    }
+}
+
+/// Parses the named fields within the braces of a struct
+fn parse_fields(stream: TokenStream) -> Result<Vec<Field>, String> {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut fields = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let attrs = parse_attrs(&tokens, &mut i);
+        let vis = parse_vis(&tokens, &mut i);
+
+        let Some(TokenTree::Ident(name)) = tokens.get(i) else {
+            return Err(String::from("expected a field name"));
+        };
+        let name = name.to_string();
+
+        match tokens.get(i + 1) {
+            Some(TokenTree::Punct(p)) if p.as_char() == ':' => {}
+            _ => return Err(format!("expected a type for field `{name}`")),
+        }
+        i += 2;
+
+        // angle brackets are not groups, so commas within generic arguments have to be skipped
+        // by tracking their depth
+        let mut ty = TokenStream::new();
+        let mut depth = 0usize;
+        let mut arrow = false;
+
+        while let Some(tt) = tokens.get(i) {
+            if let TokenTree::Punct(p) = tt {
+                match p.as_char() {
+                    ',' if depth == 0 => break,
+                    '<' => depth += 1,
+                    '>' if !arrow => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+
+                arrow = p.as_char() == '-' && p.spacing() == Spacing::Joint;
+            } else {
+                arrow = false;
+            }
+
+            ty.extend([tt.clone()]);
+            i += 1;
+        }
+
+        // skip the comma
+        i += 1;
+
+        fields.push(Field {
+            attrs,
+            vis,
+            name,
+            ty: ty.to_string(),
+        });
+    }
+
+    Ok(fields)
+}
+
+/// Parses a non generic struct with named fields
+fn parse_struct(input: TokenStream) -> Result<Struct, String> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut i = 0;
+
+    parse_attrs(&tokens, &mut i);
+    let vis = parse_vis(&tokens, &mut i);
+
+    match tokens.get(i) {
+        Some(TokenTree::Ident(kw)) if kw.to_string() == "struct" => {}
+        _ => return Err(String::from("only structs are supported")),
+    }
+
+    let Some(TokenTree::Ident(name)) = tokens.get(i + 1) else {
+        return Err(String::from("expected a struct name"));
+    };
+
+    match tokens.get(i + 2) {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => Ok(Struct {
+            vis,
+            name: name.to_string(),
+            fields: parse_fields(g.stream())?,
+        }),
+        Some(TokenTree::Punct(p)) if p.as_char() == '<' => {
+            Err(String::from("generic structs are not supported"))
+        }
+        _ => Err(String::from("only structs with named fields are supported")),
+    }
+}
+
+/// Turns an error message into a `compile_error!` invocation
+fn compile_error(msg: &str) -> TokenStream {
+    format!("::core::compile_error!({msg:?});")
+        .parse()
+        .expect("compile_error invocation was valid")
+}
+
+/// Derives a `<Name>History` struct where every field of the struct is held in its own
+/// `history_stack::UndoStack`, along with methods to save, undo and redo every field at once.
+///
+/// Each field of the generated struct has the same name and visibility as the field it tracks,
+/// so fields can also be saved, undone and redone independently. Every field must implement
+/// `Clone`.
+///
+/// Only non generic structs with named fields are supported.
+/// ```rust
+/// # use history_stack::Historied;
+/// #[derive(Historied)]
+/// struct Config {
+///     volume: u8,
+///     name: String,
+/// }
+///
+/// let mut history = ConfigHistory::new(Config {
+///     volume: 5,
+///     name: String::from("default"),
+/// });
+///
+/// history.save_all();
+/// *history.volume = 10;
+/// history.name.push_str(" profile");
+///
+/// // only undo the name change
+/// history.name.undo().unwrap();
+/// assert_eq!(*history.volume, 10);
+///
+/// assert!(history.undo_all());
+/// assert_eq!(history.current().volume, 5);
+/// ```
+///
+/// # Panics
+/// This will panic if the generated implementation failed to parse, which indicates a bug in the
+/// derive
+#[proc_macro_derive(Historied)]
+pub fn derive_historied(input: TokenStream) -> TokenStream {
+    match parse_struct(input) {
+        Ok(s) => historied(&s)
+            .parse()
+            .expect("generated Historied implementation was valid"),
+        Err(e) => compile_error(&e),
+    }
+}
+
+/// Generates the source of a `Historied` derive
+fn historied(s: &Struct) -> String {
+    let Struct { vis, name, fields } = s;
+    let history = format!("{name}History");
+    let mut out = String::new();
+
+    // writing to a String never fails
+    let _ = writeln!(
+        out,
+        "#[doc = \"Per field history of [`{name}`], generated by `#[derive(Historied)]`\"]
+        #[derive(Clone)]
+        {vis} struct {history} {{"
+    );
+
+    for f in fields {
+        let _ = writeln!(
+            out,
+            "#[doc = \"History of the `{0}` field\"] {1} {0}: ::history_stack::UndoStack<{2}>,",
+            f.name, f.vis, f.ty
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "}}
+
+        impl {history} {{
+            #[doc = \"Creates a new `{history}` with every field of `value` as the current value \
+                     of its history\"]
+            pub fn new(value: {name}) -> Self {{
+                Self {{"
+    );
+
+    for f in fields {
+        let _ = writeln!(
+            out,
+            "{0}: ::history_stack::UndoStack::new(value.{0}),",
+            f.name
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "}}
+            }}
+
+            #[doc = \"Saves every field, see `UndoStack::save`\"]
+            pub fn save_all(&mut self) {{"
+    );
+
+    for f in fields {
+        let _ = writeln!(out, "self.{}.save();", f.name);
+    }
+
+    for op in ["undo", "redo"] {
+        let _ = writeln!(
+            out,
+            "}}
+
+            #[doc = \"Calls `UndoStack::{op}` on every field, returning `true` if any field \
+                     changed\"]
+            pub fn {op}_all(&mut self) -> bool {{
+                let mut changed = false;"
+        );
+
+        for f in fields {
+            let _ = writeln!(out, "changed |= self.{}.{op}().is_ok();", f.name);
+        }
+
+        out.push_str("changed\n");
+    }
+
+    let _ = writeln!(
+        out,
+        "}}
+
+            #[doc = \"Returns a clone of the current value of every field\"]
+            pub fn current(&self) -> {name} {{
+                {name} {{"
+    );
+
+    for f in fields {
+        let _ = writeln!(
+            out,
+            "{0}: ::core::clone::Clone::clone(self.{0}.get()),",
+            f.name
+        );
+    }
+
+    out.push_str("}\n}\n}\n");
+
+    out
+}
+
+/// Derives `history_stack::Snapshot` for a struct, capturing only the fields marked
+/// `#[snapshot]` into a generated `<Name>Snapshot` struct and restoring only those fields.
+///
+/// Fields without the attribute, such as caches or handles, are left untouched by a restore.
+/// Every marked field must implement `Clone`, and the generated struct has the same name,
+/// visibility and type for each of them.
+///
+/// Only non generic structs with named fields are supported.
+/// ```rust
+/// # use history_stack::{Snapshot, UndoStack};
+/// #[derive(Snapshot)]
+/// struct Editor {
+///     #[snapshot]
+///     text: String,
+///     render_cache: Option<String>,
+/// }
+///
+/// let mut editor = Editor {
+///     text: String::from("hello"),
+///     render_cache: None,
+/// };
+///
+/// let mut undo = UndoStack::new(editor.snapshot());
+///
+/// editor.text.push_str(" world");
+/// editor.render_cache = Some(editor.text.clone());
+/// *undo.save() = editor.snapshot();
+///
+/// if let Ok(state) = undo.undo() {
+///     editor.restore(state.clone());
+/// }
+///
+/// assert_eq!(editor.text, "hello");
+/// assert!(editor.render_cache.is_some());
+/// ```
+///
+/// # Panics
+/// This will panic if the generated implementation failed to parse, which indicates a bug in the
+/// derive
+#[proc_macro_derive(Snapshot, attributes(snapshot))]
+pub fn derive_snapshot(input: TokenStream) -> TokenStream {
+    match parse_struct(input) {
+        Ok(s) => snapshot(&s)
+            .parse()
+            .expect("generated Snapshot implementation was valid"),
+        Err(e) => compile_error(&e),
+    }
+}
+
+/// Generates the source of a `Snapshot` derive
+fn snapshot(s: &Struct) -> String {
+    let Struct { vis, name, fields } = s;
+    let state = format!("{name}Snapshot");
+    let marked: Vec<&Field> = fields
+        .iter()
+        .filter(|f| f.attrs.iter().any(|a| a == "snapshot"))
+        .collect();
+    let mut out = String::new();
+
+    // writing to a String never fails
+    let _ = writeln!(
+        out,
+        "#[doc = \"The fields of [`{name}`] marked `#[snapshot]`, generated by \
+                 `#[derive(Snapshot)]`\"]
+        #[derive(Clone)]
+        {vis} struct {state} {{"
+    );
+
+    for f in &marked {
+        let _ = writeln!(
+            out,
+            "#[doc = \"Snapshot of the `{0}` field\"] {1} {0}: {2},",
+            f.name, f.vis, f.ty
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "}}
+
+        impl ::history_stack::Snapshot for {name} {{
+            type State = {state};
+
+            fn snapshot(&self) -> {state} {{
+                {state} {{"
+    );
+
+    for f in &marked {
+        let _ = writeln!(out, "{0}: ::core::clone::Clone::clone(&self.{0}),", f.name);
+    }
+
+    let _ = writeln!(
+        out,
+        "}}
+            }}
+
+            fn restore(&mut self, state: {state}) {{"
+    );
+
+    for f in &marked {
+        let _ = writeln!(out, "self.{0} = state.{0};", f.name);
+    }
+
+    out.push_str("}\n}\n");
+
+    out
+}
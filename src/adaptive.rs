@@ -0,0 +1,348 @@
+//! An undo stack that switches between full snapshots and keyframes with deltas by state cost
+
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::{Patch, UndoStack};
+
+/// An entry of an [`AdaptiveUndoStack`]
+#[derive(Clone, Debug)]
+enum Frame<T, D> {
+    /// A full snapshot of a value
+    Key(T),
+    /// The changes from the previous entry to this one
+    Delta(D),
+}
+
+/// An [`UndoStack`] that measures how expensive every state is to snapshot with a cost function,
+/// storing cheap states as full snapshots and expensive ones as a [`Patch`] delta from the entry
+/// before them, with a full keyframe at least every `stride` entries.
+///
+/// This keeps small states as simple as a plain `UndoStack` while large states only store what
+/// changed. Undoing to an entry stored as a delta rebuilds it from the keyframe before it, so a
+/// larger stride saves more memory at the cost of slower undo.
+/// ```rust
+/// # use history_stack::{AdaptiveUndoStack, Diff, Patch};
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Lines(Vec<&'static str>);
+///
+/// impl Diff for Lines {
+///     // the amount of lines kept, and the lines added after them
+///     type Output = (usize, Vec<&'static str>);
+///
+///     fn diff(&self, newer: &Self) -> Self::Output {
+///         let kept = self.0.iter().zip(&newer.0).take_while(|(a, b)| a == b).count();
+///         (kept, newer.0[kept..].to_vec())
+///     }
+/// }
+///
+/// impl Patch for Lines {
+///     fn apply(&mut self, (kept, added): &Self::Output) {
+///         self.0.truncate(*kept);
+///         self.0.extend(added);
+///     }
+/// }
+///
+/// // documents of more than 2 lines are stored as deltas, with a keyframe every 4 entries
+/// let mut undo = AdaptiveUndoStack::new(Lines(vec![]), |l| l.0.len(), 2, 4);
+///
+/// for line in ["a", "b", "c", "d", "e"] {
+///     undo.save().0.push(line);
+/// }
+///
+/// assert_eq!(undo.keyframes(), 4);
+/// assert_eq!(undo.undo().unwrap().0, ["a", "b", "c", "d"]);
+/// assert_eq!(undo.undo().unwrap().0, ["a", "b", "c"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct AdaptiveUndoStack<T: Patch> {
+    /// The underlying stack, the current value and the entry after it are always keyframes
+    stack: UndoStack<Frame<T, T::Output>>,
+    /// Estimates how expensive a state is to snapshot
+    cost: fn(&T) -> usize,
+    /// The cost states must exceed to be stored as deltas
+    threshold: usize,
+    /// The maximum amount of deltas in a row between keyframes
+    stride: usize,
+}
+
+impl<T: Patch + Clone> AdaptiveUndoStack<T> {
+    /// Creates a new `AdaptiveUndoStack` with a starting value to act as the current value.
+    ///
+    /// States whose `cost` is greater than `threshold` are stored as deltas, and at most `stride`
+    /// deltas are stored in a row before a keyframe. A `stride` of `0` stores every state as a
+    /// full snapshot.
+    pub fn new(start: T, cost: fn(&T) -> usize, threshold: usize, stride: usize) -> Self {
+        Self {
+            stack: UndoStack::new(Frame::Key(start)),
+            cost,
+            threshold,
+            stride,
+        }
+    }
+
+    /// Returns the current value, which is always a keyframe
+    fn key(&self) -> &T {
+        match self.stack.get() {
+            Frame::Key(v) => v,
+            Frame::Delta(_) => unreachable!("AdaptiveUndoStack: current value was a delta"),
+        }
+    }
+
+    /// Returns the current value mutably, which is always a keyframe
+    fn key_mut(&mut self) -> &mut T {
+        match self.stack.get_mut() {
+            Frame::Key(v) => v,
+            Frame::Delta(_) => unreachable!("AdaptiveUndoStack: current value was a delta"),
+        }
+    }
+
+    /// Rebuilds the value at `index` from the keyframe at or before it
+    fn rebuild(&self, index: usize) -> T {
+        let history = &self.stack.history[..=index];
+
+        // the first entry is always a keyframe, as it has no entry to be a delta from
+        let key = history
+            .iter()
+            .rposition(|f| matches!(f, Frame::Key(_)))
+            .expect("AdaptiveUndoStack: history held no keyframe");
+
+        let Frame::Key(v) = &history[key] else {
+            unreachable!()
+        };
+        let mut value = v.clone();
+
+        for frame in &history[key + 1..] {
+            if let Frame::Delta(d) = frame {
+                value.apply(d);
+            }
+        }
+
+        value
+    }
+
+    /// Turns the entry after the current value into a keyframe, so the current value can be
+    /// edited in place without breaking it
+    fn key_next(&mut self) {
+        let next = self.stack.current + 1;
+
+        if let Some(Frame::Delta(d)) = self.stack.history.get(next) {
+            let mut value = self.key().clone();
+            value.apply(d);
+            self.stack.history[next] = Frame::Key(value);
+        }
+    }
+
+    /// Stores the current value as a delta if it is expensive enough and the stride allows it,
+    /// which is only done when moving forward from it, as the entry before it is then final
+    fn leave_forward(&mut self) {
+        let current = self.stack.current;
+
+        let Some(previous) = current.checked_sub(1) else {
+            return;
+        };
+
+        let deltas = self.stack.history[..=previous]
+            .iter()
+            .rev()
+            .take_while(|f| matches!(f, Frame::Delta(_)))
+            .count();
+
+        if (self.cost)(self.key()) > self.threshold && deltas < self.stride {
+            let diff = self.rebuild(previous).diff(self.key());
+            self.stack.history[current] = Frame::Delta(diff);
+        }
+    }
+
+    /// Saves the current value, storing it as a delta if it is expensive, see
+    /// [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T {
+        let value = self.key().clone();
+
+        self.leave_forward();
+        self.stack.push(Frame::Key(value));
+        self.key_mut()
+    }
+
+    /// Pushes a new current value, storing the previous one as a delta if it is expensive, see
+    /// [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        self.leave_forward();
+        self.stack.push(Frame::Key(new_current));
+        self.key_mut()
+    }
+
+    /// Undoes to the previous state, rebuilding it from its keyframe if it is a delta, see
+    /// [`UndoStack::undo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        if self.stack.undo().is_err() {
+            return Err(self.key_mut());
+        }
+
+        let current = self.stack.current;
+
+        if let Frame::Delta(_) = self.stack.get() {
+            self.stack.history[current] = Frame::Key(self.rebuild(current));
+        }
+
+        Ok(self.key_mut())
+    }
+
+    /// Redoes to the next state, storing the state it moves from as a delta if it is expensive,
+    /// see [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        if !self.stack.can_redo() {
+            return Err(self.key_mut());
+        }
+
+        self.key_next();
+        self.leave_forward();
+        let _ = self.stack.redo();
+        self.key_next();
+
+        Ok(self.key_mut())
+    }
+
+    /// Returns the amount of entries in history stored as full snapshots, including the current
+    /// value
+    #[must_use]
+    pub fn keyframes(&self) -> usize {
+        self.stack
+            .history
+            .iter()
+            .filter(|f| matches!(f, Frame::Key(_)))
+            .count()
+    }
+
+    /// Returns the cost states must exceed to be stored as deltas
+    #[must_use]
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Sets the cost states must exceed to be stored as deltas, which applies to states saved
+    /// from then on
+    pub fn set_threshold(&mut self, threshold: usize) {
+        self.threshold = threshold;
+    }
+
+    /// Returns the maximum amount of deltas in a row between keyframes
+    #[must_use]
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Sets the maximum amount of deltas in a row between keyframes, which applies to states
+    /// saved from then on
+    pub fn set_stride(&mut self, stride: usize) {
+        self.stride = stride;
+    }
+
+    /// Returns the amount of values held in history, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Always returns `false`, as the current value is always held whole in history
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in history
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.stack.position()
+    }
+
+    /// Returns a reference to the current value, which is always held whole even while older
+    /// states are stored as deltas
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.key()
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        self.key_mut()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T: Patch + Clone> ops::Deref for AdaptiveUndoStack<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T: Patch + Clone> ops::DerefMut for AdaptiveUndoStack<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn adaptive_undo_stack() {
+    use crate::Diff;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Counter(u32);
+
+    impl Diff for Counter {
+        type Output = u32;
+
+        fn diff(&self, newer: &Self) -> u32 {
+            newer.0 - self.0
+        }
+    }
+
+    impl Patch for Counter {
+        fn apply(&mut self, diff: &u32) {
+            self.0 += diff;
+        }
+    }
+
+    let mut undo = AdaptiveUndoStack::new(Counter(0), |c| c.0 as usize, 2, 2);
+
+    for _ in 0..8 {
+        undo.save().0 += 1;
+    }
+
+    // 0, 1 and 2 are cheap, and every third expensive state is a keyframe
+    assert_eq!(undo.keyframes(), 5);
+
+    for expected in (0..8).rev() {
+        assert_eq!(undo.undo(), Ok(&mut Counter(expected)));
+    }
+    assert!(undo.undo().is_err());
+
+    *undo.redo().unwrap() = Counter(1);
+    for expected in 2..=8 {
+        assert_eq!(undo.redo(), Ok(&mut Counter(expected)));
+    }
+
+    // editing a state after undoing to it never breaks the entries after it
+    undo.undo().unwrap();
+    undo.undo().unwrap();
+    undo.get_mut().0 = 100;
+    assert_eq!(undo.redo(), Ok(&mut Counter(7)));
+    assert_eq!(undo.undo(), Ok(&mut Counter(100)));
+
+    undo.set_stride(0);
+    undo.push(Counter(200));
+    undo.save();
+    assert_eq!(undo.len(), 9);
+    assert_eq!(undo.undo(), Ok(&mut Counter(200)));
+}
@@ -0,0 +1,432 @@
+//! An undo stack carrying user metadata alongside every entry of its history
+
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::UndoStack;
+
+/// A value in history along with its metadata
+#[derive(Clone, Debug, Default)]
+struct Annotated<T, M> {
+    /// The value
+    value: T,
+    /// The metadata attached to value
+    meta: M,
+}
+
+/// An [`UndoStack`] where every entry of history carries user metadata `M`, such as an action
+/// name, the id of the user that made the change, or a timestamp.
+///
+/// Metadata is stored together with its value, so it can never drift out of sync when history is
+/// truncated by a save or evicted by [`keep_last`](AnnotatedUndoStack::keep_last).
+/// ```rust
+/// # use history_stack::AnnotatedUndoStack;
+/// let mut undo = AnnotatedUndoStack::new(String::new(), "open");
+///
+/// undo.save_with_meta("type").push_str("hello");
+/// undo.save_with_meta("type").push_str(" world");
+///
+/// assert_eq!(*undo.meta(), "type");
+/// assert_eq!(undo.meta_at(0), Some(&"open"));
+///
+/// undo.undo().unwrap();
+/// assert_eq!(undo.get(), "hello");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AnnotatedUndoStack<T, M> {
+    /// The underlying stack holding values together with their metadata
+    stack: UndoStack<Annotated<T, M>>,
+}
+
+impl<T, M> AnnotatedUndoStack<T, M> {
+    /// Creates a new `AnnotatedUndoStack` with a starting value and its metadata to act as the
+    /// current value
+    pub fn new(start: T, meta: M) -> Self {
+        Self {
+            stack: UndoStack::new(Annotated { value: start, meta }),
+        }
+    }
+
+    /// Saves the current value to history, attaching `meta` to the new current value, see
+    /// [`UndoStack::save`]. The metadata of the saved value is unchanged.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save_with_meta(&mut self, meta: M) -> &mut T
+    where
+        T: Clone,
+    {
+        let value = self.stack.get().value.clone();
+
+        &mut self.stack.push(Annotated { value, meta }).value
+    }
+
+    /// Pushes a new current value with its metadata, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push_with_meta(&mut self, new_current: T, meta: M) -> &mut T {
+        &mut self
+            .stack
+            .push(Annotated {
+                value: new_current,
+                meta,
+            })
+            .value
+    }
+
+    /// Undoes to the previous state, tags are kept with the state they were given to, see
+    /// [`UndoStack::undo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        match self.stack.undo() {
+            Ok(v) => Ok(&mut v.value),
+            Err(v) => Err(&mut v.value),
+        }
+    }
+
+    /// Redoes to the next state, tags are kept with the state they were given to, see
+    /// [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        match self.stack.redo() {
+            Ok(v) => Ok(&mut v.value),
+            Err(v) => Err(&mut v.value),
+        }
+    }
+
+    /// Drops the oldest entries until at most `n` remain, see [`UndoStack::keep_last`]
+    pub fn keep_last(&mut self, n: usize) -> usize {
+        self.stack.keep_last(n)
+    }
+
+    /// Returns the metadata of the current value
+    #[must_use]
+    pub fn meta(&self) -> &M {
+        &self.stack.get().meta
+    }
+
+    /// Returns a mutable reference to the metadata of the current value
+    pub fn meta_mut(&mut self) -> &mut M {
+        &mut self.stack.get_mut().meta
+    }
+
+    /// Returns the metadata of the value at `index` in history, or `None` if `index` is out of
+    /// bounds
+    #[must_use]
+    pub fn meta_at(&self, index: usize) -> Option<&M> {
+        self.stack.history.get(index).map(|v| &v.meta)
+    }
+
+    /// Returns the value at `index` in history, or `None` if `index` is out of bounds
+    #[must_use]
+    pub fn value_at(&self, index: usize) -> Option<&T> {
+        self.stack.history.get(index).map(|v| &v.value)
+    }
+
+    /// Returns an iterator over every value in history along with its metadata, oldest first
+    #[must_use]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&T, &M)> + ExactSizeIterator {
+        self.stack.history.iter().map(|v| (&v.value, &v.meta))
+    }
+
+    /// Returns the amount of values held in history, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Always returns `false`, as the current value is in history whether it is tagged or not
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in history
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.stack.position()
+    }
+
+    /// Returns a reference to the current value, without its tags
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.stack.get().value
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.stack.get_mut().value
+    }
+}
+
+/// The kind of savepoint that created an entry of an [`AnnotatedUndoStack`], distinguishing fine
+/// grained edits such as keystrokes from user visible actions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Savepoint {
+    /// A user visible action, which [`undo_major`](AnnotatedUndoStack::undo_major) and
+    /// [`redo_major`](AnnotatedUndoStack::redo_major) stop at
+    #[default]
+    Major,
+    /// A fine grained edit, which is skipped over by major undo and redo
+    Minor,
+}
+
+impl<T: Clone> AnnotatedUndoStack<T, Savepoint> {
+    /// Saves the current value as a minor savepoint, see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save_minor(&mut self) -> &mut T {
+        self.save_with_meta(Savepoint::Minor)
+    }
+
+    /// Saves the current value as a major savepoint, see [`UndoStack::save`]
+    ///
+    /// ```rust
+    /// # use history_stack::{AnnotatedUndoStack, Savepoint};
+    /// let mut undo = AnnotatedUndoStack::new(String::new(), Savepoint::Major);
+    ///
+    /// // typing is made of many minor edits
+    /// undo.save_major().push('h');
+    /// undo.save_minor().push('i');
+    /// undo.save_minor().push('!');
+    ///
+    /// undo.save_major().make_ascii_uppercase();
+    ///
+    /// assert_eq!(undo.undo_major().unwrap(), "hi!");
+    /// assert_eq!(undo.undo_major().unwrap(), "");
+    /// assert_eq!(undo.redo_major().unwrap(), "hi!");
+    /// // undo still steps over a single edit
+    /// assert_eq!(undo.undo().unwrap(), "hi");
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save_major(&mut self) -> &mut T {
+        self.save_with_meta(Savepoint::Major)
+    }
+}
+
+impl<T> AnnotatedUndoStack<T, Savepoint> {
+    /// Undoes to the last entry of the previous action, where an action is a major savepoint
+    /// along with every minor savepoint after it. This is the inverse of
+    /// [`redo_major`](AnnotatedUndoStack::redo_major), which redoes to the last entry of the next
+    /// action. Within the first action this undoes to the oldest entry, and it returns `Err` like
+    /// [`undo`](AnnotatedUndoStack::undo) if there is no previous state.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo_major(&mut self) -> Result<&mut T, &mut T> {
+        if self.position() == 0 {
+            return Err(&mut self.stack.get_mut().value);
+        }
+
+        let mut start = self.position();
+
+        while start > 0 && self.meta_at(start) == Some(&Savepoint::Minor) {
+            start -= 1;
+        }
+
+        while self.position() > start.saturating_sub(1) {
+            let _ = self.undo();
+        }
+
+        Ok(&mut self.stack.get_mut().value)
+    }
+
+    /// Redoes every minor savepoint up to the next major savepoint, behaving like
+    /// [`redo`](AnnotatedUndoStack::redo) if there is no future state
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo_major(&mut self) -> Result<&mut T, &mut T> {
+        if self.redo().is_err() {
+            return Err(&mut self.stack.get_mut().value);
+        }
+
+        while self.meta_at(self.position() + 1) == Some(&Savepoint::Minor) {
+            let _ = self.redo();
+        }
+
+        Ok(&mut self.stack.get_mut().value)
+    }
+}
+
+/// A set of up to 32 user defined tag bits attached to an entry of an [`AnnotatedUndoStack`], such
+/// as marking entries as formatting changes or as the result of a paste, so they can be filtered
+/// with a mask.
+///
+/// An entry matches a mask when it shares at least one bit with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Tags(pub u32);
+
+impl Tags {
+    /// Returns `true` if these tags share at least one bit with `mask`
+    #[must_use]
+    pub fn matches(self, mask: u32) -> bool {
+        self.0 & mask != 0
+    }
+}
+
+impl<T: Clone> AnnotatedUndoStack<T, Tags> {
+    /// Saves the current value, tagging the new current value with `tags`, see
+    /// [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save_tagged(&mut self, tags: u32) -> &mut T {
+        self.save_with_meta(Tags(tags))
+    }
+}
+
+impl<T> AnnotatedUndoStack<T, Tags> {
+    /// Pushes a new current value tagged with `tags`, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push_tagged(&mut self, new_current: T, tags: u32) -> &mut T {
+        self.push_with_meta(new_current, Tags(tags))
+    }
+
+    /// Undoes to the newest entry before the current value whose tags match `mask`, returning
+    /// `Ok(&mut T)` of the new current value.
+    ///
+    /// Returns `Err(&mut T)` of the unchanged current value if no earlier entry matched. Entries
+    /// that were undone past remain available to [`redo`](AnnotatedUndoStack::redo).
+    /// ```rust
+    /// # use history_stack::{AnnotatedUndoStack, Tags};
+    /// const FORMAT: u32 = 1 << 0;
+    /// const PASTE: u32 = 1 << 1;
+    ///
+    /// let mut undo = AnnotatedUndoStack::new(String::new(), Tags(0));
+    ///
+    /// undo.push_tagged(String::from("pasted"), PASTE);
+    /// undo.push_tagged(String::from("Pasted"), FORMAT);
+    /// undo.push_tagged(String::from("Pasted!"), 0);
+    ///
+    /// assert_eq!(undo.undo_to_tag(PASTE).unwrap(), "pasted");
+    /// assert!(undo.undo_to_tag(FORMAT).is_err());
+    ///
+    /// let formatted: Vec<_> = undo.iter_tagged(FORMAT | PASTE).map(|(i, _)| i).collect();
+    /// assert_eq!(formatted, [1, 2]);
+    /// ```
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo_to_tag(&mut self, mask: u32) -> Result<&mut T, &mut T> {
+        let found = self.stack.history[..self.position()]
+            .iter()
+            .rposition(|v| v.meta.matches(mask));
+
+        match found {
+            Some(index) => {
+                self.stack.move_to(index);
+                Ok(&mut self.stack.get_mut().value)
+            }
+            None => Err(&mut self.stack.get_mut().value),
+        }
+    }
+
+    /// Returns an iterator over every entry in history whose tags match `mask` along with its
+    /// index, oldest first
+    #[must_use]
+    pub fn iter_tagged(&self, mask: u32) -> impl DoubleEndedIterator<Item = (usize, &T)> {
+        self.stack
+            .history
+            .iter()
+            .enumerate()
+            .filter(move |(_, v)| v.meta.matches(mask))
+            .map(|(i, v)| (i, &v.value))
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, M> ops::Deref for AnnotatedUndoStack<T, M> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, M> ops::DerefMut for AnnotatedUndoStack<T, M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn annotated_undo_stack() {
+    use alloc::vec::Vec;
+
+    let mut undo = AnnotatedUndoStack::new(0u8, 'a');
+
+    *undo.save_with_meta('b') += 1;
+    undo.push_with_meta(5, 'c');
+
+    assert_eq!(undo.undo(), Ok(&mut 1));
+    assert_eq!(*undo.meta(), 'b');
+
+    *undo.save_with_meta('d') += 1;
+    *undo.meta_mut() = 'e';
+
+    assert_eq!(
+        undo.iter().map(|(v, m)| (*v, *m)).collect::<Vec<_>>(),
+        [(0, 'a'), (1, 'b'), (2, 'e')]
+    );
+
+    assert_eq!(undo.keep_last(2), 1);
+    assert_eq!(undo.meta_at(0), Some(&'b'));
+    assert_eq!(undo.value_at(1), Some(&2));
+    assert_eq!(undo.meta_at(2), None);
+}
+
+#[test]
+fn major_minor_savepoints() {
+    let mut undo = AnnotatedUndoStack::new(0u8, Savepoint::Major);
+
+    *undo.save_minor() = 1;
+    *undo.save_major() = 2;
+    *undo.save_minor() = 3;
+    *undo.save_minor() = 4;
+
+    // stops at the last entry of the action started by the initial value
+    assert_eq!(undo.undo_major(), Ok(&mut 1));
+    assert_eq!(undo.undo_major(), Ok(&mut 0));
+    assert_eq!(undo.undo_major(), Err(&mut 0));
+
+    assert_eq!(undo.redo_major(), Ok(&mut 1));
+    assert_eq!(undo.redo_major(), Ok(&mut 4));
+    assert_eq!(undo.redo_major(), Err(&mut 4));
+
+    // major undo and redo are inverses of each other
+    for _ in 0..2 {
+        let position = undo.position();
+        undo.undo_major().unwrap();
+        undo.redo_major().unwrap();
+        assert_eq!(undo.position(), position);
+        undo.undo_major().unwrap();
+    }
+    assert_eq!(undo.position(), 0);
+}
+
+#[test]
+fn tagged_entries() {
+    use alloc::vec::Vec;
+
+    let mut undo = AnnotatedUndoStack::new(0u8, Tags(0b01));
+
+    *undo.save_tagged(0b10) = 1;
+    *undo.save_tagged(0b11) = 2;
+    undo.push_tagged(3, 0);
+
+    assert_eq!(
+        undo.iter_tagged(0b01)
+            .map(|(i, v)| (i, *v))
+            .collect::<Vec<_>>(),
+        [(0, 0), (2, 2)]
+    );
+    assert_eq!(undo.iter_tagged(0).count(), 0);
+
+    assert_eq!(undo.undo_to_tag(0b10), Ok(&mut 2));
+    assert_eq!(undo.undo_to_tag(0b10), Ok(&mut 1));
+    assert_eq!(undo.undo_to_tag(0b10), Err(&mut 1));
+    assert_eq!(undo.undo_to_tag(0b01), Ok(&mut 0));
+    assert_eq!(undo.redo(), Ok(&mut 1));
+}
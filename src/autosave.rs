@@ -0,0 +1,236 @@
+//! An undo stack that groups mutations into undo steps automatically, by count or by time
+
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::{Clock, UndoStack};
+
+/// An [`UndoStack`] that decides on its own when to save, grouping mutations reported through
+/// [`mark_dirty`](AutoSaver::mark_dirty) into undo steps.
+///
+/// A new step starts on the first mutation after any other operation, and then whenever the
+/// current step holds the configured amount of mutations (see
+/// [`set_mutation_limit`](AutoSaver::set_mutation_limit)) or was started at least the configured
+/// interval ago, as measured by a user provided [`Clock`] (see
+/// [`set_interval`](AutoSaver::set_interval)). Without either limit a step lasts until
+/// [`checkpoint`](AutoSaver::checkpoint) is called.
+/// ```rust
+/// # use history_stack::AutoSaver;
+/// # use core::cell::Cell;
+/// let time = Cell::new(0u32);
+/// let mut undo = AutoSaver::new(0u8, || time.get());
+/// undo.set_mutation_limit(Some(3));
+/// undo.set_interval(Some(100));
+///
+/// for _ in 0..5 {
+///     *undo.mark_dirty() += 1;
+/// }
+///
+/// time.set(100);
+/// *undo.mark_dirty() += 1;
+///
+/// // steps of 3 and 2 mutations, then one after the interval passed
+/// assert_eq!(*undo.undo().unwrap(), 5);
+/// assert_eq!(*undo.undo().unwrap(), 3);
+/// assert_eq!(*undo.undo().unwrap(), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct AutoSaver<T, C: Clock> {
+    /// The underlying stack
+    stack: UndoStack<T>,
+    /// The clock used to measure how long the current step has lasted
+    clock: C,
+    /// The amount of mutations after which a new step starts
+    mutations: Option<usize>,
+    /// The time after which a new step starts
+    interval: Option<C::Duration>,
+    /// The amount of mutations in the current step, 0 if the next mutation starts a new step
+    pending: usize,
+    /// When the current step was started
+    started: C::Instant,
+}
+
+impl<T: Default, C: Clock + Default> Default for AutoSaver<T, C> {
+    fn default() -> Self {
+        Self::new(T::default(), C::default())
+    }
+}
+
+impl<T, C: Clock> AutoSaver<T, C> {
+    /// Creates a new `AutoSaver` with a starting value to act as the current value, and no limits
+    /// set
+    pub fn new(start: T, clock: C) -> Self {
+        Self::from_stack(UndoStack::new(start), clock)
+    }
+
+    /// Wraps an existing `UndoStack`, the next mutation always starts a new step
+    pub fn from_stack(stack: UndoStack<T>, clock: C) -> Self {
+        let started = clock.now();
+
+        Self {
+            stack,
+            clock,
+            mutations: None,
+            interval: None,
+            pending: 0,
+            started,
+        }
+    }
+
+    /// Sets the amount of mutations a step holds before the next one starts a new step, or removes
+    /// the limit if `None`, which is the default. A limit of 0 acts like a limit of 1.
+    pub fn set_mutation_limit(&mut self, limit: Option<usize>) {
+        self.mutations = limit;
+    }
+
+    /// Returns the mutation limit, see [`set_mutation_limit`](AutoSaver::set_mutation_limit)
+    pub fn mutation_limit(&self) -> Option<usize> {
+        self.mutations
+    }
+
+    /// Sets the time after which the next mutation starts a new step, measured from the first
+    /// mutation of the current step, or removes the limit if `None`, which is the default
+    pub fn set_interval(&mut self, interval: Option<C::Duration>) {
+        self.interval = interval;
+    }
+
+    /// Returns the interval, see [`set_interval`](AutoSaver::set_interval)
+    pub fn interval(&self) -> Option<C::Duration> {
+        self.interval
+    }
+
+    /// Records a mutation that is about to be made, saving first if it starts a new step, and
+    /// returns the current value to mutate
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn mark_dirty(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        let now = self.clock.now();
+
+        let full = self.mutations.map_or(false, |n| self.pending >= n);
+        let expired = self
+            .interval
+            .map_or(false, |d| self.clock.between(self.started, now) >= d);
+
+        if self.pending == 0 || full || expired {
+            self.stack.save();
+            self.pending = 0;
+            self.started = now;
+        }
+
+        self.pending += 1;
+
+        self.stack.get_mut()
+    }
+
+    /// Returns `true` if mutations were made since the current step was started, meaning the
+    /// current value differs from the newest saved state
+    pub fn is_dirty(&self) -> bool {
+        self.pending != 0
+    }
+
+    /// Ends the current step, so the next mutation always starts a new step
+    pub fn checkpoint(&mut self) {
+        self.pending = 0;
+    }
+
+    /// Saves the current value and ends the current step, see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        self.pending = 0;
+        self.stack.save()
+    }
+
+    /// Pushes a new current value and ends the current step, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        self.pending = 0;
+        self.stack.push(new_current)
+    }
+
+    /// Undoes to the previous state and ends the current step, see [`UndoStack::undo`]. Any
+    /// mutations of the current step are discarded, as with [`UndoStack::undo`] after editing the
+    /// current value.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        self.pending = 0;
+        self.stack.undo()
+    }
+
+    /// Redoes to the next state and ends the current step, see [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        self.pending = 0;
+        self.stack.redo()
+    }
+
+    /// Returns a reference to the underlying stack
+    pub fn stack(&self) -> &UndoStack<T> {
+        &self.stack
+    }
+
+    /// Returns the underlying stack
+    pub fn into_stack(self) -> UndoStack<T> {
+        self.stack
+    }
+
+    /// Returns a reference to the current value, only
+    /// [`mark_dirty`](AutoSaver::mark_dirty) counts as a mutation so reading never starts a step
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.stack.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, C: Clock> ops::Deref for AutoSaver<T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[test]
+fn auto_saver() {
+    use core::cell::Cell;
+
+    let time = Cell::new(0u32);
+    let mut undo = AutoSaver::new(0u8, || time.get());
+
+    *undo.mark_dirty() += 1;
+    *undo.mark_dirty() += 1;
+    assert!(undo.is_dirty());
+    assert_eq!(undo.stack().len(), 2);
+
+    undo.checkpoint();
+    *undo.mark_dirty() += 1;
+    assert_eq!(undo.stack().len(), 3);
+
+    undo.set_interval(Some(10));
+    time.set(9);
+    *undo.mark_dirty() += 1;
+    time.set(10);
+    *undo.mark_dirty() += 1;
+    assert_eq!(undo.stack().len(), 4);
+
+    assert_eq!(undo.undo(), Ok(&mut 4));
+    assert!(!undo.is_dirty());
+
+    // mutating after an undo starts a new step that replaces the undone future
+    *undo.mark_dirty() += 10;
+    assert_eq!(undo.stack().len(), 4);
+    assert_eq!(undo.undo(), Ok(&mut 4));
+    assert_eq!(undo.undo(), Ok(&mut 2));
+    assert_eq!(undo.undo(), Ok(&mut 0));
+}
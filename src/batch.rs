@@ -0,0 +1,119 @@
+//! Batch edits that record at most one history entry, however many saves they make
+
+use core::ops;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// Ends a batch edit when dropped, including when the batch panics
+struct Batch<'a, T, S> {
+    /// The stack the batch runs on
+    stack: &'a mut UndoStack<T, S>,
+    /// Whether the stack was already batching, in which case it keeps batching
+    nested: bool,
+}
+
+impl<T, S> Drop for Batch<'_, T, S> {
+    fn drop(&mut self) {
+        self.stack.batching = self.nested;
+    }
+}
+
+impl<T, S> ops::Deref for Batch<'_, T, S> {
+    type Target = UndoStack<T, S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.stack
+    }
+}
+
+impl<T, S> ops::DerefMut for Batch<'_, T, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.stack
+    }
+}
+
+impl<T: Clone, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Saves once, then runs `f` on the stack as a single batch where every
+    /// [`save`](UndoStack::save) is ignored and every [`push`](UndoStack::push) replaces the
+    /// current value, returning what `f` returned.
+    ///
+    /// This makes one user gesture exactly one undo step, even when it is implemented with helper
+    /// functions that save on their own. Batches may be nested, in which case only the outermost
+    /// batch saves. Undo and redo still work within a batch.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// fn indent(doc: &mut UndoStack<String>) {
+    ///     doc.save().insert_str(0, "    ");
+    /// }
+    ///
+    /// let mut doc = UndoStack::new(String::from("x"));
+    ///
+    /// doc.edit_batch(|doc| {
+    ///     indent(doc);
+    ///     indent(doc);
+    /// });
+    ///
+    /// assert_eq!(doc.len(), 2);
+    /// assert_eq!(doc.undo().unwrap(), "x");
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn edit_batch<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.save();
+
+        let nested = self.batching;
+        self.batching = true;
+
+        let mut batch = Batch {
+            stack: self,
+            nested,
+        };
+
+        f(&mut batch)
+    }
+
+    /// Returns `true` if called within an [`edit_batch`](UndoStack::edit_batch)
+    #[must_use]
+    pub fn is_batching(&self) -> bool {
+        self.batching
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn edit_batch() {
+    let mut undo = UndoStack::new(0u8);
+
+    let len = undo.edit_batch(|undo| {
+        *undo.save() += 1;
+        undo.push(5);
+
+        undo.edit_batch(|undo| *undo.save() += 1);
+        assert!(undo.is_batching());
+
+        undo.len()
+    });
+
+    assert_eq!(len, 2);
+    assert!(!undo.is_batching());
+    assert_eq!(undo, 6);
+    assert_eq!(undo.undo(), Ok(&mut 0));
+
+    undo.redo().unwrap();
+    *undo.save() = 7;
+    assert_eq!(undo.len(), 3);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn batch_ends_on_unwind() {
+    let mut undo = UndoStack::new(0u8);
+
+    let panicked = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        undo.edit_batch(|_| panic!("edit failed"));
+    }));
+
+    assert!(panicked.is_err());
+    assert!(!undo.is_batching());
+}
@@ -0,0 +1,263 @@
+//! An undo tree that keeps every redo branch alive instead of invalidating it
+
+use alloc::vec::Vec;
+#[cfg(feature = "deref")]
+use core::ops;
+
+/// A node of the undo tree
+#[derive(Clone, Debug)]
+struct Node<T> {
+    /// The value of this node
+    value: T,
+    /// The node this one was saved from, `None` for the root
+    parent: Option<usize>,
+    /// The nodes saved from this one, oldest first
+    children: Vec<usize>,
+    /// The child that [`BranchingUndoStack::redo`] moves into
+    active: Option<usize>,
+}
+
+/// Identifies one branch of a [`BranchingUndoStack`], as returned by
+/// [`branches`](BranchingUndoStack::branches)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BranchId(usize);
+
+/// An undo stack that keeps branches, where saving after an undo starts a new branch of history
+/// instead of invalidating the future states that could be redone to.
+///
+/// Every alternative future of the current value can be listed with
+/// [`branches`](BranchingUndoStack::branches), and redone into with
+/// [`redo_into`](BranchingUndoStack::redo_into). A plain [`redo`](BranchingUndoStack::redo)
+/// follows the branch that was last created, undone out of, or switched to.
+/// ```rust
+/// # use history_stack::BranchingUndoStack;
+/// let mut undo = BranchingUndoStack::new(String::from("draft"));
+///
+/// undo.save().push_str(" one");
+/// undo.undo().unwrap();
+/// undo.save().push_str(" two");
+/// undo.undo().unwrap();
+///
+/// let branches: Vec<_> = undo.branches().map(|(_, v)| v.as_str()).collect();
+/// assert_eq!(branches, ["draft one", "draft two"]);
+///
+/// // redo follows the newest branch, but any branch can be chosen
+/// let (first, _) = undo.branches().next().unwrap();
+/// assert_eq!(undo.redo_into(first).unwrap(), "draft one");
+/// ```
+#[derive(Clone, Debug)]
+pub struct BranchingUndoStack<T> {
+    /// Every node of the tree, the root is at index 0
+    nodes: Vec<Node<T>>,
+    /// Index of the node holding the current value
+    current: usize,
+}
+
+impl<T> BranchingUndoStack<T> {
+    /// Creates a new `BranchingUndoStack` with a starting value to act as the current value
+    pub fn new(start: T) -> Self {
+        Self {
+            nodes: alloc::vec![Node {
+                value: start,
+                parent: None,
+                children: Vec::new(),
+                active: None,
+            }],
+            current: 0,
+        }
+    }
+
+    /// Returns the node holding the current value
+    fn node(&self) -> &Node<T> {
+        &self.nodes[self.current]
+    }
+
+    /// Returns a mutable reference to the current value
+    fn value_mut(&mut self) -> &mut T {
+        &mut self.nodes[self.current].value
+    }
+
+    /// Saves the current value and starts a new branch with a clone of it as the current value,
+    /// any existing branches stay reachable, see [`UndoStack::save`](crate::UndoStack::save)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        let value = self.node().value.clone();
+
+        self.push(value)
+    }
+
+    /// Starts a new branch with the given value as the current value, any existing branches stay
+    /// reachable, see [`UndoStack::push`](crate::UndoStack::push)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        let id = self.nodes.len();
+
+        self.nodes.push(Node {
+            value: new_current,
+            parent: Some(self.current),
+            children: Vec::new(),
+            active: None,
+        });
+
+        let parent = &mut self.nodes[self.current];
+        parent.children.push(id);
+        parent.active = Some(id);
+
+        self.current = id;
+
+        self.value_mut()
+    }
+
+    /// Undoes to the state this one was saved from, remembering this branch as the one to redo
+    /// into, see [`UndoStack::undo`](crate::UndoStack::undo)
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        let Some(parent) = self.node().parent else {
+            return Err(self.value_mut());
+        };
+
+        self.nodes[parent].active = Some(self.current);
+        self.current = parent;
+
+        Ok(self.value_mut())
+    }
+
+    /// Redoes into the active branch, see [`UndoStack::redo`](crate::UndoStack::redo)
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        match self.node().active {
+            Some(child) => {
+                self.current = child;
+                Ok(self.value_mut())
+            }
+            None => Err(self.value_mut()),
+        }
+    }
+
+    /// Returns an iterator over every branch that can be redone into from the current value,
+    /// along with the value it starts with, oldest first
+    #[must_use]
+    pub fn branches(&self) -> impl DoubleEndedIterator<Item = (BranchId, &T)> + ExactSizeIterator {
+        self.node()
+            .children
+            .iter()
+            .map(|&id| (BranchId(id), &self.nodes[id].value))
+    }
+
+    /// Returns the branch that [`redo`](BranchingUndoStack::redo) moves into, or `None` if there
+    /// are no branches at the current value
+    #[must_use]
+    pub fn active_branch(&self) -> Option<BranchId> {
+        self.node().active.map(BranchId)
+    }
+
+    /// Makes `branch` the one that [`redo`](BranchingUndoStack::redo) moves into without moving,
+    /// returning `false` and changing nothing if it is not a branch of the current value
+    pub fn switch_to_branch(&mut self, branch: BranchId) -> bool {
+        if !self.node().children.contains(&branch.0) {
+            return false;
+        }
+
+        self.nodes[self.current].active = Some(branch.0);
+
+        true
+    }
+
+    /// Redoes into `branch`, making it the active branch, or returns `Err` with the current value
+    /// if it is not a branch of the current value
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo_into(&mut self, branch: BranchId) -> Result<&mut T, &mut T> {
+        if !self.switch_to_branch(branch) {
+            return Err(self.value_mut());
+        }
+
+        self.redo()
+    }
+
+    /// Returns `true` if there is a previous state to [`undo`](BranchingUndoStack::undo) to
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        self.node().parent.is_some()
+    }
+
+    /// Returns `true` if there is a branch to [`redo`](BranchingUndoStack::redo) into
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        self.node().active.is_some()
+    }
+
+    /// Returns the amount of values held across every branch, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Always returns `false`, as the tree always holds its root
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns a reference to the value of the current node
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.node().value
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value_mut()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::Deref for BranchingUndoStack<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::DerefMut for BranchingUndoStack<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn branching_undo_stack() {
+    let mut undo = BranchingUndoStack::new(0u8);
+
+    *undo.save() = 1;
+    *undo.save() = 2;
+    undo.undo().unwrap();
+    undo.undo().unwrap();
+    undo.push(3);
+    undo.undo().unwrap();
+
+    assert_eq!(undo.len(), 4);
+    assert_eq!(undo.branches().len(), 2);
+    assert_eq!(undo.redo(), Ok(&mut 3));
+    assert!(!undo.can_redo());
+
+    undo.undo().unwrap();
+    let (old, _) = undo.branches().next().unwrap();
+    assert!(undo.switch_to_branch(old));
+    assert_eq!(undo.active_branch(), Some(old));
+
+    assert_eq!(undo.redo(), Ok(&mut 1));
+    // the branch that was undone out of is still followed below the switched branch
+    assert_eq!(undo.redo(), Ok(&mut 2));
+
+    assert!(!undo.switch_to_branch(old));
+    assert_eq!(undo.redo_into(old), Err(&mut 2));
+}
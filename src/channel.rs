@@ -0,0 +1,118 @@
+//! Sending history events over standard library channels
+
+use std::sync::mpsc;
+
+use crate::{HistoryHook, UndoStack};
+
+/// A history event of an [`HookedUndoStack`](crate::HookedUndoStack), sent over a channel
+/// when an [`mpsc::Sender`] or [`mpsc::SyncSender`] of them is used as its hook.
+///
+/// This lets background services such as autosaving react to history activity from another
+/// thread.
+/// ```rust
+/// # use history_stack::{HistoryEvent, HookedUndoStack};
+/// let (tx, rx) = std::sync::mpsc::channel();
+///
+/// let autosave = std::thread::spawn(move || {
+///     rx.iter().filter(|e| *e == HistoryEvent::Saved).count()
+/// });
+///
+/// let mut undo = HookedUndoStack::new(String::new(), tx);
+/// undo.save().push('a');
+/// undo.save().push('b');
+/// drop(undo);
+///
+/// assert_eq!(autosave.join().unwrap(), 2);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HistoryEvent {
+    /// The current value was saved
+    Saved,
+    /// A new current value was pushed
+    Pushed,
+    /// The stack undid `steps` states
+    Undone {
+        /// The amount of states undone
+        steps: usize,
+    },
+    /// The stack redid `steps` states
+    Redone {
+        /// The amount of states redone
+        steps: usize,
+    },
+    /// `n` future values were dropped, when invalidated by a save or push this is sent before the
+    /// corresponding [`Saved`](HistoryEvent::Saved) or [`Pushed`](HistoryEvent::Pushed)
+    Truncated {
+        /// The amount of values dropped
+        n: usize,
+    },
+    /// `n` past values were evicted from history
+    Evicted {
+        /// The amount of values evicted
+        n: usize,
+    },
+}
+
+/// Implements [`HistoryHook`] for a channel sender by sending a [`HistoryEvent`] per
+/// callback, events sent after the receiver was dropped are discarded
+macro_rules! sender_hook {
+    ($($sender:ident),*) => {$(
+        impl<T> HistoryHook<T> for mpsc::$sender<HistoryEvent> {
+            fn on_save(&mut self, _: &UndoStack<T>) {
+                let _ = self.send(HistoryEvent::Saved);
+            }
+
+            fn on_push(&mut self, _: &UndoStack<T>) {
+                let _ = self.send(HistoryEvent::Pushed);
+            }
+
+            fn on_undo(&mut self, _: &UndoStack<T>) {
+                let _ = self.send(HistoryEvent::Undone { steps: 1 });
+            }
+
+            fn on_redo(&mut self, _: &UndoStack<T>) {
+                let _ = self.send(HistoryEvent::Redone { steps: 1 });
+            }
+
+            fn on_truncate(&mut self, _: &UndoStack<T>, dropped: usize) {
+                let _ = self.send(HistoryEvent::Truncated { n: dropped });
+            }
+
+            fn on_evict(&mut self, _: &UndoStack<T>, evicted: &[usize]) {
+                let _ = self.send(HistoryEvent::Evicted { n: evicted.len() });
+            }
+        }
+    )*};
+}
+
+sender_hook!(Sender, SyncSender);
+
+#[test]
+fn channel_events() {
+    use crate::HookedUndoStack;
+
+    let (tx, rx) = mpsc::channel();
+    let mut undo = HookedUndoStack::new(0u8, tx);
+
+    undo.save();
+    undo.undo().unwrap();
+    undo.push(1);
+    undo.keep_last(1);
+    assert!(undo.redo().is_err());
+
+    assert_eq!(
+        rx.try_iter().collect::<alloc::vec::Vec<_>>(),
+        [
+            HistoryEvent::Saved,
+            HistoryEvent::Undone { steps: 1 },
+            HistoryEvent::Truncated { n: 1 },
+            HistoryEvent::Pushed,
+            HistoryEvent::Evicted { n: 1 },
+        ]
+    );
+
+    // a dropped receiver does not affect the stack
+    drop(rx);
+    undo.save();
+}
@@ -0,0 +1,207 @@
+//! Tracking whether an undo stack has moved away from a state marked clean, such as the last
+//! saved version of a document
+
+use crate::{HistoryStorage, UndoStack};
+
+/// Where the clean state of an [`UndoStack`] is relative to its current value, returned by
+/// [`UndoStack::clean_state`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CleanState {
+    /// The current value is the clean state
+    Clean,
+    /// The clean state is held at this index in history, which is not the current value
+    Dirty(usize),
+    /// The clean state is no longer held in history, it was dropped by truncation or eviction or
+    /// merged into another entry, or the stack was built without one. The stack stays dirty until
+    /// it is marked clean again.
+    Invalidated,
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Marks the current value as the clean state, such as after writing it to disk
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(String::from("draft"));
+    /// undo.save().push_str(" one");
+    /// undo.mark_clean();
+    ///
+    /// undo.save().push_str(" two");
+    /// assert!(undo.is_dirty());
+    ///
+    /// // undoing back to the clean state clears the dirty flag
+    /// undo.undo().unwrap();
+    /// assert!(!undo.is_dirty());
+    ///
+    /// undo.undo().unwrap();
+    /// assert!(undo.is_dirty());
+    /// ```
+    pub fn mark_clean(&mut self) {
+        self.clean = Some(self.current);
+    }
+
+    /// Returns `true` if the current value is not the state marked clean by
+    /// [`mark_clean`](UndoStack::mark_clean).
+    ///
+    /// Dirtiness follows the position in history, so undoing or redoing back to the clean state
+    /// makes the stack clean again, while editing the current value in place without saving does
+    /// not make it dirty. A new stack is clean at its starting value, and a stack whose clean state
+    /// was dropped, such as by saving after undoing past it, stays dirty until it is marked clean
+    /// again.
+    #[must_use]
+    pub fn is_dirty(&self) -> bool {
+        self.clean != Some(self.current)
+    }
+
+    /// Returns the index in history of the state marked clean, or `None` if there is none
+    #[must_use]
+    pub fn clean_position(&self) -> Option<usize> {
+        self.clean
+    }
+
+    /// Returns where the state marked clean is, reporting whether it was invalidated by an
+    /// operation that dropped or rewrote it.
+    ///
+    /// Operations that trim history, such as [`keep_last`](UndoStack::keep_last), move the clean
+    /// state along with the entry it marks, and only invalidate it when that entry itself is
+    /// dropped. Operations that replace the marked entry with a different value, such as merging
+    /// a fork back onto it, invalidate it as well. The clean state never points at a different
+    /// entry than the one marked.
+    /// ```rust
+    /// # use history_stack::{CleanState, UndoStack};
+    /// let mut undo = UndoStack::new(0u8);
+    /// *undo.save() = 1;
+    /// undo.mark_clean();
+    /// *undo.save() = 2;
+    ///
+    /// undo.keep_last(2);
+    /// assert_eq!(undo.clean_state(), CleanState::Dirty(0));
+    ///
+    /// undo.keep_last(1);
+    /// assert_eq!(undo.clean_state(), CleanState::Invalidated);
+    /// ```
+    #[must_use]
+    pub fn clean_state(&self) -> CleanState {
+        match self.clean {
+            Some(clean) if clean == self.current => CleanState::Clean,
+            Some(clean) => CleanState::Dirty(clean),
+            None => CleanState::Invalidated,
+        }
+    }
+
+    /// Moves the clean state after the `n` oldest entries were removed from history
+    pub(crate) fn clean_removed_oldest(&mut self, n: usize) {
+        self.clean = self.clean.and_then(|c| c.checked_sub(n));
+    }
+
+    /// Invalidates the clean state if history was truncated to `len` entries past it
+    pub(crate) fn clean_truncated(&mut self, len: usize) {
+        if self.clean >= Some(len) {
+            self.clean = None;
+        }
+    }
+
+    /// Invalidates the clean state if it marks the entry at `index`, which was replaced by a
+    /// different value
+    #[cfg(feature = "alloc")]
+    pub(crate) fn clean_rewritten(&mut self, index: usize) {
+        if self.clean == Some(index) {
+            self.clean = None;
+        }
+    }
+
+    /// Moves the clean state after the entries from `start` to `end` inclusive were merged into
+    /// one entry at `start`, invalidating it if it was one of the merged entries
+    #[cfg(feature = "alloc")]
+    pub(crate) fn clean_squashed(&mut self, start: usize, end: usize) {
+        self.clean = match self.clean {
+            Some(c) if c > end => Some(c - (end - start)),
+            Some(c) if c >= start && start != end => None,
+            clean => clean,
+        };
+    }
+
+    /// Moves the clean state before history is filtered to the entries whose index `keep` returns
+    /// `true` for, invalidating it if its own entry is not kept
+    #[cfg(feature = "alloc")]
+    pub(crate) fn clean_retain(&mut self, mut keep: impl FnMut(usize) -> bool) {
+        if let Some(clean) = self.clean {
+            self.clean = if keep(clean) {
+                Some((0..clean).filter(|&i| keep(i)).count())
+            } else {
+                None
+            };
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn clean_state() {
+    let mut undo = UndoStack::new(0u8);
+    assert!(!undo.is_dirty());
+
+    *undo.save() = 1;
+    *undo.save() = 2;
+    undo.mark_clean();
+    assert_eq!(undo.clean_position(), Some(2));
+
+    undo.undo().unwrap();
+    assert!(undo.is_dirty());
+    undo.redo().unwrap();
+    assert!(!undo.is_dirty());
+
+    assert_eq!(undo.keep_last(2), 1);
+    assert_eq!(undo.clean_position(), Some(1));
+
+    // saving after undoing past the clean state drops it
+    undo.undo().unwrap();
+    *undo.save() = 5;
+    assert_eq!(undo.clean_position(), None);
+    undo.undo().unwrap();
+    assert!(undo.is_dirty());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn clean_survives_trimming() {
+    use crate::Mergeable;
+
+    #[derive(Debug)]
+    struct Newest(u8);
+
+    impl Mergeable for Newest {
+        fn merge(self, newer: Self) -> Self {
+            newer
+        }
+    }
+
+    let mut undo = UndoStack::new(Newest(0));
+    for i in 1..=5 {
+        undo.push(Newest(i));
+    }
+    undo.undo().unwrap();
+    undo.mark_clean();
+
+    undo.squash(0, 1);
+    assert_eq!(undo.get().0, 4);
+    assert_eq!(undo.clean_state(), CleanState::Clean);
+    assert_eq!(undo.clean_position(), Some(3));
+
+    undo.squash(2, 3);
+    assert_eq!(undo.clean_state(), CleanState::Invalidated);
+
+    let mut undo = UndoStack::new(0u8);
+    for i in 1..=8 {
+        undo.push(i);
+    }
+    undo.undo_to_matching(|&v| v == 5).unwrap();
+    undo.mark_clean();
+    undo.redo().unwrap();
+
+    // the clean state is recent enough to be kept, and moves back along with the thinned out
+    // states before it
+    assert_eq!(undo.thin(2), 2);
+    assert_eq!(undo.clean_position(), Some(undo.position() - 1));
+    assert_eq!(undo.clean_state(), CleanState::Dirty(undo.position() - 1));
+}
@@ -0,0 +1,241 @@
+//! An undo stack that merges consecutive saves made with the same key into one undo step
+
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::{Mergeable, UndoStack};
+
+/// An [`UndoStack`] supporting key based coalescing of saves through
+/// [`save_coalesced`](CoalescingUndoStack::save_coalesced).
+///
+/// Consecutive coalesced saves with an equal key act as a single undo step, while a different key
+/// starts a new step. This is useful for continuous interactions such as dragging a handle, where
+/// every intermediate position is saved but undo should revert the whole drag at once.
+/// ```rust
+/// # use history_stack::CoalescingUndoStack;
+/// let mut undo = CoalescingUndoStack::new(0i32);
+///
+/// for _ in 0..10 {
+///     *undo.save_coalesced("drag") += 1;
+/// }
+///
+/// *undo.save_coalesced("resize") *= 2;
+///
+/// assert_eq!(*undo.undo().unwrap(), 10);
+/// assert_eq!(*undo.undo().unwrap(), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CoalescingUndoStack<T, K> {
+    /// The underlying stack
+    stack: UndoStack<T>,
+    /// The key of the last coalesced save, if the current value was created by one and no other
+    /// operation has happened since
+    last_key: Option<K>,
+}
+
+impl<T: Default, K> Default for CoalescingUndoStack<T, K> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T, K> CoalescingUndoStack<T, K> {
+    /// Creates a new `CoalescingUndoStack` with a starting value to act as the current value
+    pub fn new(start: T) -> Self {
+        Self::from_stack(UndoStack::new(start))
+    }
+
+    /// Wraps an existing `UndoStack`, the next coalesced save always creates a new step
+    #[must_use]
+    pub fn from_stack(stack: UndoStack<T>) -> Self {
+        Self {
+            stack,
+            last_key: None,
+        }
+    }
+
+    /// Saves the current value unless the previous operation was a coalesced save with an equal
+    /// `key`, in which case the current value is returned as is and keeps being part of the same
+    /// undo step.
+    ///
+    /// Any other operation on the stack, including [`end_coalescing`], ends the step so the next
+    /// coalesced save always saves.
+    ///
+    /// [`end_coalescing`]: CoalescingUndoStack::end_coalescing
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save_coalesced(&mut self, key: K) -> &mut T
+    where
+        T: Clone,
+        K: PartialEq,
+    {
+        if self.last_key.as_ref() != Some(&key) {
+            self.stack.save();
+            self.last_key = Some(key);
+        }
+
+        self.stack.get_mut()
+    }
+
+    /// Pushes a new current value unless the previous operation was a coalesced save or push with
+    /// an equal `key`, in which case `new_current` is [merged](Mergeable::merge) into the current
+    /// value and keeps being part of the same undo step.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push_coalesced(&mut self, key: K, new_current: T) -> &mut T
+    where
+        T: Mergeable,
+        K: PartialEq,
+    {
+        if self.last_key.as_ref() == Some(&key) {
+            // coalesced steps always end on any operation that could leave redo history, so the
+            // current value is the last value and can be taken out to be merged
+            let current = self
+                .stack
+                .history
+                .pop()
+                .expect("CoalescingUndoStack: history was empty");
+
+            self.stack.history.push(current.merge(new_current));
+            self.stack.clean_rewritten(self.stack.current);
+            self.stack.bump_version();
+        } else {
+            self.stack.push(new_current);
+            self.last_key = Some(key);
+        }
+
+        self.stack.get_mut()
+    }
+
+    /// Ends the current coalesced step, so the next coalesced save always saves
+    pub fn end_coalescing(&mut self) {
+        self.last_key = None;
+    }
+
+    /// Saves the current value and ends any coalesced step, see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        self.last_key = None;
+        self.stack.save()
+    }
+
+    /// Pushes a new current value and ends any coalesced step, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        self.last_key = None;
+        self.stack.push(new_current)
+    }
+
+    /// Undoes to the previous state and ends any coalesced step, see [`UndoStack::undo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        self.last_key = None;
+        self.stack.undo()
+    }
+
+    /// Redoes to the next state and ends any coalesced step, see [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        self.last_key = None;
+        self.stack.redo()
+    }
+
+    /// Returns a reference to the underlying stack
+    pub fn stack(&self) -> &UndoStack<T> {
+        &self.stack
+    }
+
+    /// Returns the underlying stack
+    pub fn into_stack(self) -> UndoStack<T> {
+        self.stack
+    }
+
+    /// Returns a reference to the current value, which saves with the last key keep editing
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.stack.get()
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        self.stack.get_mut()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, K> ops::Deref for CoalescingUndoStack<T, K> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, K> ops::DerefMut for CoalescingUndoStack<T, K> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn key_coalescing() {
+    let mut undo = CoalescingUndoStack::new(0u8);
+
+    *undo.save_coalesced(1) += 1;
+    *undo.save_coalesced(1) += 1;
+    *undo.save_coalesced(2) += 1;
+    undo.end_coalescing();
+    *undo.save_coalesced(2) += 1;
+
+    assert_eq!(undo.stack().len(), 4);
+
+    assert_eq!(*undo.undo().unwrap(), 3);
+    *undo.save_coalesced(2) += 10;
+
+    assert_eq!(undo.stack().len(), 4);
+    assert_eq!(*undo.undo().unwrap(), 3);
+    assert_eq!(*undo.undo().unwrap(), 2);
+    assert_eq!(*undo.undo().unwrap(), 0);
+}
+
+#[test]
+fn push_coalescing() {
+    #[derive(Debug, PartialEq)]
+    struct Sum(u8);
+
+    impl Mergeable for Sum {
+        fn merge(self, newer: Self) -> Self {
+            Sum(self.0 + newer.0)
+        }
+    }
+
+    let mut undo = CoalescingUndoStack::new(Sum(0));
+
+    undo.push_coalesced('a', Sum(1));
+    undo.push_coalesced('a', Sum(2));
+    undo.push_coalesced('b', Sum(4));
+
+    assert_eq!(undo.stack().len(), 3);
+    assert_eq!(undo.undo(), Ok(&mut Sum(3)));
+
+    // merging into the entry marked clean invalidates it, merging after it leaves it in place
+    undo.push_coalesced('c', Sum(1));
+    undo.stack.mark_clean();
+    undo.push_coalesced('c', Sum(1));
+    assert_eq!(undo.stack().clean_state(), crate::CleanState::Invalidated);
+
+    undo.stack.mark_clean();
+    undo.push_coalesced('d', Sum(1));
+    undo.push_coalesced('d', Sum(1));
+    assert_eq!(undo.stack().clean_state(), crate::CleanState::Dirty(2));
+}
@@ -0,0 +1,408 @@
+//! The [`Codec`] trait, and an undo stack that keeps cold history entries encoded
+
+use alloc::vec::Vec;
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::UndoStack;
+
+/// A reversible encoding of `T` into bytes, used by [`CompressedUndoStack`] to store entries that
+/// are far away from the current value in a compressed or serialized form.
+///
+/// `decode` is only ever given bytes that were returned by `encode` on the same codec, and must
+/// return a value equal to the one that was encoded.
+pub trait Codec<T> {
+    /// Encodes `value` into bytes
+    fn encode(&self, value: &T) -> Vec<u8>;
+
+    /// Decodes a value from bytes that were returned by [`encode`](Codec::encode)
+    fn decode(&self, bytes: &[u8]) -> T;
+}
+
+/// An entry of a [`CompressedUndoStack`]
+#[derive(Clone, Debug)]
+enum Slot<T> {
+    /// A decoded value
+    Hot(T),
+    /// An encoded value
+    Cold(Vec<u8>),
+}
+
+/// An [`UndoStack`] that keeps entries more than a given amount of steps away from the current
+/// value encoded with a [`Codec`], and transparently decodes them when they are undone or redone
+/// to.
+///
+/// This allows keeping a long history of large states in a fraction of the memory, at the cost of
+/// encoding every entry once as it leaves the hot window, and decoding it if it is returned to.
+/// ```rust
+/// # use history_stack::{Codec, CompressedUndoStack};
+/// struct Le;
+///
+/// impl Codec<u32> for Le {
+///     fn encode(&self, value: &u32) -> Vec<u8> {
+///         value.to_le_bytes().to_vec()
+///     }
+///
+///     fn decode(&self, bytes: &[u8]) -> u32 {
+///         u32::from_le_bytes(bytes.try_into().unwrap())
+///     }
+/// }
+///
+/// // keep only the current value and one step around it decoded
+/// let mut undo = CompressedUndoStack::new(0u32, Le, 1);
+///
+/// *undo.save() += 1;
+/// *undo.save() += 1;
+/// assert_eq!(undo.cold_len(), 1);
+///
+/// undo.undo().unwrap();
+/// assert_eq!(*undo.undo().unwrap(), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CompressedUndoStack<T, C> {
+    /// The underlying stack, the current value is always hot
+    stack: UndoStack<Slot<T>>,
+    /// The codec used to encode cold entries
+    codec: C,
+    /// The amount of steps from the current value that entries are kept decoded within
+    hot_window: usize,
+}
+
+impl<T, C: Codec<T>> CompressedUndoStack<T, C> {
+    /// Creates a new `CompressedUndoStack` with a starting value to act as the current value,
+    /// keeping entries up to `hot_window` steps away from the current value decoded and encoding
+    /// the rest with `codec`
+    pub fn new(start: T, codec: C, hot_window: usize) -> Self {
+        Self {
+            stack: UndoStack::new(Slot::Hot(start)),
+            codec,
+            hot_window,
+        }
+    }
+
+    /// Encodes the entry at `index` if it is hot, returning `true` if it was
+    fn freeze(&mut self, index: usize) -> bool {
+        if let Some(slot) = self.stack.history.get_mut(index) {
+            if let Slot::Hot(v) = slot {
+                *slot = Slot::Cold(self.codec.encode(v));
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Decodes the current entry if it is cold
+    fn thaw_current(&mut self) {
+        if let Slot::Cold(bytes) = self.stack.get() {
+            let value = self.codec.decode(bytes);
+            *self.stack.get_mut() = Slot::Hot(value);
+        }
+    }
+
+    /// Encodes the hot entries outside of the hot window, walking outward from each edge of the
+    /// window until an encoded entry or the end of history is reached.
+    ///
+    /// Entries outside the window are only ever decoded all at once by `decompress_all`, so hot
+    /// entries outside of it always directly border the window, and every walk usually stops after
+    /// a single entry.
+    fn cool_edges(&mut self) {
+        let current = self.stack.current;
+
+        if let Some(past) = current.checked_sub(self.hot_window.saturating_add(1)) {
+            for i in (0..=past).rev() {
+                if !self.freeze(i) {
+                    break;
+                }
+            }
+        }
+
+        for i in current.saturating_add(self.hot_window).saturating_add(1)..self.stack.len() {
+            if !self.freeze(i) {
+                break;
+            }
+        }
+    }
+
+    /// Returns the current value, which is always decoded
+    fn hot(&self) -> &T {
+        match self.stack.get() {
+            Slot::Hot(v) => v,
+            Slot::Cold(_) => unreachable!("CompressedUndoStack: current value was encoded"),
+        }
+    }
+
+    /// Returns the current value mutably, which is always decoded
+    fn hot_mut(&mut self) -> &mut T {
+        match self.stack.get_mut() {
+            Slot::Hot(v) => v,
+            Slot::Cold(_) => unreachable!("CompressedUndoStack: current value was encoded"),
+        }
+    }
+
+    /// Saves the current value to history, encoding the entry that leaves the hot window, see
+    /// [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        self.stack.save();
+        self.cool_edges();
+        self.hot_mut()
+    }
+
+    /// Pushes a new current value, encoding the entry that leaves the hot window, see
+    /// [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        self.stack.push(Slot::Hot(new_current));
+        self.cool_edges();
+        self.hot_mut()
+    }
+
+    /// Undoes to the previous state, decoding it if needed, see [`UndoStack::undo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        if self.stack.undo().is_ok() {
+            self.thaw_current();
+            self.cool_edges();
+            Ok(self.hot_mut())
+        } else {
+            Err(self.hot_mut())
+        }
+    }
+
+    /// Redoes to the next state, decoding it if needed, see [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        if self.stack.redo().is_ok() {
+            self.thaw_current();
+            self.cool_edges();
+            Ok(self.hot_mut())
+        } else {
+            Err(self.hot_mut())
+        }
+    }
+
+    /// Sets the amount of steps from the current value that entries are kept decoded within, and
+    /// encodes every entry outside of the new window. Entries inside the window that are already
+    /// encoded stay encoded until they become the current value.
+    pub fn set_hot_window(&mut self, hot_window: usize) {
+        self.hot_window = hot_window;
+
+        let current = self.stack.current;
+
+        for i in 0..self.stack.len() {
+            if current.abs_diff(i) > hot_window {
+                self.freeze(i);
+            }
+        }
+    }
+
+    /// Encodes every entry in history except the current value, regardless of the hot window.
+    ///
+    /// This is meant to be called right before the application is suspended or asked to free
+    /// memory. Encoded entries inside the hot window are decoded lazily as they become the current
+    /// value, or all at once with [`decompress_all`](CompressedUndoStack::decompress_all).
+    pub fn compress_all(&mut self) {
+        let current = self.stack.current;
+
+        for i in (0..self.stack.len()).filter(|&i| i != current) {
+            self.freeze(i);
+        }
+    }
+
+    /// Decodes every entry in history, regardless of the hot window. Every decoded entry outside
+    /// of the hot window is encoded again by the next save, push, undo or redo.
+    pub fn decompress_all(&mut self) {
+        for slot in &mut self.stack.history {
+            if let Slot::Cold(bytes) = slot {
+                *slot = Slot::Hot(self.codec.decode(bytes));
+            }
+        }
+    }
+
+    /// Returns the amount of steps from the current value that entries are kept decoded within
+    #[must_use]
+    pub fn hot_window(&self) -> usize {
+        self.hot_window
+    }
+
+    /// Returns the amount of entries in history that are encoded
+    #[must_use]
+    pub fn cold_len(&self) -> usize {
+        self.stack
+            .history
+            .iter()
+            .filter(|s| matches!(s, Slot::Cold(_)))
+            .count()
+    }
+
+    /// Returns the total amount of bytes held by encoded entries
+    #[must_use]
+    pub fn cold_bytes(&self) -> usize {
+        self.stack
+            .history
+            .iter()
+            .map(|s| match s {
+                Slot::Hot(_) => 0,
+                Slot::Cold(b) => b.len(),
+            })
+            .sum()
+    }
+
+    /// Returns the amount of values held in history, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Always returns `false`, as the hot window always holds the current value
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in history
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.stack.position()
+    }
+
+    /// Returns a reference to the codec
+    #[must_use]
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns a reference to the current value, which is never held compressed
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.hot()
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        self.hot_mut()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, C: Codec<T>> ops::Deref for CompressedUndoStack<T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, C: Codec<T>> ops::DerefMut for CompressedUndoStack<T, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn compressed_undo_stack() {
+    use alloc::vec;
+
+    struct Bytes;
+
+    impl Codec<u8> for Bytes {
+        fn encode(&self, value: &u8) -> Vec<u8> {
+            vec![*value]
+        }
+
+        fn decode(&self, bytes: &[u8]) -> u8 {
+            bytes[0]
+        }
+    }
+
+    let mut undo = CompressedUndoStack::new(0u8, Bytes, 0);
+
+    for _ in 0..4 {
+        *undo.save() += 1;
+    }
+
+    assert_eq!(undo.cold_len(), 4);
+    assert_eq!(undo.cold_bytes(), 4);
+
+    assert_eq!(*undo.undo().unwrap(), 3);
+    assert_eq!(*undo.undo().unwrap(), 2);
+    assert_eq!(undo.cold_len(), 4);
+    *undo.get_mut() += 10;
+
+    assert_eq!(*undo.redo().unwrap(), 3);
+    assert_eq!(*undo.undo().unwrap(), 12);
+
+    undo.set_hot_window(5);
+    assert_eq!(*undo.undo().unwrap(), 1);
+    assert_eq!(undo.cold_len(), 3);
+
+    undo.decompress_all();
+    assert_eq!(undo.cold_len(), 0);
+
+    undo.compress_all();
+    assert_eq!(undo.cold_len(), 4);
+    assert_eq!(*undo.redo().unwrap(), 12);
+    assert_eq!(undo.cold_len(), 3);
+
+    // entries decoded outside the window are encoded again on the next operation
+    undo.set_hot_window(1);
+    for _ in 0..3 {
+        *undo.save() += 1;
+    }
+    undo.decompress_all();
+    assert_eq!(undo.cold_len(), 0);
+
+    *undo.save() += 1;
+    assert_eq!(undo.len(), 7);
+    assert_eq!(undo.cold_len(), 5);
+
+    undo.undo().unwrap();
+    undo.undo().unwrap();
+    assert_eq!(undo.cold_len(), 5);
+    assert_eq!(*undo.redo().unwrap(), 15);
+    assert_eq!(undo.cold_len(), 5);
+
+    undo.decompress_all();
+    assert_eq!(*undo.undo().unwrap(), 14);
+    assert_eq!(undo.cold_len(), 4);
+}
+
+#[test]
+fn unbounded_hot_window() {
+    use alloc::vec;
+
+    struct Bytes;
+
+    impl Codec<u8> for Bytes {
+        fn encode(&self, value: &u8) -> Vec<u8> {
+            vec![*value]
+        }
+
+        fn decode(&self, bytes: &[u8]) -> u8 {
+            bytes[0]
+        }
+    }
+
+    // only compress on demand
+    let mut undo = CompressedUndoStack::new(0u8, Bytes, usize::MAX);
+
+    *undo.save() += 1;
+    undo.push(2);
+    assert_eq!(undo.cold_len(), 0);
+    assert_eq!(*undo.undo().unwrap(), 1);
+    assert_eq!(*undo.redo().unwrap(), 2);
+
+    undo.compress_all();
+    assert_eq!(undo.cold_len(), 2);
+    assert_eq!(*undo.undo().unwrap(), 1);
+    assert_eq!(undo.cold_len(), 1);
+}
@@ -0,0 +1,224 @@
+//! The [`Resolver`] trait, and an undo stack that only undoes local changes in collaborative
+//! editing
+
+use alloc::vec::Vec;
+#[cfg(feature = "deref")]
+use core::ops;
+
+/// A three way merge of two versions of a `T` that diverged from a common base.
+///
+/// [`resolve`](Resolver::resolve) must return `theirs` when `ours` is equal to `base` and `ours`
+/// when `theirs` is equal to `base`, and should keep the changes of both sides when they diverged.
+/// Any `Fn(&T, &T, &T) -> T` closure taking `base`, `ours` and `theirs` is also a `Resolver`.
+pub trait Resolver<T> {
+    /// Merges `ours` and `theirs`, which both derive from `base`
+    fn resolve(&self, base: &T, ours: &T, theirs: &T) -> T;
+}
+
+impl<T, F: Fn(&T, &T, &T) -> T> Resolver<T> for F {
+    fn resolve(&self, base: &T, ours: &T, theirs: &T) -> T {
+        self(base, ours, theirs)
+    }
+}
+
+/// A local change, the state before and after it was made
+#[derive(Clone, Debug)]
+struct Change<T> {
+    /// The state before the change
+    before: T,
+    /// The state after the change
+    after: T,
+}
+
+/// A history manager for collaborative editing, where changes are tagged as either local or
+/// remote, and undo and redo only revert or reapply local changes while keeping every remote
+/// change made since.
+///
+/// Reverting a local change that later remote changes were made on top of is done with a
+/// [`Resolver`], merging the state before the local change into the current state using the
+/// state after the local change as the base. When no remote change happened since, this simply
+/// restores the state before the change.
+/// ```rust
+/// # use history_stack::CollabUndoStack;
+/// // a document of two independent fields
+/// let merge = |base: &[u8; 2], ours: &[u8; 2], theirs: &[u8; 2]| {
+///     core::array::from_fn(|i| if ours[i] == base[i] { theirs[i] } else { ours[i] })
+/// };
+///
+/// let mut doc = CollabUndoStack::new([0, 0], merge);
+///
+/// doc.commit_local([1, 0]);
+/// doc.apply_remote([1, 5]);
+///
+/// // only the local edit is reverted, the remote edit is kept
+/// assert_eq!(*doc.undo_local().unwrap(), [0, 5]);
+/// assert_eq!(*doc.redo_local().unwrap(), [1, 5]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CollabUndoStack<T, R> {
+    /// The current state, including local and remote changes
+    current: T,
+    /// Local changes that can be undone, oldest first
+    undo: Vec<Change<T>>,
+    /// Local changes that were undone and can be redone, most recently undone last
+    redo: Vec<Change<T>>,
+    /// The merge used to revert and reapply local changes on top of remote ones
+    resolver: R,
+}
+
+impl<T, R: Resolver<T>> CollabUndoStack<T, R> {
+    /// Creates a new `CollabUndoStack` with a starting state and the resolver used to revert and
+    /// reapply local changes on top of remote ones
+    pub fn new(start: T, resolver: R) -> Self {
+        Self {
+            current: start,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            resolver,
+        }
+    }
+
+    /// Makes `new` the current state as a local change that can be undone, invalidating any local
+    /// changes that may be redone
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn commit_local(&mut self, new: T) -> &T
+    where
+        T: Clone,
+    {
+        let before = core::mem::replace(&mut self.current, new);
+
+        self.undo.push(Change {
+            before,
+            after: self.current.clone(),
+        });
+        self.redo.clear();
+
+        &self.current
+    }
+
+    /// Modifies the current state with `f` as a local change that can be undone, see
+    /// [`commit_local`](CollabUndoStack::commit_local)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn modify_local(&mut self, f: impl FnOnce(&mut T)) -> &T
+    where
+        T: Clone,
+    {
+        let mut new = self.current.clone();
+        f(&mut new);
+
+        self.commit_local(new)
+    }
+
+    /// Makes `new` the current state as a remote change, which local undo and redo never revert.
+    /// Local changes stay undoable and redoable.
+    pub fn apply_remote(&mut self, new: T) -> &T {
+        self.current = new;
+
+        &self.current
+    }
+
+    /// If there is a local change to undo, reverts it while keeping every remote change made
+    /// since and returns `Ok(&T)` of the new current state, otherwise returns `Err(&T)` of the
+    /// unchanged current state.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo_local(&mut self) -> Result<&T, &T> {
+        let Some(change) = self.undo.pop() else {
+            return Err(&self.current);
+        };
+
+        self.current = self
+            .resolver
+            .resolve(&change.after, &self.current, &change.before);
+        self.redo.push(change);
+
+        Ok(&self.current)
+    }
+
+    /// If there is an undone local change to redo, reapplies it while keeping every remote change
+    /// made since and returns `Ok(&T)` of the new current state, otherwise returns `Err(&T)` of the
+    /// unchanged current state.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo_local(&mut self) -> Result<&T, &T> {
+        let Some(change) = self.redo.pop() else {
+            return Err(&self.current);
+        };
+
+        self.current = self
+            .resolver
+            .resolve(&change.before, &self.current, &change.after);
+        self.undo.push(change);
+
+        Ok(&self.current)
+    }
+
+    /// Returns `true` if there is a local change to [`undo_local`](CollabUndoStack::undo_local)
+    #[must_use]
+    pub fn can_undo_local(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there is an undone local change to
+    /// [`redo_local`](CollabUndoStack::redo_local)
+    #[must_use]
+    pub fn can_redo_local(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Returns a reference to the resolver
+    #[must_use]
+    pub fn resolver(&self) -> &R {
+        &self.resolver
+    }
+
+    /// Returns a reference to the current value, including both local and remote changes
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.current
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, R: Resolver<T>> ops::Deref for CollabUndoStack<T, R> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[test]
+fn collab_undo_stack() {
+    let merge = |base: &[u8; 3], ours: &[u8; 3], theirs: &[u8; 3]| {
+        core::array::from_fn(|i| {
+            if ours[i] == base[i] {
+                theirs[i]
+            } else {
+                ours[i]
+            }
+        })
+    };
+
+    let mut doc = CollabUndoStack::new([0u8; 3], merge);
+
+    doc.commit_local([1, 0, 0]);
+    doc.apply_remote([1, 2, 0]);
+    doc.modify_local(|d| d[2] = 3);
+    doc.apply_remote([1, 4, 3]);
+
+    assert_eq!(*doc.undo_local().unwrap(), [1, 4, 0]);
+    assert_eq!(*doc.undo_local().unwrap(), [0, 4, 0]);
+    assert!(doc.undo_local().is_err());
+
+    doc.apply_remote([0, 4, 9]);
+
+    assert_eq!(*doc.redo_local().unwrap(), [1, 4, 9]);
+    assert!(doc.can_redo_local());
+
+    doc.commit_local([1, 1, 1]);
+    assert!(!doc.can_redo_local());
+    assert_eq!(*doc.undo_local().unwrap(), [1, 4, 9]);
+}
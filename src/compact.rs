@@ -0,0 +1,209 @@
+//! An undo stack that runs a user provided compaction hook over its history every few saves
+
+use alloc::vec::Vec;
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::UndoStack;
+
+/// An [`UndoStack`] that hands its whole history and the index of its current value to a
+/// compaction hook after every `interval` saves or pushes, so applications can implement their
+/// own trimming such as deduplication or thinning without a fixed policy.
+///
+/// The hook may rewrite history freely, but must leave at least one entry and the current index
+/// within it. If the hook changes the amount of entries in history the clean state of the stack
+/// is invalidated, as it can no longer be followed, see [`UndoStack::clean_state`].
+/// ```rust
+/// # use history_stack::CompactingUndoStack;
+/// // drop past entries equal to the entry before them, every 4 saves
+/// let mut undo = CompactingUndoStack::new(0u8, 4, |history: &mut Vec<u8>, current: &mut usize| {
+///     let before = history.len();
+///     let mut kept = history.drain(*current..).collect::<Vec<_>>();
+///     history.dedup();
+///     history.append(&mut kept);
+///     *current -= before - history.len();
+/// });
+///
+/// for i in [1, 1, 1, 2] {
+///     *undo.save() = i;
+/// }
+///
+/// assert_eq!(undo.stack().len(), 3);
+/// assert_eq!(undo.undo(), Ok(&mut 1));
+/// assert_eq!(undo.undo(), Ok(&mut 0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CompactingUndoStack<T, F> {
+    /// The underlying stack
+    stack: UndoStack<T>,
+    /// The compaction hook
+    hook: F,
+    /// The amount of saves and pushes between runs of the hook, 0 if it is only run manually
+    interval: usize,
+    /// The amount of saves and pushes since the hook last ran
+    saves: usize,
+}
+
+impl<T, F: FnMut(&mut Vec<T>, &mut usize)> CompactingUndoStack<T, F> {
+    /// Creates a new `CompactingUndoStack` with a starting value to act as the current value,
+    /// running `hook` after every `interval` saves or pushes. An interval of 0 never runs the hook
+    /// automatically.
+    pub fn new(start: T, interval: usize, hook: F) -> Self {
+        Self {
+            stack: UndoStack::new(start),
+            hook,
+            interval,
+            saves: 0,
+        }
+    }
+
+    /// Runs the compaction hook now, and restarts the count of saves until the next run
+    ///
+    /// # Panics
+    /// This will panic if the hook left history empty or the current index out of bounds
+    pub fn compact(&mut self) {
+        self.saves = 0;
+
+        let stack = &mut self.stack;
+        let len = stack.history.len();
+
+        (self.hook)(&mut stack.history, &mut stack.current);
+
+        assert!(
+            stack.current < stack.history.len(),
+            "CompactingUndoStack: compaction hook left the current index out of bounds"
+        );
+
+        if stack.history.len() != len {
+            stack.clean = None;
+        }
+
+        stack.bump_version();
+    }
+
+    /// Counts a save or push, running the hook if the interval was reached
+    fn saved(&mut self) {
+        self.saves += 1;
+
+        if self.saves == self.interval {
+            self.compact();
+        }
+    }
+
+    /// Saves the current value, running the hook if the interval was reached, see
+    /// [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed, or if the hook broke the invariants of history, see
+    /// [`compact`](CompactingUndoStack::compact)
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        self.stack.save();
+        self.saved();
+        self.stack.get_mut()
+    }
+
+    /// Pushes a new current value, running the hook if the interval was reached, see
+    /// [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed, or if the hook broke the invariants of history, see
+    /// [`compact`](CompactingUndoStack::compact)
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        self.stack.push(new_current);
+        self.saved();
+        self.stack.get_mut()
+    }
+
+    /// Undoes to the previous state without running the compaction hook, see
+    /// [`UndoStack::undo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        self.stack.undo()
+    }
+
+    /// Redoes to the next state without running the compaction hook, see [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        self.stack.redo()
+    }
+
+    /// Returns the amount of saves and pushes between runs of the hook
+    #[must_use]
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    /// Sets the amount of saves and pushes between runs of the hook, 0 disables automatic runs
+    pub fn set_interval(&mut self, interval: usize) {
+        self.interval = interval;
+        self.saves = 0;
+    }
+
+    /// Returns a reference to the underlying stack
+    #[must_use]
+    pub fn stack(&self) -> &UndoStack<T> {
+        &self.stack
+    }
+
+    /// Returns a reference to the current value, which the compaction hook never drops
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.stack.get()
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        self.stack.get_mut()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, F: FnMut(&mut Vec<T>, &mut usize)> ops::Deref for CompactingUndoStack<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, F: FnMut(&mut Vec<T>, &mut usize)> ops::DerefMut for CompactingUndoStack<T, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn compaction_hook() {
+    let mut runs = 0;
+    let mut undo =
+        CompactingUndoStack::new(0u8, 2, |history: &mut Vec<u8>, current: &mut usize| {
+            runs += 1;
+
+            // keep only the current value and everything after it
+            history.drain(..*current);
+            *current = 0;
+        });
+
+    undo.push(1);
+    assert_eq!(undo.stack().len(), 2);
+    undo.push(2);
+    assert_eq!(undo.stack().len(), 1);
+    assert!(undo.stack().is_dirty());
+
+    undo.set_interval(0);
+    undo.push(3);
+    undo.push(4);
+    assert_eq!(undo.stack().len(), 3);
+
+    undo.undo().unwrap();
+    undo.compact();
+    assert_eq!(undo.stack().len(), 2);
+    assert_eq!(undo.redo(), Ok(&mut 4));
+
+    drop(undo);
+    assert_eq!(runs, 2);
+}
@@ -0,0 +1,85 @@
+//! Comparisons between the current values of both stack types, and with their current value on
+//! the left hand side
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::cmp;
+
+use crate::{HistoryStack, HistoryStorage, UndoStack};
+
+impl<T: PartialEq, S, S2: HistoryStorage<T>> PartialEq<UndoStack<T, S2>> for HistoryStack<T, S> {
+    fn eq(&self, other: &UndoStack<T, S2>) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: PartialEq, S: HistoryStorage<T>, S2> PartialEq<HistoryStack<T, S2>> for UndoStack<T, S> {
+    fn eq(&self, other: &HistoryStack<T, S2>) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: PartialOrd, S, S2: HistoryStorage<T>> PartialOrd<UndoStack<T, S2>> for HistoryStack<T, S> {
+    fn partial_cmp(&self, other: &UndoStack<T, S2>) -> Option<cmp::Ordering> {
+        self.get().partial_cmp(other.get())
+    }
+}
+
+impl<T: PartialOrd, S: HistoryStorage<T>, S2> PartialOrd<HistoryStack<T, S2>> for UndoStack<T, S> {
+    fn partial_cmp(&self, other: &HistoryStack<T, S2>) -> Option<cmp::Ordering> {
+        self.get().partial_cmp(other.get())
+    }
+}
+
+/// Implements `PartialEq` and `PartialOrd` with both stack types on the right hand side for each
+/// of the given types, as the orphan rules forbid doing so for every `T` at once
+macro_rules! reverse_cmp {
+    ($($ty:ty),*) => {$(
+        impl<S> PartialEq<HistoryStack<$ty, S>> for $ty {
+            fn eq(&self, other: &HistoryStack<$ty, S>) -> bool {
+                *self == *other.get()
+            }
+        }
+
+        impl<S: HistoryStorage<$ty>> PartialEq<UndoStack<$ty, S>> for $ty {
+            fn eq(&self, other: &UndoStack<$ty, S>) -> bool {
+                *self == *other.get()
+            }
+        }
+
+        impl<S> PartialOrd<HistoryStack<$ty, S>> for $ty {
+            fn partial_cmp(&self, other: &HistoryStack<$ty, S>) -> Option<cmp::Ordering> {
+                self.partial_cmp(other.get())
+            }
+        }
+
+        impl<S: HistoryStorage<$ty>> PartialOrd<UndoStack<$ty, S>> for $ty {
+            fn partial_cmp(&self, other: &UndoStack<$ty, S>) -> Option<cmp::Ordering> {
+                self.partial_cmp(other.get())
+            }
+        }
+    )*};
+}
+
+reverse_cmp!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char);
+
+#[cfg(feature = "alloc")]
+reverse_cmp!(String);
+
+#[cfg(feature = "alloc")]
+#[test]
+fn symmetric_comparisons() {
+    let mut undo = UndoStack::new(1u8);
+    let history = HistoryStack::new(1u8);
+
+    assert_eq!(1, undo);
+    assert_eq!(history, undo);
+    assert_eq!(undo, history);
+
+    *undo.save() = 2;
+    assert!(history < undo);
+    assert!(undo > history);
+    assert!(3 > undo);
+
+    assert_eq!(String::from("a"), HistoryStack::new(String::from("a")));
+}
@@ -0,0 +1,316 @@
+//! A single writer, many reader history where the writer publishes a copy of the timeline under
+//! a lock after every operation, and readers take consistent snapshots of it
+
+use alloc::{sync::Arc, vec::Vec};
+#[cfg(feature = "deref")]
+use core::ops;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock};
+
+use crate::UndoStack;
+
+/// Creates a new concurrent history with a starting value to act as the current value, returning
+/// the single [`HistoryWriter`] and a [`HistoryReader`] that may be cloned and sent to other
+/// threads.
+///
+/// This is a locked, copy-on-publish design: the writer builds a new [`HistorySnapshot`] after
+/// every operation and swaps it in under an [`RwLock`]. Snapshots share their entries with the
+/// writer through `Arc`, so publishing copies one pointer per entry of history, which makes every
+/// operation O(n) in the length of history, but never clones a `T`. A `T` is only cloned by the
+/// writer when it mutates a value that a snapshot still holds.
+///
+/// The lock is only ever held for the duration of an `Arc` clone or pointer replacement, never
+/// while the writer clones values or builds a snapshot, but readers and the writer do briefly
+/// block each other on it. Readers that only want to know whether anything changed can poll
+/// [`HistoryReader::epoch`], which is a single atomic load.
+/// ```rust
+/// # use history_stack::locked_history;
+/// let (mut writer, reader) = locked_history(1u32);
+///
+/// let render = std::thread::spawn(move || reader.snapshot());
+///
+/// writer.save();
+/// writer.modify(|v| *v += 1);
+///
+/// // the render thread either saw the initial or a later timeline, never a half written one
+/// let snapshot = render.join().unwrap();
+/// assert!(snapshot.len() <= 2);
+/// ```
+pub fn locked_history<T>(start: T) -> (HistoryWriter<T>, HistoryReader<T>) {
+    let stack = UndoStack::new(Arc::new(start));
+
+    let shared = Arc::new(Published {
+        epoch: AtomicUsize::new(0),
+        snapshot: RwLock::new(Arc::new(HistorySnapshot::of(&stack, 0))),
+    });
+
+    (
+        HistoryWriter {
+            stack,
+            epoch: 0,
+            shared: Arc::clone(&shared),
+        },
+        HistoryReader { shared },
+    )
+}
+
+/// The state shared between a writer and its readers
+#[derive(Debug)]
+struct Published<T> {
+    /// The epoch of the latest published snapshot
+    epoch: AtomicUsize,
+    /// The latest published snapshot
+    snapshot: RwLock<Arc<HistorySnapshot<T>>>,
+}
+
+/// An immutable, consistent view of the timeline of a concurrent history at one point in time,
+/// obtained from [`HistoryReader::snapshot`]
+#[derive(Debug)]
+pub struct HistorySnapshot<T> {
+    /// All entries, oldest first
+    entries: Vec<Arc<T>>,
+    /// Index of the current value within entries
+    current: usize,
+    /// The epoch this snapshot was published at
+    epoch: usize,
+}
+
+impl<T> HistorySnapshot<T> {
+    /// Creates a snapshot of the writers stack
+    fn of(stack: &UndoStack<Arc<T>>, epoch: usize) -> Self {
+        Self {
+            entries: stack.history.clone(),
+            current: stack.current,
+            epoch,
+        }
+    }
+
+    /// Returns the current value at the time of the snapshot
+    #[must_use]
+    pub fn current(&self) -> &T {
+        &self.entries[self.current]
+    }
+
+    /// Returns the index of the current value within the timeline
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the amount of entries in the timeline, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Always returns `false`, as a timeline always holds at least its current value
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the entry at `index` in the timeline, oldest first
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.entries.get(index).map(|v| &**v)
+    }
+
+    /// Returns an iterator over all entries of the timeline, oldest first
+    #[must_use]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.entries.iter().map(|v| &**v)
+    }
+
+    /// Returns the epoch this snapshot was published at. Every published snapshot has a
+    /// different epoch than the one before it, epochs wrap around after `usize::MAX` operations so
+    /// they should only be compared for equality.
+    #[must_use]
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+}
+
+/// A handle that can take snapshots of a concurrent history, created by [`locked_history`].
+/// Cloning a reader creates another handle to the same history.
+#[derive(Debug)]
+pub struct HistoryReader<T> {
+    /// The state published by the writer
+    shared: Arc<Published<T>>,
+}
+
+impl<T> Clone for HistoryReader<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> HistoryReader<T> {
+    /// Returns the latest published snapshot of the timeline
+    #[must_use]
+    pub fn snapshot(&self) -> Arc<HistorySnapshot<T>> {
+        Arc::clone(
+            &self
+                .shared
+                .snapshot
+                .read()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    /// Returns the latest published snapshot if its epoch is different from `seen`, this can be
+    /// used to only look at the timeline after the writer changes it
+    #[must_use]
+    pub fn snapshot_since(&self, seen: usize) -> Option<Arc<HistorySnapshot<T>>> {
+        if self.epoch() == seen {
+            None
+        } else {
+            Some(self.snapshot())
+        }
+    }
+
+    /// Returns the epoch of the latest published snapshot without taking any lock
+    #[must_use]
+    pub fn epoch(&self) -> usize {
+        self.shared.epoch.load(Ordering::Acquire)
+    }
+}
+
+/// The writing half of a concurrent history, created by [`locked_history`]. Every operation
+/// publishes a new snapshot to all [`HistoryReader`]s.
+///
+/// The writer only hands out shared references to its current value, mutations go through
+/// [`modify`](HistoryWriter::modify) so that they are always published.
+#[derive(Debug)]
+pub struct HistoryWriter<T> {
+    /// The writers private stack, entries are shared with published snapshots
+    stack: UndoStack<Arc<T>>,
+    /// The epoch of the last published snapshot
+    epoch: usize,
+    /// The state shared with readers
+    shared: Arc<Published<T>>,
+}
+
+impl<T> HistoryWriter<T> {
+    /// Publishes a snapshot of the current stack to readers
+    fn publish(&mut self) {
+        self.epoch = self.epoch.wrapping_add(1);
+
+        let snapshot = Arc::new(HistorySnapshot::of(&self.stack, self.epoch));
+
+        let old = core::mem::replace(
+            &mut *self
+                .shared
+                .snapshot
+                .write()
+                .unwrap_or_else(PoisonError::into_inner),
+            snapshot,
+        );
+
+        self.shared.epoch.store(self.epoch, Ordering::Release);
+
+        // drop the old snapshot only after the lock is released
+        drop(old);
+    }
+
+    /// Saves the current value and publishes, see [`UndoStack::save`]. This never clones a `T`.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) {
+        self.stack.save();
+        self.publish();
+    }
+
+    /// Pushes a new current value and publishes, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) {
+        self.stack.push(Arc::new(new_current));
+        self.publish();
+    }
+
+    /// Undoes to the previous state and publishes if there was one, returning `true` if the
+    /// position changed, see [`UndoStack::undo`]
+    pub fn undo(&mut self) -> bool {
+        let moved = self.stack.undo().is_ok();
+
+        if moved {
+            self.publish();
+        }
+
+        moved
+    }
+
+    /// Redoes to the next state and publishes if there was one, returning `true` if the position
+    /// changed, see [`UndoStack::redo`]
+    pub fn redo(&mut self) -> bool {
+        let moved = self.stack.redo().is_ok();
+
+        if moved {
+            self.publish();
+        }
+
+        moved
+    }
+
+    /// Mutates the current value with `f` and publishes. The current value is cloned first if a
+    /// snapshot or an older entry still shares it.
+    pub fn modify<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R
+    where
+        T: Clone,
+    {
+        let out = f(Arc::make_mut(self.stack.get_mut()));
+        self.publish();
+        out
+    }
+
+    /// Returns a new reader of this history
+    #[must_use]
+    pub fn reader(&self) -> HistoryReader<T> {
+        HistoryReader {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Returns a reference to the current value as the writer sees it, which readers only see
+    /// once published
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.stack.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::Deref for HistoryWriter<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[test]
+fn concurrent_snapshots() {
+    let (mut writer, reader) = locked_history(0u8);
+
+    let before = reader.snapshot();
+
+    writer.save();
+    writer.modify(|v| *v = 5);
+
+    assert_eq!(*before.current(), 0);
+    assert_eq!(before.len(), 1);
+
+    let after = reader.snapshot_since(before.epoch()).unwrap();
+    assert_eq!(*after.current(), 5);
+    assert_eq!(after.iter().copied().collect::<Vec<_>>(), [0, 5]);
+    assert!(reader.snapshot_since(after.epoch()).is_none());
+
+    assert!(writer.undo());
+    assert_eq!(reader.snapshot().position(), 0);
+    assert!(!writer.undo());
+    assert_eq!(*writer.get(), 0);
+}
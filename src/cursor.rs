@@ -0,0 +1,143 @@
+//! A read only cursor for navigating the history of an undo stack
+
+use alloc::vec::Vec;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// A cursor over the history of an [`UndoStack`], created by [`UndoStack::cursor`].
+///
+/// The cursor starts at the current value and can be moved backward and forward over history
+/// without changing the real position of the stack, which makes it suitable for previewing states
+/// before undoing or redoing to them.
+/// ```rust
+/// # use history_stack::UndoStack;
+/// let mut undo = UndoStack::new(0u8);
+/// *undo.save() = 1;
+/// *undo.save() = 2;
+///
+/// let mut cursor = undo.cursor();
+/// assert_eq!(cursor.back(), Some(&1));
+/// assert_eq!(cursor.back(), Some(&0));
+/// assert_eq!(cursor.back(), None);
+/// assert_eq!(cursor.offset(), -2);
+///
+/// // the stack never moved
+/// assert_eq!(undo, 2);
+/// ```
+#[derive(Debug)]
+pub struct HistoryCursor<'a, T, S = Vec<T>> {
+    /// The stack being navigated
+    stack: &'a UndoStack<T, S>,
+    /// The index in history the cursor points at
+    index: usize,
+}
+
+impl<T, S> Clone for HistoryCursor<'_, T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, S> Copy for HistoryCursor<'_, T, S> {}
+
+impl<'a, T, S: HistoryStorage<T>> HistoryCursor<'a, T, S> {
+    /// Returns the entry the cursor points at
+    #[must_use]
+    pub fn peek(&self) -> &'a T {
+        self.stack.at(self.index)
+    }
+
+    /// Returns the entry before the one the cursor points at without moving it, or `None` if the
+    /// cursor is at the oldest entry
+    #[must_use]
+    pub fn peek_back(&self) -> Option<&'a T> {
+        self.index.checked_sub(1).map(|i| self.stack.at(i))
+    }
+
+    /// Returns the entry after the one the cursor points at without moving it, or `None` if the
+    /// cursor is at the newest entry
+    #[must_use]
+    pub fn peek_forward(&self) -> Option<&'a T> {
+        self.stack.history.get(self.index + 1)
+    }
+
+    /// Moves the cursor one entry back and returns the entry it now points at, or returns `None`
+    /// and leaves the cursor as is if it was at the oldest entry
+    pub fn back(&mut self) -> Option<&'a T> {
+        let value = self.peek_back()?;
+        self.index -= 1;
+
+        Some(value)
+    }
+
+    /// Moves the cursor one entry forward and returns the entry it now points at, or returns
+    /// `None` and leaves the cursor as is if it was at the newest entry
+    pub fn forward(&mut self) -> Option<&'a T> {
+        let value = self.peek_forward()?;
+        self.index += 1;
+
+        Some(value)
+    }
+
+    /// Moves the cursor back to the current value of the stack
+    pub fn reset(&mut self) {
+        self.index = self.stack.current;
+    }
+
+    /// Returns the index in history the cursor points at
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the offset of the cursor from the current value of the stack, negative offsets are
+    /// in the past, and positive ones in the future that can be redone to.
+    ///
+    /// Histories of zero sized types or custom storages can hold more than `isize::MAX` entries,
+    /// offsets that do not fit in an `isize` saturate at `isize::MIN` or `isize::MAX`.
+    #[must_use]
+    pub fn offset(&self) -> isize {
+        let (index, current) = (self.index, self.stack.current);
+
+        if index >= current {
+            isize::try_from(index - current).unwrap_or(isize::MAX)
+        } else {
+            isize::try_from(current - index).map_or(isize::MIN, |d| -d)
+        }
+    }
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns a [`HistoryCursor`] starting at the current value, which can navigate history
+    /// without changing the position of the stack
+    #[must_use]
+    pub fn cursor(&self) -> HistoryCursor<'_, T, S> {
+        HistoryCursor {
+            stack: self,
+            index: self.current,
+        }
+    }
+}
+
+#[test]
+fn history_cursor() {
+    let mut undo = UndoStack::new(0u8);
+    *undo.save() = 1;
+    *undo.save() = 2;
+    undo.undo().unwrap();
+
+    let mut cursor = undo.cursor();
+    assert_eq!(cursor.peek(), &1);
+    assert_eq!(cursor.offset(), 0);
+
+    assert_eq!(cursor.forward(), Some(&2));
+    assert_eq!(cursor.forward(), None);
+    assert_eq!(cursor.offset(), 1);
+    assert_eq!(cursor.peek_back(), Some(&1));
+
+    let saved = cursor;
+    cursor.reset();
+    assert_eq!(cursor.back(), Some(&0));
+    assert_eq!(cursor.position(), 0);
+    assert_eq!(saved.position(), 2);
+}
@@ -0,0 +1,236 @@
+//! The [`Diff`] trait and [`Projections`], for describing the changes between states in history
+
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// A value that can describe the changes from an older version of itself to a newer one, such as
+/// an edit script for text or a list of changed fields for a struct.
+pub trait Diff {
+    /// The description of the changes between two values
+    type Output;
+
+    /// Describes the changes from `self` to `newer`
+    fn diff(&self, newer: &Self) -> Self::Output;
+}
+
+/// A [`Diff`] whose output can be applied to the older value to recreate the newer one, used by
+/// [`AdaptiveUndoStack`](crate::AdaptiveUndoStack) to store entries as deltas.
+///
+/// For any `old` and `new`, applying `old.diff(&new)` to a clone of `old` must produce a value
+/// equal to `new`.
+pub trait Patch: Diff {
+    /// Applies `diff`, which was created from a value equal to `self`, turning `self` into the
+    /// newer value it was created against
+    fn apply(&mut self, diff: &Self::Output);
+}
+
+impl<T: Diff, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns an iterator over the [`Diff`] between every pair of consecutive entries in history,
+    /// oldest first. The iterator yields one item less than the length of history, the last item
+    /// being the diff to the newest entry, which may be past the current value.
+    /// ```rust
+    /// # use history_stack::{Diff, UndoStack};
+    /// struct Counter(i32);
+    ///
+    /// impl Diff for Counter {
+    ///     type Output = i32;
+    ///
+    ///     fn diff(&self, newer: &Self) -> i32 {
+    ///         newer.0 - self.0
+    ///     }
+    /// }
+    ///
+    /// let mut undo = UndoStack::new(Counter(0));
+    /// undo.push(Counter(5));
+    /// undo.push(Counter(3));
+    ///
+    /// assert_eq!(undo.iter_diffs().collect::<Vec<_>>(), [5, -2]);
+    /// ```
+    pub fn iter_diffs(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = T::Output> + ExactSizeIterator + '_ {
+        (1..self.len()).map(move |i| self.at(i - 1).diff(self.at(i)))
+    }
+}
+
+/// A list of named field projections of a `T`, used by [`UndoStack::changed_fields`] to report
+/// which fields changed between two states by comparing each projected field with `PartialEq`.
+/// ```rust
+/// # use history_stack::{Projections, UndoStack};
+/// struct Doc {
+///     title: String,
+///     body: String,
+///     views: u32,
+/// }
+///
+/// let fields = Projections::new()
+///     .field("title", |d: &Doc| &d.title)
+///     .field("body", |d: &Doc| &d.body);
+///
+/// let mut undo = UndoStack::new(Doc {
+///     title: String::from("draft"),
+///     body: String::new(),
+///     views: 0,
+/// });
+///
+/// let doc = undo.push(Doc {
+///     title: String::from("draft"),
+///     body: String::from("hello"),
+///     views: 0,
+/// });
+/// doc.views += 1;
+///
+/// // review changes since the first checkpoint, views are not tracked
+/// assert_eq!(undo.changed_fields(0, &fields).unwrap(), ["body"]);
+/// ```
+pub struct Projections<T, K> {
+    /// Every field, along with a function returning `true` if it differs between two values
+    fields: Vec<(K, Differs<T>)>,
+}
+
+/// A function returning `true` if a field differs between two values
+type Differs<T> = Box<dyn Fn(&T, &T) -> bool>;
+
+impl<T, K: fmt::Debug> fmt::Debug for Projections<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.fields.iter().map(|(k, _)| k))
+            .finish()
+    }
+}
+
+impl<T, K> Default for Projections<T, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, K> Projections<T, K> {
+    /// Creates an empty list of projections
+    #[must_use]
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Adds a field named `key`, projected out of a `T` by `project`
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn field<F: PartialEq + ?Sized>(
+        mut self,
+        key: K,
+        project: impl Fn(&T) -> &F + 'static,
+    ) -> Self {
+        self.fields
+            .push((key, Box::new(move |a, b| project(a) != project(b))));
+        self
+    }
+
+    /// Returns the keys of every field that differs between `old` and `new`, in the order the
+    /// fields were added
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn changed(&self, old: &T, new: &T) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.fields
+            .iter()
+            .filter(|(_, differs)| differs(old, new))
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns the [`Diff`] from the entry at `index` in history to the current value, or `None`
+    /// if `index` is out of bounds. When `index` is past the current value this describes what
+    /// undoing back from it changed.
+    #[must_use]
+    pub fn diff_against(&self, index: usize) -> Option<T::Output>
+    where
+        T: Diff,
+    {
+        self.history.get(index).map(|old| old.diff(self.get()))
+    }
+
+    /// Returns the keys of every field of `fields` that differs between the entry at `index` in
+    /// history and the current value, or `None` if `index` is out of bounds, see [`Projections`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn changed_fields<K: Clone>(
+        &self,
+        index: usize,
+        fields: &Projections<T, K>,
+    ) -> Option<Vec<K>> {
+        self.history
+            .get(index)
+            .map(|old| fields.changed(old, self.inner()))
+    }
+}
+
+#[test]
+fn iter_diffs() {
+    use alloc::vec::Vec;
+
+    #[derive(Debug)]
+    struct Level(u8);
+
+    impl Diff for Level {
+        type Output = bool;
+
+        fn diff(&self, newer: &Self) -> bool {
+            newer.0 > self.0
+        }
+    }
+
+    let mut undo = UndoStack::new(Level(1));
+    assert_eq!(undo.iter_diffs().len(), 0);
+
+    for i in [3, 2, 4] {
+        undo.push(Level(i));
+    }
+    undo.undo().unwrap();
+
+    assert_eq!(undo.iter_diffs().collect::<Vec<_>>(), [true, false, true]);
+    assert_eq!(undo.iter_diffs().next_back(), Some(true));
+}
+
+#[test]
+fn diff_against() {
+    #[derive(Clone, Debug)]
+    struct Point {
+        x: i8,
+        y: i8,
+    }
+
+    impl Diff for Point {
+        type Output = (i8, i8);
+
+        fn diff(&self, newer: &Self) -> (i8, i8) {
+            (newer.x - self.x, newer.y - self.y)
+        }
+    }
+
+    let fields = Projections::new()
+        .field('x', |p: &Point| &p.x)
+        .field('y', |p: &Point| &p.y);
+
+    let mut undo = UndoStack::new(Point { x: 0, y: 0 });
+    undo.save().x = 3;
+    undo.save().y = -1;
+
+    assert_eq!(undo.diff_against(0), Some((3, -1)));
+    assert_eq!(undo.diff_against(3), None);
+    assert_eq!(undo.changed_fields(1, &fields).unwrap(), ['y']);
+
+    undo.undo().unwrap();
+    assert_eq!(undo.diff_against(2), Some((0, 1)));
+    assert!(undo.changed_fields(1, &fields).unwrap().is_empty());
+}
@@ -0,0 +1,218 @@
+//! The object safe [`HistoryOps`] trait, for driving undo managers without knowing their values
+
+use alloc::{boxed::Box, vec::Vec};
+
+#[cfg(feature = "std")]
+use crate::SharedUndoStack;
+use crate::{
+    AdaptiveUndoStack, AutoSaver, Clock, CoalescingUndoStack, Codec, CompactingUndoStack,
+    CompressedUndoStack, GenerationalUndoStack, HistoryHook, HistoryStorage, HookedUndoStack,
+    LazyUndoStack, OpLog, Patch, PersistedUndoStack, SyncedUndoStack, TimedUndoStack, UndoStack,
+};
+
+/// The operations shared by every undo manager of this crate that do not involve the values they
+/// hold, so that managers of different value types can be held together as
+/// `Box<dyn HistoryOps>`.
+///
+/// This allows an application with many independent histories to implement a global "undo the
+/// last thing anywhere" command.
+/// ```rust
+/// # use history_stack::{HistoryOps, LazyUndoStack, UndoStack};
+/// let mut text = UndoStack::new(String::from("hello"));
+/// let mut zoom = LazyUndoStack::new(1.0f32);
+///
+/// text.save().push_str(" world");
+/// zoom.save();
+/// *zoom.get_mut() = 2.0;
+///
+/// let mut edited: Vec<&mut dyn HistoryOps> = vec![&mut text, &mut zoom];
+///
+/// // undo the last thing anywhere
+/// while let Some(last) = edited.pop() {
+///     if last.undo() {
+///         break;
+///     }
+/// }
+///
+/// assert_eq!(text.get(), "hello world");
+/// assert_eq!(*zoom.get(), 1.0);
+/// ```
+pub trait HistoryOps {
+    /// Saves the current value to history, invalidating any data that may be used to redo
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    fn save(&mut self);
+
+    /// Undoes to the previous state, returning `true` if there was a previous state to undo to
+    fn undo(&mut self) -> bool;
+
+    /// Redoes to the next state, returning `true` if there was a future state to redo to
+    fn redo(&mut self) -> bool;
+
+    /// Returns `true` if there is a previous state to undo to
+    fn can_undo(&self) -> bool;
+
+    /// Returns `true` if there is a future state to redo to
+    fn can_redo(&self) -> bool;
+}
+
+impl<H: HistoryOps + ?Sized> HistoryOps for &mut H {
+    fn save(&mut self) {
+        (**self).save();
+    }
+
+    fn undo(&mut self) -> bool {
+        (**self).undo()
+    }
+
+    fn redo(&mut self) -> bool {
+        (**self).redo()
+    }
+
+    fn can_undo(&self) -> bool {
+        (**self).can_undo()
+    }
+
+    fn can_redo(&self) -> bool {
+        (**self).can_redo()
+    }
+}
+
+impl<H: HistoryOps + ?Sized> HistoryOps for Box<H> {
+    fn save(&mut self) {
+        (**self).save();
+    }
+
+    fn undo(&mut self) -> bool {
+        (**self).undo()
+    }
+
+    fn redo(&mut self) -> bool {
+        (**self).redo()
+    }
+
+    fn can_undo(&self) -> bool {
+        (**self).can_undo()
+    }
+
+    fn can_redo(&self) -> bool {
+        (**self).can_redo()
+    }
+}
+
+/// Implements [`HistoryOps`] for a type whose inherent `save`, `undo` and `redo` methods have the
+/// usual shape, given expressions for `can_undo` and `can_redo`
+macro_rules! history_ops {
+    ($(impl [$($generics:tt)*] for $ty:ty { $s:ident => $can_undo:expr, $can_redo:expr })*) => {$(
+        impl<$($generics)*> HistoryOps for $ty {
+            fn save(&mut self) {
+                <$ty>::save(self);
+            }
+
+            fn undo(&mut self) -> bool {
+                <$ty>::undo(self).is_ok()
+            }
+
+            fn redo(&mut self) -> bool {
+                <$ty>::redo(self).is_ok()
+            }
+
+            fn can_undo(&self) -> bool {
+                let $s = self;
+                $can_undo
+            }
+
+            fn can_redo(&self) -> bool {
+                let $s = self;
+                $can_redo
+            }
+        }
+    )*};
+}
+
+history_ops! {
+    impl [T: Clone, S: HistoryStorage<T>] for UndoStack<T, S> {
+        s => s.can_undo(), s.can_redo()
+    }
+    impl [T] for LazyUndoStack<T> {
+        s => s.can_undo(), s.can_redo()
+    }
+    impl [T: Clone, C: Clock] for TimedUndoStack<T, C> {
+        s => s.position() != 0, s.position() + 1 != s.len()
+    }
+    impl [T: Clone, K] for CoalescingUndoStack<T, K> {
+        s => s.stack().can_undo(), s.stack().can_redo()
+    }
+    impl [T: Clone, C: Codec<T>] for CompressedUndoStack<T, C> {
+        s => s.position() != 0, s.position() + 1 != s.len()
+    }
+    impl [T: Clone, H: HistoryHook<T>] for HookedUndoStack<T, H> {
+        s => s.stack().can_undo(), s.stack().can_redo()
+    }
+    impl [T: Clone] for SyncedUndoStack<T> {
+        s => s.stack().can_undo(), s.stack().can_redo()
+    }
+    impl [T: Clone] for OpLog<T> {
+        s => s.stack().can_undo(), s.stack().can_redo()
+    }
+    impl [T: Clone, F: FnMut(&mut Vec<T>, &mut usize)] for CompactingUndoStack<T, F> {
+        s => s.stack().can_undo(), s.stack().can_redo()
+    }
+    impl [T: Clone] for GenerationalUndoStack<T> {
+        s => s.position() != 0, s.position() + 1 != s.len()
+    }
+    impl [T: Clone, C: Clock] for AutoSaver<T, C> {
+        s => s.stack().can_undo(), s.stack().can_redo()
+    }
+    impl [T: Patch + Clone] for AdaptiveUndoStack<T> {
+        s => s.position() != 0, s.position() + 1 != s.len()
+    }
+    impl [T: Clone, P] for PersistedUndoStack<T, P> {
+        s => s.position() != 0, s.position() + 1 != s.len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> HistoryOps for SharedUndoStack<T> {
+    fn save(&mut self) {
+        SharedUndoStack::save(self);
+    }
+
+    fn undo(&mut self) -> bool {
+        SharedUndoStack::undo(self)
+    }
+
+    fn redo(&mut self) -> bool {
+        SharedUndoStack::redo(self)
+    }
+
+    fn can_undo(&self) -> bool {
+        self.read_stack(UndoStack::can_undo)
+    }
+
+    fn can_redo(&self) -> bool {
+        self.read_stack(UndoStack::can_redo)
+    }
+}
+
+#[test]
+fn history_ops() {
+    let mut stacks: Vec<Box<dyn HistoryOps>> = alloc::vec![
+        Box::new(UndoStack::new(0u8)),
+        Box::new(LazyUndoStack::new("a")),
+        Box::new(CoalescingUndoStack::<_, ()>::new(0u8)),
+        Box::new(OpLog::new(0i32)),
+    ];
+
+    for s in &mut stacks {
+        assert!(!s.can_undo());
+        s.save();
+        assert!(s.can_undo());
+        assert!(s.undo());
+        assert!(!s.undo());
+        assert!(s.can_redo());
+        assert!(s.redo());
+        assert!(!s.can_redo());
+    }
+}
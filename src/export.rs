@@ -0,0 +1,286 @@
+//! A stable, versioned representation of an [`UndoStack`] for persisting history across runs
+
+use alloc::vec::Vec;
+use core::{fmt, marker::PhantomData};
+
+use crate::{HistoryStorage, UndoStack};
+
+/// A portable snapshot of an [`UndoStack`], holding every entry of its history and the position of
+/// its current value, produced by [`UndoStack::export`] and read back by [`UndoStack::import`].
+///
+/// The meaning of every field is part of the stability guarantees of the crate, and every change
+/// to it bumps [`Exported::VERSION`]. Imports accept every version from
+/// [`Exported::OLDEST_VERSION`] to [`Exported::VERSION`] inclusive, and reject the rest. This type
+/// is `#[non_exhaustive]` so that fields can be added in later versions without breaking callers,
+/// build one with [`Exported::new`] instead of a struct expression.
+/// ```rust
+/// # use history_stack::UndoStack;
+/// let mut undo = UndoStack::new(1u8);
+/// *undo.save() += 1;
+/// undo.undo().unwrap();
+///
+/// let exported = undo.export();
+/// assert_eq!(exported.entries, [1, 2]);
+///
+/// let imported = UndoStack::import(exported).unwrap();
+/// assert_eq!(imported, undo);
+/// assert!(imported.can_redo());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Exported<T> {
+    /// The format version this export was created with
+    pub version: u32,
+    /// The index of the current value in `entries`
+    pub current: usize,
+    /// Every entry of history, oldest first
+    pub entries: Vec<T>,
+}
+
+impl<T> Exported<T> {
+    /// The format version written by this release of the crate
+    pub const VERSION: u32 = 1;
+
+    /// The oldest format version this release of the crate can read
+    pub const OLDEST_VERSION: u32 = 1;
+
+    /// Creates an export of the current format version holding `entries` oldest first, with the
+    /// current value at index `current`, such as when reading it back from a custom format
+    #[must_use]
+    pub fn new(current: usize, entries: Vec<T>) -> Self {
+        Self {
+            version: Self::VERSION,
+            current,
+            entries,
+        }
+    }
+}
+
+/// The error returned by [`UndoStack::import`] when an [`Exported`] history cannot be read back
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ImportError {
+    /// The export was created with a format version this release of the crate cannot read, either
+    /// by a newer release or not by this crate at all
+    UnsupportedVersion(u32),
+    /// The export held no entries, but a history always holds its current value
+    Empty,
+    /// The position of the current value was not within the entries
+    CurrentOutOfBounds,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(v) => write!(
+                f,
+                "unsupported history export version {v}, the supported versions are {} through {}",
+                Exported::<()>::OLDEST_VERSION,
+                Exported::<()>::VERSION
+            ),
+            Self::Empty => f.write_str("history export held no entries"),
+            Self::CurrentOutOfBounds => {
+                f.write_str("history export current position was out of bounds")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ImportError {}
+
+impl From<FromPartsError> for ImportError {
+    fn from(err: FromPartsError) -> Self {
+        match err {
+            FromPartsError::Empty => Self::Empty,
+            FromPartsError::CurrentOutOfBounds => Self::CurrentOutOfBounds,
+        }
+    }
+}
+
+/// The error returned by [`UndoStack::from_parts`] when its parts do not form a valid history
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FromPartsError {
+    /// The history held no entries, but a history always holds its current value
+    Empty,
+    /// The position of the current value was not within the history
+    CurrentOutOfBounds,
+}
+
+impl fmt::Display for FromPartsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("history held no entries"),
+            Self::CurrentOutOfBounds => f.write_str("current position was out of bounds"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromPartsError {}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Exports the whole history and the position of the current value into a versioned
+    /// [`Exported`] snapshot, which can be persisted and later read back with
+    /// [`import`](UndoStack::import)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn export(&self) -> Exported<T>
+    where
+        T: Clone,
+    {
+        Exported {
+            version: Exported::<T>::VERSION,
+            current: self.current,
+            entries: (0..self.len()).map(|i| self.at(i).clone()).collect(),
+        }
+    }
+
+    /// Rebuilds an `UndoStack` from an [`Exported`] snapshot, using `storage` to hold its history.
+    /// Any contents of `storage` are dropped.
+    ///
+    /// # Errors
+    /// If the export version is not supported by this release of the crate, the export held no
+    /// entries, or its current position was out of bounds
+    pub fn import_with_storage(exported: Exported<T>, mut storage: S) -> Result<Self, ImportError> {
+        let Exported {
+            version,
+            current,
+            entries,
+        } = exported;
+
+        if !(Exported::<T>::OLDEST_VERSION..=Exported::<T>::VERSION).contains(&version) {
+            return Err(ImportError::UnsupportedVersion(version));
+        }
+
+        // validate before filling storage so nothing is dropped from it on error
+        Self::validate_parts(entries.len(), current)?;
+
+        storage.truncate(0);
+
+        for entry in entries {
+            storage.push(entry);
+        }
+
+        Self::from_parts(storage, current).map_err(ImportError::from)
+    }
+
+    /// Checks that a history of `len` entries with its current value at `current` upholds the
+    /// invariants of an `UndoStack`, this is shared by every path building a stack from raw parts
+    fn validate_parts(len: usize, current: usize) -> Result<(), FromPartsError> {
+        if len == 0 {
+            return Err(FromPartsError::Empty);
+        }
+
+        if current >= len {
+            return Err(FromPartsError::CurrentOutOfBounds);
+        }
+
+        Ok(())
+    }
+
+    /// Builds an `UndoStack` from storage holding every entry of its history oldest first, and the
+    /// index of the current value within it. This is the inverse of
+    /// [`into_parts`](UndoStack::into_parts).
+    ///
+    /// ```rust
+    /// # use history_stack::{FromPartsError, UndoStack};
+    /// let mut undo = UndoStack::from_parts(vec![1u8, 2, 3], 1).unwrap();
+    /// assert_eq!(undo, 2);
+    /// assert!(undo.redo().is_ok());
+    ///
+    /// assert_eq!(
+    ///     UndoStack::from_parts(vec![1u8], 1),
+    ///     Err(FromPartsError::CurrentOutOfBounds)
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// If `history` is empty, or `current` is out of bounds
+    pub fn from_parts(history: S, current: usize) -> Result<Self, FromPartsError> {
+        Self::validate_parts(history.len(), current)?;
+
+        Ok(Self {
+            history,
+            current,
+            clean: None,
+            version: 0,
+            batching: false,
+            limits: crate::limits::Limits::default(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Splits an `UndoStack` into the storage holding every entry of its history oldest first,
+    /// and the index of the current value within it
+    pub fn into_parts(self) -> (S, usize) {
+        (self.history, self.current)
+    }
+}
+
+impl<T> UndoStack<T> {
+    /// Rebuilds an `UndoStack` from an [`Exported`] snapshot, see
+    /// [`import_with_storage`](UndoStack::import_with_storage)
+    ///
+    /// # Errors
+    /// If the export version is not supported by this release of the crate, the export held no
+    /// entries, or its current position was out of bounds
+    pub fn import(exported: Exported<T>) -> Result<Self, ImportError> {
+        Self::import_with_storage(exported, Vec::new())
+    }
+}
+
+#[test]
+fn export_import() {
+    use alloc::vec;
+
+    let mut undo = UndoStack::new(0u8);
+    for _ in 0..3 {
+        *undo.save() += 1;
+    }
+    undo.undo().unwrap();
+
+    let exported = undo.export();
+    assert_eq!(exported.version, 1);
+    assert_eq!(exported.current, 2);
+
+    let mut imported = UndoStack::import(exported.clone()).unwrap();
+    assert_eq!(*imported.get(), 2);
+    assert_eq!(*imported.redo().unwrap(), 3);
+
+    let newer = Exported {
+        version: 2,
+        ..exported.clone()
+    };
+    assert_eq!(
+        UndoStack::import(newer),
+        Err(ImportError::UnsupportedVersion(2))
+    );
+
+    let unversioned = Exported {
+        version: 0,
+        ..exported.clone()
+    };
+    assert_eq!(
+        UndoStack::import(unversioned),
+        Err(ImportError::UnsupportedVersion(0))
+    );
+
+    let empty = Exported::<u8>::new(0, vec![]);
+    assert_eq!(UndoStack::import(empty), Err(ImportError::Empty));
+
+    let oob = Exported {
+        current: 4,
+        ..exported
+    };
+    assert_eq!(UndoStack::import(oob), Err(ImportError::CurrentOutOfBounds));
+
+    let (history, current) = imported.into_parts();
+    assert_eq!((&*history, current), (&[0, 1, 2, 3][..], 3));
+    assert_eq!(
+        UndoStack::<u8>::from_parts(vec![], 0),
+        Err(FromPartsError::Empty)
+    );
+}
@@ -0,0 +1,170 @@
+//! Speculative forks of an undo stack that can later be adopted back into it
+
+use alloc::vec::Vec;
+use core::{
+    marker::PhantomData,
+    ops::{Range, RangeBounds},
+};
+
+use crate::{range, HistoryStorage, UndoStack};
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Creates a child stack starting from a clone of the current value, on which speculative
+    /// edits and saves can be made without touching this stack. The child can later be adopted
+    /// with [`merge_back`](UndoStack::merge_back), or discarded by dropping it.
+    ///
+    /// For large values, forking an `UndoStack<Rc<T>>` or a [`LazyUndoStack`](crate::LazyUndoStack)
+    /// only clones a pointer, and defers cloning the value until it is first mutated.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(1u8);
+    ///
+    /// let mut preview = undo.fork();
+    /// *preview.save() += 1;
+    /// *preview.save() += 1;
+    ///
+    /// // the preview was accepted
+    /// undo.merge_back(preview);
+    ///
+    /// assert_eq!(undo, 3);
+    /// assert_eq!(*undo.undo().unwrap(), 2);
+    /// assert_eq!(*undo.undo().unwrap(), 1);
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn fork(&self) -> UndoStack<T>
+    where
+        T: Clone,
+    {
+        UndoStack::new(self.inner().clone())
+    }
+
+    /// Creates a new stack holding clones of only the entries of history within `range`, such as
+    /// a bounded window of recent history to hand to a crash reporter. The current value of the new
+    /// stack is the entry closest to the current value of this stack within the window, and the
+    /// clean state is kept if it lies within the window, see [`UndoStack::clean_state`].
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(0u8);
+    /// for i in 1..=9 {
+    ///     undo.push(i);
+    /// }
+    /// undo.undo().unwrap();
+    ///
+    /// let recent = undo.clone_range(undo.len() - 3..);
+    /// assert_eq!(recent.len(), 3);
+    /// assert_eq!(recent, 8);
+    ///
+    /// // the current value is clamped into the window
+    /// assert_eq!(undo.clone_range(..2), 1);
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if `range` is empty or out of bounds, or if allocation failed
+    #[must_use]
+    pub fn clone_range(&self, range: impl RangeBounds<usize>) -> UndoStack<T>
+    where
+        T: Clone,
+    {
+        let Range { start, end } = range::resolve(&range, self.history.len());
+
+        assert!(
+            start < end && end <= self.history.len(),
+            "UndoStack::clone_range: range was empty or out of bounds"
+        );
+
+        let history: Vec<T> = (start..end).map(|i| self.at(i).clone()).collect();
+
+        UndoStack {
+            current: self.current.clamp(start, end - 1) - start,
+            clean: self
+                .clean
+                .filter(|c| (start..end).contains(c))
+                .map(|c| c - start),
+            version: 0,
+            batching: false,
+            limits: crate::limits::Limits::default(),
+            history,
+            marker: PhantomData,
+        }
+    }
+
+    /// Adopts the timeline of a child created by [`fork`](UndoStack::fork) up to its current
+    /// value, invalidating any data that may be used to redo.
+    ///
+    /// The current value is replaced by the first entry of the child, which is the value it was
+    /// forked from along with any edit made to it in place, and every later entry of the child up
+    /// to its current value is pushed after it. The redo history of the child is dropped.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn merge_back(&mut self, child: UndoStack<T>) {
+        self.invariant_ck();
+
+        self.invalidate_future();
+
+        let mut entries = child.history.into_iter().take(child.current + 1);
+
+        if let Some(first) = entries.next() {
+            *self.at_mut(self.current) = first;
+            self.clean_rewritten(self.current);
+        }
+
+        for entry in entries {
+            self.push_unchecked(entry);
+        }
+    }
+}
+
+#[test]
+fn clone_range() {
+    let mut undo = UndoStack::new(0u8);
+    for i in 1..=5 {
+        undo.push(i);
+    }
+    undo.set_position(1).unwrap();
+    undo.mark_clean();
+
+    let window = undo.clone_range(1..=3);
+    assert_eq!(window.len(), 3);
+    assert_eq!(window, 1);
+    assert!(!window.is_dirty());
+    assert!(!window.can_undo());
+
+    let window = undo.clone_range(4..);
+    assert_eq!(window, 4);
+    assert_eq!(window.clean_position(), None);
+}
+
+#[test]
+fn fork_merge_back() {
+    let mut undo = UndoStack::new(0u8);
+    *undo.save() = 1;
+    *undo.save() = 2;
+    undo.undo().unwrap();
+
+    let mut child = undo.fork();
+    *child.get_mut() += 10;
+    *child.save() = 20;
+    *child.save() = 30;
+    child.undo().unwrap();
+
+    // discarding leaves the parent untouched
+    drop(undo.fork());
+    assert_eq!(undo, 1);
+
+    undo.merge_back(child);
+    assert_eq!(undo.len(), 3);
+    assert!(!undo.can_redo());
+    assert_eq!(undo, 20);
+    assert_eq!(*undo.undo().unwrap(), 11);
+    assert_eq!(*undo.undo().unwrap(), 0);
+
+    // the entry merged onto is replaced, so it is no longer the clean state
+    undo.redo().unwrap();
+    undo.mark_clean();
+    undo.merge_back(undo.fork());
+    assert_eq!(undo.clean_state(), crate::CleanState::Invalidated);
+}
@@ -0,0 +1,152 @@
+//! An immutable, shareable snapshot of the timeline of an undo stack
+
+use alloc::{sync::Arc, vec::Vec};
+use core::marker::PhantomData;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// An immutable copy of the timeline of an [`UndoStack`] at one point in time, created by
+/// [`UndoStack::freeze`].
+///
+/// Cloning a `FrozenHistory` only clones a pointer, and it can be sent to another thread when `T`
+/// is `Send + Sync`, so it can be handed to background work such as autosaving or computing diffs
+/// while the stack it was frozen from keeps being edited.
+/// ```rust
+/// # use history_stack::UndoStack;
+/// let mut undo = UndoStack::new(1u8);
+/// *undo.save() = 2;
+///
+/// let frozen = undo.freeze();
+///
+/// let autosave = std::thread::spawn({
+///     let frozen = frozen.clone();
+///     move || frozen.iter().copied().collect::<Vec<_>>()
+/// });
+///
+/// *undo.save() = 3;
+///
+/// assert_eq!(autosave.join().unwrap(), [1, 2]);
+/// assert_eq!(*frozen.current(), 2);
+/// ```
+#[derive(Debug)]
+pub struct FrozenHistory<T> {
+    /// All entries, oldest first
+    entries: Arc<[T]>,
+    /// Index of the current value within entries
+    current: usize,
+}
+
+impl<T> Clone for FrozenHistory<T> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: Arc::clone(&self.entries),
+            current: self.current,
+        }
+    }
+}
+
+impl<T> FrozenHistory<T> {
+    /// Returns the current value at the time of freezing
+    #[must_use]
+    pub fn current(&self) -> &T {
+        &self.entries[self.current]
+    }
+
+    /// Returns the index of the current value within the timeline
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the amount of entries in the timeline, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Always returns `false`, as a timeline always holds at least its current value
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the entry at `index` in the timeline, oldest first
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.entries.get(index)
+    }
+
+    /// Returns every entry of the timeline as a slice, oldest first
+    #[must_use]
+    pub fn entries(&self) -> &[T] {
+        &self.entries
+    }
+
+    /// Returns an iterator over all entries of the timeline, oldest first
+    #[must_use]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    /// Creates a new `UndoStack` holding a copy of the frozen timeline, with the same current value
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn thaw(&self) -> UndoStack<T>
+    where
+        T: Clone,
+    {
+        UndoStack {
+            history: self.entries.to_vec(),
+            current: self.current,
+            clean: None,
+            version: 0,
+            batching: false,
+            limits: crate::limits::Limits::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Copies the timeline of this stack into an immutable [`FrozenHistory`] that can be cheaply
+    /// shared, including across threads
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn freeze(&self) -> FrozenHistory<T>
+    where
+        T: Clone,
+    {
+        let entries: Vec<T> = (0..self.len()).map(|i| self.at(i).clone()).collect();
+
+        FrozenHistory {
+            entries: entries.into(),
+            current: self.current,
+        }
+    }
+}
+
+#[test]
+fn frozen_history() {
+    let mut undo = UndoStack::new(0u8);
+    *undo.save() = 1;
+    *undo.save() = 2;
+    undo.undo().unwrap();
+
+    let frozen = undo.freeze();
+    let shared = frozen.clone();
+    assert!(Arc::ptr_eq(&frozen.entries, &shared.entries));
+
+    *undo.save() = 5;
+
+    assert_eq!(frozen.entries(), [0, 1, 2]);
+    assert_eq!(frozen.position(), 1);
+    assert_eq!(frozen.get(3), None);
+
+    let mut thawed = frozen.thaw();
+    assert_eq!(thawed, 1);
+    assert_eq!(*thawed.redo().unwrap(), 2);
+}
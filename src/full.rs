@@ -0,0 +1,157 @@
+//! Hashing and comparisons over the whole timeline of a stack, instead of only its current value
+
+use core::{cmp, hash};
+
+use crate::{HistoryStack, HistoryStorage, UndoStack};
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns an iterator over every entry of history, oldest first
+    fn entries(&self) -> impl Iterator<Item = &T> {
+        (0..self.len()).map(|i| self.at(i))
+    }
+
+    /// Feeds every entry of history and the position of the current value into `state`, unlike
+    /// the [`Hash`](hash::Hash) impl which only hashes the current value
+    pub fn full_hash<H: hash::Hasher>(&self, state: &mut H)
+    where
+        T: hash::Hash,
+    {
+        state.write_usize(self.len());
+        state.write_usize(self.current);
+
+        for entry in self.entries() {
+            entry.hash(state);
+        }
+    }
+
+    /// Returns `true` if both stacks hold equal histories with their current value at the same
+    /// position, unlike [`PartialEq`] which only compares current values
+    pub fn full_eq<S2: HistoryStorage<T>>(&self, other: &UndoStack<T, S2>) -> bool
+    where
+        T: PartialEq,
+    {
+        self.current == other.current && self.entries().eq(other.entries())
+    }
+}
+
+impl<T, S: HistoryStorage<T>> HistoryStack<T, S> {
+    /// Returns an iterator over every saved value oldest first, followed by the current value
+    fn oldest_first(&self) -> impl Iterator<Item = &T> {
+        (0..self.stack.len())
+            .filter_map(|i| self.stack.get(i))
+            .chain([&self.current])
+    }
+
+    /// Feeds every saved value and the current value into `state`, unlike the
+    /// [`Hash`](hash::Hash) impl which only hashes the current value
+    pub fn full_hash<H: hash::Hasher>(&self, state: &mut H)
+    where
+        T: hash::Hash,
+    {
+        state.write_usize(self.stack.len());
+
+        for entry in self.oldest_first() {
+            entry.hash(state);
+        }
+    }
+
+    /// Returns `true` if both stacks hold equal saved values and current values, unlike
+    /// [`PartialEq`] which only compares current values
+    pub fn full_eq<S2: HistoryStorage<T>>(&self, other: &HistoryStack<T, S2>) -> bool
+    where
+        T: PartialEq,
+    {
+        self.oldest_first().eq(other.oldest_first())
+    }
+}
+
+/// A wrapper over a reference to an [`UndoStack`] that implements [`Hash`](hash::Hash), [`Eq`]
+/// and [`Ord`] over its entire timeline and the position of its current value.
+///
+/// This allows stacks with different histories to be told apart as keys of maps and sets, such as
+/// in a cache deduplicating stacks.
+/// ```rust
+/// # use history_stack::{FullCmp, UndoStack};
+/// let a = UndoStack::new(1u8);
+/// let mut b = UndoStack::new(0u8);
+/// *b.save() = 1;
+///
+/// // transparently equal, but with different histories
+/// assert_eq!(a, b);
+/// assert_ne!(FullCmp(&a), FullCmp(&b));
+/// assert!(FullCmp(&b) < FullCmp(&a));
+/// ```
+#[derive(Debug)]
+pub struct FullCmp<'a, T, S>(pub &'a UndoStack<T, S>);
+
+impl<T, S> Clone for FullCmp<'_, T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, S> Copy for FullCmp<'_, T, S> {}
+
+impl<T: hash::Hash, S: HistoryStorage<T>> hash::Hash for FullCmp<'_, T, S> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.full_hash(state);
+    }
+}
+
+impl<T: PartialEq, S: HistoryStorage<T>> PartialEq for FullCmp<'_, T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.full_eq(other.0)
+    }
+}
+
+impl<T: Eq, S: HistoryStorage<T>> Eq for FullCmp<'_, T, S> {}
+
+impl<T: PartialOrd, S: HistoryStorage<T>> PartialOrd for FullCmp<'_, T, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        match self.0.entries().partial_cmp(other.0.entries())? {
+            cmp::Ordering::Equal => Some(self.0.current.cmp(&other.0.current)),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl<T: Ord, S: HistoryStorage<T>> Ord for FullCmp<'_, T, S> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.0
+            .entries()
+            .cmp(other.0.entries())
+            .then(self.0.current.cmp(&other.0.current))
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn full_comparisons() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    fn full_hash(undo: &UndoStack<u8>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        undo.full_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut a = UndoStack::new(0u8);
+    *a.save() = 1;
+    let mut b = a.clone();
+
+    assert!(a.full_eq(&b));
+    assert_eq!(full_hash(&a), full_hash(&b));
+
+    b.undo().unwrap();
+    assert!(!a.full_eq(&b));
+    assert_ne!(full_hash(&a), full_hash(&b));
+    assert!(FullCmp(&b) < FullCmp(&a));
+
+    let mut h = HistoryStack::new(0u8);
+    h.push_value(1);
+    let g = HistoryStack::with_storage(1u8, alloc::vec![0]);
+    assert!(h.full_eq(&g));
+    h.push_value(1);
+    assert!(!h.full_eq(&g));
+}
@@ -0,0 +1,234 @@
+//! An undo stack that stamps every entry with a stable generation id
+
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::UndoStack;
+
+/// A stable id of an entry of a [`GenerationalUndoStack`], unique among every entry the stack has
+/// ever held.
+///
+/// Ids increase in the order entries were created, so they can be compared to tell which of two
+/// entries is newer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Generation(pub u64);
+
+/// A value in history along with its generation
+#[derive(Clone, Debug)]
+struct Stamped<T> {
+    /// The value
+    value: T,
+    /// The id of the value, assigned when it was saved or pushed
+    generation: Generation,
+}
+
+/// An [`UndoStack`] that stamps every entry of its history with a [`Generation`], giving entries an
+/// identity that stays valid when history is trimmed and indices shift, such as for caches or
+/// annotation layers kept outside of the stack.
+///
+/// The generation of an entry is assigned when it is saved or pushed, mutating the current value
+/// afterwards does not change its generation.
+/// ```rust
+/// # use history_stack::GenerationalUndoStack;
+/// let mut undo = GenerationalUndoStack::new(0u8);
+/// *undo.save() = 1;
+/// let one = undo.generation();
+/// *undo.save() = 2;
+///
+/// undo.keep_last(2);
+///
+/// // the index of the entry changed, but its generation still finds it
+/// assert_eq!(undo.find_generation(one), Some(0));
+/// assert_eq!(undo.generation_of(0), Some(one));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GenerationalUndoStack<T> {
+    /// The underlying stack holding stamped values
+    stack: UndoStack<Stamped<T>>,
+    /// The generation the next saved or pushed entry is stamped with
+    next: u64,
+}
+
+impl<T: Default> Default for GenerationalUndoStack<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> GenerationalUndoStack<T> {
+    /// Creates a new `GenerationalUndoStack` with a starting value to act as the current value,
+    /// stamped with generation 0
+    pub fn new(start: T) -> Self {
+        Self {
+            stack: UndoStack::new(Stamped {
+                value: start,
+                generation: Generation(0),
+            }),
+            next: 1,
+        }
+    }
+
+    /// Returns a fresh generation
+    fn bump(&mut self) -> Generation {
+        let generation = Generation(self.next);
+        self.next += 1;
+
+        generation
+    }
+
+    /// Saves the current value to history, stamping the new current value with a new generation,
+    /// see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        let generation = self.bump();
+
+        let new = self.stack.save();
+        new.generation = generation;
+
+        &mut new.value
+    }
+
+    /// Pushes a new current value stamped with a new generation, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        let generation = self.bump();
+
+        &mut self
+            .stack
+            .push(Stamped {
+                value: new_current,
+                generation,
+            })
+            .value
+    }
+
+    /// Undoes to the previous state, whose generation stays the same, see [`UndoStack::undo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        match self.stack.undo() {
+            Ok(v) => Ok(&mut v.value),
+            Err(v) => Err(&mut v.value),
+        }
+    }
+
+    /// Redoes to the next state, whose generation stays the same, see [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        match self.stack.redo() {
+            Ok(v) => Ok(&mut v.value),
+            Err(v) => Err(&mut v.value),
+        }
+    }
+
+    /// Drops the oldest values in history until at most `n` values remain, keeping the
+    /// generations of the rest, see [`UndoStack::keep_last`]
+    pub fn keep_last(&mut self, n: usize) -> usize {
+        self.stack.keep_last(n)
+    }
+
+    /// Returns the generation of the current value
+    #[must_use]
+    pub fn generation(&self) -> Generation {
+        self.stack.get().generation
+    }
+
+    /// Returns the generation of the value at `index` in history, or `None` if `index` is out of
+    /// bounds
+    #[must_use]
+    pub fn generation_of(&self, index: usize) -> Option<Generation> {
+        self.stack.history.get(index).map(|v| v.generation)
+    }
+
+    /// Returns the index in history of the value stamped with `generation`, or `None` if it is no
+    /// longer held in history
+    #[must_use]
+    pub fn find_generation(&self, generation: Generation) -> Option<usize> {
+        // generations are assigned in increasing order and entries are only ever appended, so
+        // history is sorted by generation
+        self.stack
+            .history
+            .binary_search_by_key(&generation, |v| v.generation)
+            .ok()
+    }
+
+    /// Returns the value stamped with `generation`, or `None` if it is no longer held in history
+    #[must_use]
+    pub fn get_generation(&self, generation: Generation) -> Option<&T> {
+        self.find_generation(generation)
+            .map(|i| &self.stack.history[i].value)
+    }
+
+    /// Returns the amount of values held in history, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Always returns `false`, as the current value always holds a generation
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in history
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.stack.position()
+    }
+
+    /// Returns a reference to the current value, without its generation
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.stack.get().value
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.stack.get_mut().value
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::Deref for GenerationalUndoStack<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::DerefMut for GenerationalUndoStack<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn generations() {
+    let mut undo = GenerationalUndoStack::new(0u8);
+    *undo.save() = 1;
+    *undo.save() = 2;
+
+    let two = undo.generation();
+    assert_eq!(two, Generation(2));
+
+    undo.undo().unwrap();
+    undo.push(5);
+
+    // generations are never reused, even after the entry holding one was dropped
+    assert_eq!(undo.generation(), Generation(3));
+    assert_eq!(undo.find_generation(two), None);
+    assert_eq!(undo.get_generation(Generation(1)), Some(&1));
+
+    assert_eq!(undo.keep_last(1), 2);
+    assert_eq!(undo.generation_of(0), Some(Generation(3)));
+    assert_eq!(undo.generation_of(1), None);
+}
@@ -0,0 +1,155 @@
+//! A hook that ignores long bursts of repeated undo or redo calls, such as from key repeat
+
+use crate::{Clock, HistoryHook, UndoStack};
+
+/// The direction of a move through history
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    /// An undo
+    Back,
+    /// A redo
+    Forward,
+}
+
+/// A [`HistoryHook`] that only lets a limited amount of consecutive undo or redo calls through
+/// when they arrive in quick succession, as measured by a user provided [`Clock`].
+///
+/// A call made less than the window after the previous call in the same direction continues a burst,
+/// and every call of a burst past the repeat limit is vetoed, so holding down an undo shortcut
+/// does not blow through the entire history. Any save or push ends the burst, and a move can be
+/// confirmed by ending the burst with [`end_burst`](RepeatGuard::end_burst) before retrying it.
+/// ```rust
+/// # use history_stack::{HookedUndoStack, RepeatGuard};
+/// # use core::cell::Cell;
+/// let time = Cell::new(0u32);
+/// let mut undo = HookedUndoStack::new(0u8, RepeatGuard::new(|| time.get(), 2, 100));
+///
+/// for i in 1..=5 {
+///     *undo.save() = i;
+/// }
+///
+/// // key repeat, one call every 30 time units
+/// for _ in 0..4 {
+///     time.set(time.get() + 30);
+///     undo.undo().ok();
+/// }
+/// assert_eq!(*undo.get(), 3);
+///
+/// // the call is vetoed even though there is a state to undo to
+/// time.set(time.get() + 30);
+/// assert!(undo.undo().is_err());
+/// assert!(undo.stack().can_undo());
+///
+/// // after a pause the next undo goes through
+/// time.set(time.get() + 100);
+/// assert_eq!(undo.undo(), Ok(&mut 2));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RepeatGuard<C: Clock> {
+    /// The clock used to measure the time between calls
+    clock: C,
+    /// The amount of calls a burst lets through
+    limit: usize,
+    /// Two consecutive calls less than this apart are part of the same burst
+    window: C::Duration,
+    /// The direction and time of the last undo or redo call, if no other operation happened since
+    last: Option<(Direction, C::Instant)>,
+    /// The amount of calls in the current burst
+    burst: usize,
+}
+
+impl<C: Clock> RepeatGuard<C> {
+    /// Creates a new `RepeatGuard` which lets at most `limit` calls of a burst through, where
+    /// consecutive calls in the same direction less than `window` apart form a burst
+    pub fn new(clock: C, limit: usize, window: C::Duration) -> Self {
+        Self {
+            clock,
+            limit,
+            window,
+            last: None,
+            burst: 0,
+        }
+    }
+
+    /// Sets the amount of calls a burst lets through and the window that consecutive calls of a
+    /// burst are less apart than, the current burst continues with the new limits
+    pub fn set_repeat_limit(&mut self, limit: usize, window: C::Duration) {
+        self.limit = limit;
+        self.window = window;
+    }
+
+    /// Ends the current burst, so the next call is let through regardless of the repeat limit,
+    /// such as after the user confirmed it
+    pub fn end_burst(&mut self) {
+        self.last = None;
+    }
+
+    /// Records a call in `direction`, returning `true` if it is let through
+    fn admit(&mut self, direction: Direction) -> bool {
+        let now = self.clock.now();
+
+        let repeated = self.last.map_or(false, |(d, at)| {
+            d == direction && self.clock.between(at, now) < self.window
+        });
+
+        self.burst = if repeated { self.burst + 1 } else { 1 };
+        self.last = Some((direction, now));
+
+        self.burst <= self.limit
+    }
+}
+
+impl<T, C: Clock> HistoryHook<T> for RepeatGuard<C> {
+    fn allow_undo(&mut self, _: &UndoStack<T>) -> bool {
+        self.admit(Direction::Back)
+    }
+
+    fn allow_redo(&mut self, _: &UndoStack<T>) -> bool {
+        self.admit(Direction::Forward)
+    }
+
+    fn on_save(&mut self, _: &UndoStack<T>) {
+        self.end_burst();
+    }
+
+    fn on_push(&mut self, _: &UndoStack<T>) {
+        self.end_burst();
+    }
+}
+
+#[test]
+fn repeat_guard() {
+    use core::cell::Cell;
+
+    use crate::HookedUndoStack;
+
+    let time = Cell::new(0u32);
+    let mut undo = HookedUndoStack::new(0u8, RepeatGuard::new(|| time.get(), 1, 10));
+
+    for i in 1..=4 {
+        undo.push(i);
+    }
+
+    assert_eq!(undo.undo(), Ok(&mut 3));
+    assert_eq!(undo.undo(), Err(&mut 3));
+
+    // changing direction starts a new burst
+    assert_eq!(undo.redo(), Ok(&mut 4));
+    assert_eq!(undo.redo(), Err(&mut 4));
+    time.set(5);
+    assert_eq!(undo.redo(), Err(&mut 4));
+
+    // the window is measured from the previous call, so the burst only ends after a pause
+    time.set(16);
+    assert_eq!(undo.redo(), Err(&mut 4));
+    assert!(!undo.stack().can_redo());
+
+    assert_eq!(undo.undo(), Ok(&mut 3));
+    undo.hook_mut().end_burst();
+    assert_eq!(undo.undo(), Ok(&mut 2));
+    assert_eq!(undo.undo(), Err(&mut 2));
+
+    // saving ends the burst as well
+    undo.save();
+    assert_eq!(undo.undo(), Ok(&mut 2));
+}
@@ -0,0 +1,127 @@
+//! The [`History`] trait, unifying the history strategies of [`HistoryStack`] and [`UndoStack`]
+
+use crate::{HistoryStack, HistoryStorage, UndoStack};
+
+/// The surface shared by [`HistoryStack`] and [`UndoStack`], so that code can be written once
+/// against either history strategy and the choice left to the downstream application.
+///
+/// [`snapshot`](History::snapshot) saves the current value so it can later be returned to with
+/// [`revert`](History::revert). A `HistoryStack` drops the reverted from value, while an
+/// `UndoStack` keeps it so that it can be redone to with its own methods.
+/// ```rust
+/// # use history_stack::{History, HistoryStack, UndoStack};
+/// fn try_edit<H: History<Value = String>>(history: &mut H) {
+///     history.snapshot().push_str("!!");
+///
+///     if history.current().len() > 5 {
+///         history.revert();
+///     }
+/// }
+///
+/// let mut stack = HistoryStack::new(String::from("hello"));
+/// let mut undo = UndoStack::new(String::from("hi"));
+///
+/// try_edit(&mut stack);
+/// try_edit(&mut undo);
+///
+/// assert_eq!(stack.get(), "hello");
+/// assert_eq!(undo.get(), "hi!!");
+/// ```
+pub trait History {
+    /// The type of the values held in history
+    type Value;
+
+    /// Returns a reference to the current value
+    fn current(&self) -> &Self::Value;
+
+    /// Returns a mutable reference to the current value
+    fn current_mut(&mut self) -> &mut Self::Value;
+
+    /// Saves a snapshot of the current value to history, and returns a mutable reference to the
+    /// new current value
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    fn snapshot(&mut self) -> &mut Self::Value
+    where
+        Self::Value: Clone;
+
+    /// Reverts the current value to the last snapshot, returning `true` if there was a snapshot
+    /// to revert to, otherwise the current value is unchanged
+    fn revert(&mut self) -> bool;
+
+    /// Returns `true` if there is a snapshot to [`revert`](History::revert) to
+    fn can_revert(&self) -> bool;
+}
+
+impl<T, S: HistoryStorage<T>> History for HistoryStack<T, S> {
+    type Value = T;
+
+    fn current(&self) -> &T {
+        &self.current
+    }
+
+    fn current_mut(&mut self) -> &mut T {
+        &mut self.current
+    }
+
+    fn snapshot(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        self.push();
+        &mut self.current
+    }
+
+    fn revert(&mut self) -> bool {
+        self.pop().is_some()
+    }
+
+    fn can_revert(&self) -> bool {
+        !self.stack.is_empty()
+    }
+}
+
+impl<T, S: HistoryStorage<T>> History for UndoStack<T, S> {
+    type Value = T;
+
+    fn current(&self) -> &T {
+        self.get()
+    }
+
+    fn current_mut(&mut self) -> &mut T {
+        self.get_mut()
+    }
+
+    fn snapshot(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        self.save()
+    }
+
+    fn revert(&mut self) -> bool {
+        self.undo().is_ok()
+    }
+
+    fn can_revert(&self) -> bool {
+        self.can_undo()
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn history_trait() {
+    fn exercise<H: History<Value = u8>>(h: &mut H) {
+        assert!(!h.can_revert());
+        *h.snapshot() += 1;
+        *h.current_mut() += 1;
+        assert!(h.can_revert());
+        assert!(h.revert());
+        assert!(!h.revert());
+        assert_eq!(*h.current(), 0);
+    }
+
+    exercise(&mut HistoryStack::new(0u8));
+    exercise(&mut UndoStack::new(0u8));
+}
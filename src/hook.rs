@@ -0,0 +1,688 @@
+//! The [`HistoryHook`] trait, a single extension point that is notified of history events on an
+//! [`UndoStack`] and can veto moves, coalesce saves, and evict entries
+
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::UndoStack;
+
+/// Callbacks invoked by a [`HookedUndoStack`] around each history event, every method has a
+/// default implementation that does nothing and allows everything, so only the relevant parts
+/// need to be implemented.
+///
+/// The `on_*` callbacks are given a reference to the stack after the event has been applied, the
+/// other methods are asked before it is applied. Hooks are combined by using a tuple `(A, B)` of
+/// them as the hook, which notifies and asks both.
+#[allow(unused_variables)]
+pub trait HistoryHook<T> {
+    /// Called before every [`undo`](HookedUndoStack::undo), even if there is no state to undo to,
+    /// which is vetoed if this returns `false`
+    fn allow_undo(&mut self, stack: &UndoStack<T>) -> bool {
+        true
+    }
+
+    /// Called before every [`redo`](HookedUndoStack::redo), even if there is no state to redo to,
+    /// which is vetoed if this returns `false`
+    fn allow_redo(&mut self, stack: &UndoStack<T>) -> bool {
+        true
+    }
+
+    /// Called before every [`save`](HookedUndoStack::save), if this returns `true` no entry is
+    /// created and the save keeps editing the current value instead. A coalesced save is not a
+    /// history event, so no other callback is invoked for it.
+    fn coalesce_save(&mut self, stack: &UndoStack<T>) -> bool {
+        false
+    }
+
+    /// Called after every save and push and on [`enforce`](HookedUndoStack::enforce), returning
+    /// the indices of the entries of history to evict.
+    ///
+    /// Indices of the current value, any value after it, or out of bounds indices are ignored, as
+    /// are duplicates.
+    fn evict(&mut self, stack: &UndoStack<T>) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// Called after the current value was saved to history by [`HookedUndoStack::save`]
+    fn on_save(&mut self, stack: &UndoStack<T>) {}
+
+    /// Called after a new current value was pushed by [`HookedUndoStack::push`]
+    fn on_push(&mut self, stack: &UndoStack<T>) {}
+
+    /// Called after a successful undo, failed or vetoed undos do not notify the hook
+    fn on_undo(&mut self, stack: &UndoStack<T>) {}
+
+    /// Called after a successful redo, failed or vetoed redos do not notify the hook
+    fn on_redo(&mut self, stack: &UndoStack<T>) {}
+
+    /// Called when the `dropped` newest values were dropped from history, starting at the index
+    /// `stack.len()`. This happens before a save or push invalidates the redo history, in which
+    /// case it is called before the corresponding [`on_save`](HistoryHook::on_save) or
+    /// [`on_push`](HistoryHook::on_push), and when a [redo limit](UndoStack::set_redo_limit) is
+    /// exceeded. It is only called if at least one value was dropped.
+    fn on_truncate(&mut self, stack: &UndoStack<T>, dropped: usize) {}
+
+    /// Called when past values were evicted from history, with `evicted` holding the indices they
+    /// had before they were evicted in ascending order. This happens when
+    /// [`evict`](HistoryHook::evict) chose them, on [`keep_last`](HookedUndoStack::keep_last),
+    /// and when an [undo limit](UndoStack::set_undo_limit) or
+    /// [size budget](UndoStack::set_size_budget) is exceeded. It is only called if at least one
+    /// value was evicted.
+    fn on_evict(&mut self, stack: &UndoStack<T>, evicted: &[usize]) {}
+}
+
+impl<T> HistoryHook<T> for () {}
+
+/// Implements [`HistoryHook`] for a pointer to a hook by forwarding every method to the pointee
+macro_rules! forward_hook {
+    ($($ptr:ty),*) => {$(
+        impl<T, H: HistoryHook<T> + ?Sized> HistoryHook<T> for $ptr {
+            fn allow_undo(&mut self, stack: &UndoStack<T>) -> bool {
+                (**self).allow_undo(stack)
+            }
+
+            fn allow_redo(&mut self, stack: &UndoStack<T>) -> bool {
+                (**self).allow_redo(stack)
+            }
+
+            fn coalesce_save(&mut self, stack: &UndoStack<T>) -> bool {
+                (**self).coalesce_save(stack)
+            }
+
+            fn evict(&mut self, stack: &UndoStack<T>) -> Vec<usize> {
+                (**self).evict(stack)
+            }
+
+            fn on_save(&mut self, stack: &UndoStack<T>) {
+                (**self).on_save(stack);
+            }
+
+            fn on_push(&mut self, stack: &UndoStack<T>) {
+                (**self).on_push(stack);
+            }
+
+            fn on_undo(&mut self, stack: &UndoStack<T>) {
+                (**self).on_undo(stack);
+            }
+
+            fn on_redo(&mut self, stack: &UndoStack<T>) {
+                (**self).on_redo(stack);
+            }
+
+            fn on_truncate(&mut self, stack: &UndoStack<T>, dropped: usize) {
+                (**self).on_truncate(stack, dropped);
+            }
+
+            fn on_evict(&mut self, stack: &UndoStack<T>, evicted: &[usize]) {
+                (**self).on_evict(stack, evicted);
+            }
+        }
+    )*};
+}
+
+forward_hook!(&mut H, Box<H>);
+
+/// Both hooks are always asked, so hooks that track calls see every one of them. A move is
+/// allowed only if both allow it, a save is coalesced if either coalesces it, and the entries
+/// either chose are evicted.
+impl<T, A: HistoryHook<T>, B: HistoryHook<T>> HistoryHook<T> for (A, B) {
+    fn allow_undo(&mut self, stack: &UndoStack<T>) -> bool {
+        let a = self.0.allow_undo(stack);
+        let b = self.1.allow_undo(stack);
+
+        a && b
+    }
+
+    fn allow_redo(&mut self, stack: &UndoStack<T>) -> bool {
+        let a = self.0.allow_redo(stack);
+        let b = self.1.allow_redo(stack);
+
+        a && b
+    }
+
+    fn coalesce_save(&mut self, stack: &UndoStack<T>) -> bool {
+        let a = self.0.coalesce_save(stack);
+        let b = self.1.coalesce_save(stack);
+
+        a || b
+    }
+
+    fn evict(&mut self, stack: &UndoStack<T>) -> Vec<usize> {
+        let mut evict = self.0.evict(stack);
+        evict.extend(self.1.evict(stack));
+
+        evict
+    }
+
+    fn on_save(&mut self, stack: &UndoStack<T>) {
+        self.0.on_save(stack);
+        self.1.on_save(stack);
+    }
+
+    fn on_push(&mut self, stack: &UndoStack<T>) {
+        self.0.on_push(stack);
+        self.1.on_push(stack);
+    }
+
+    fn on_undo(&mut self, stack: &UndoStack<T>) {
+        self.0.on_undo(stack);
+        self.1.on_undo(stack);
+    }
+
+    fn on_redo(&mut self, stack: &UndoStack<T>) {
+        self.0.on_redo(stack);
+        self.1.on_redo(stack);
+    }
+
+    fn on_truncate(&mut self, stack: &UndoStack<T>, dropped: usize) {
+        self.0.on_truncate(stack, dropped);
+        self.1.on_truncate(stack, dropped);
+    }
+
+    fn on_evict(&mut self, stack: &UndoStack<T>, evicted: &[usize]) {
+        self.0.on_evict(stack, evicted);
+        self.1.on_evict(stack, evicted);
+    }
+}
+
+/// An [`UndoStack`] that runs a [`HistoryHook`] around every history event. The hook can be a
+/// concrete type, a tuple of hooks, or a `Box<dyn HistoryHook<T>>` when it must be swapped at
+/// runtime.
+///
+/// Limits are configured on the stack itself before wrapping it, see
+/// [`set_undo_limit`](UndoStack::set_undo_limit), and the entries they drop are reported to the
+/// hook.
+/// ```rust
+/// # use history_stack::{HistoryHook, HookedUndoStack, Metrics, UndoStack};
+/// #[derive(Default)]
+/// struct Dirty(bool);
+///
+/// impl<T> HistoryHook<T> for Dirty {
+///     fn on_save(&mut self, _: &UndoStack<T>) {
+///         self.0 = true;
+///     }
+/// }
+///
+/// let mut stack = UndoStack::new(0u8);
+/// stack.set_undo_limit(Some(1));
+///
+/// let mut undo = HookedUndoStack::from_stack(stack, (Dirty::default(), Metrics::default()));
+///
+/// *undo.save() += 1;
+/// *undo.save() += 1;
+///
+/// let (dirty, metrics) = undo.hook();
+/// assert!(dirty.0);
+/// assert_eq!(metrics.evicted, 1);
+/// ```
+///
+/// Mutating the current value through [`get_mut`](HookedUndoStack::get_mut) is not a history
+/// event, and does not notify the hook.
+#[derive(Clone, Debug, Default)]
+pub struct HookedUndoStack<T, H> {
+    /// The hooked stack
+    stack: UndoStack<T>,
+    /// The hook run around events on stack
+    hook: H,
+}
+
+impl<T, H: HistoryHook<T>> HookedUndoStack<T, H> {
+    /// Creates a new `HookedUndoStack` with a starting value to act as the current value
+    pub fn new(start: T, hook: H) -> Self {
+        Self::from_stack(UndoStack::new(start), hook)
+    }
+
+    /// Wraps an existing `UndoStack`, running `hook` around any further events
+    pub fn from_stack(mut stack: UndoStack<T>, hook: H) -> Self {
+        // entries dropped before wrapping are not events of this hook
+        stack.take_evicted();
+        stack.take_truncated();
+
+        Self { stack, hook }
+    }
+
+    /// Drops the redo history ahead of a save or push and notifies the hook of it
+    fn invalidate_future(&mut self) {
+        let dropped = self.stack.keep_future(0);
+
+        if dropped != 0 {
+            self.hook.on_truncate(&self.stack, dropped);
+        }
+    }
+
+    /// Notifies the hook of the entries the limits of the stack dropped
+    fn report_limits(&mut self) {
+        let dropped = self.stack.take_truncated();
+
+        if dropped != 0 {
+            self.hook.on_truncate(&self.stack, dropped);
+        }
+
+        let evicted = self.stack.take_evicted();
+
+        if evicted != 0 {
+            self.hook
+                .on_evict(&self.stack, &(0..evicted).collect::<Vec<_>>());
+        }
+    }
+
+    /// Saves the current value to history, see [`UndoStack::save`], unless the hook coalesces the
+    /// save into the current value
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        if self.hook.coalesce_save(&self.stack) {
+            return self.stack.get_mut();
+        }
+
+        self.invalidate_future();
+        self.stack.save();
+        self.hook.on_save(&self.stack);
+
+        self.report_limits();
+        self.enforce();
+
+        self.stack.get_mut()
+    }
+
+    /// Pushes a new current value, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        self.invalidate_future();
+        self.stack.push(new_current);
+        self.hook.on_push(&self.stack);
+
+        self.report_limits();
+        self.enforce();
+
+        self.stack.get_mut()
+    }
+
+    /// Undoes to the previous state unless the hook vetoes it, see [`UndoStack::undo`]. A vetoed
+    /// undo returns `Err` like an undo without a previous state, the two can be told apart with
+    /// [`can_undo`](UndoStack::can_undo).
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        if self.hook.allow_undo(&self.stack) && self.stack.undo().is_ok() {
+            self.hook.on_undo(&self.stack);
+            self.report_limits();
+
+            Ok(self.stack.get_mut())
+        } else {
+            Err(self.stack.get_mut())
+        }
+    }
+
+    /// Redoes to the next state unless the hook vetoes it, see [`UndoStack::redo`] and
+    /// [`undo`](HookedUndoStack::undo)
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        if self.hook.allow_redo(&self.stack) && self.stack.redo().is_ok() {
+            self.hook.on_redo(&self.stack);
+            self.report_limits();
+
+            Ok(self.stack.get_mut())
+        } else {
+            Err(self.stack.get_mut())
+        }
+    }
+
+    /// Drops the oldest values until at most `n` remain, see [`UndoStack::keep_last`]
+    pub fn keep_last(&mut self, n: usize) -> usize {
+        let evicted = self.stack.keep_last(n);
+
+        if evicted != 0 {
+            self.hook
+                .on_evict(&self.stack, &(0..evicted).collect::<Vec<_>>());
+        }
+
+        evicted
+    }
+
+    /// Asks the hook which entries to evict and drops them, this happens after every save and
+    /// push, but can be called after the hook was changed so the change takes effect immediately
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn enforce(&mut self) {
+        let current = self.stack.current;
+        let mut evict = self.hook.evict(&self.stack);
+
+        evict.retain(|&i| i < current);
+        evict.sort_unstable();
+        evict.dedup();
+
+        if evict.is_empty() {
+            return;
+        }
+
+        self.stack
+            .clean_retain(|i| evict.binary_search(&i).is_err());
+
+        let mut index = 0;
+        let mut evicting = evict.iter().peekable();
+
+        self.stack.history.retain(|_| {
+            let drop = evicting.next_if_eq(&&index).is_some();
+            index += 1;
+            !drop
+        });
+
+        self.stack.current -= evict.len();
+        self.stack.bump_version();
+
+        self.hook.on_evict(&self.stack, &evict);
+    }
+
+    /// Returns a reference to the hooked stack
+    pub fn stack(&self) -> &UndoStack<T> {
+        &self.stack
+    }
+
+    /// Returns a reference to the hook
+    pub fn hook(&self) -> &H {
+        &self.hook
+    }
+
+    /// Returns a mutable reference to the hook
+    pub fn hook_mut(&mut self) -> &mut H {
+        &mut self.hook
+    }
+
+    /// Replaces the hook, returning the previous one
+    pub fn set_hook(&mut self, hook: H) -> H {
+        core::mem::replace(&mut self.hook, hook)
+    }
+
+    /// Returns the hooked stack and the hook
+    pub fn into_parts(self) -> (UndoStack<T>, H) {
+        (self.stack, self.hook)
+    }
+
+    /// Returns a reference to the current value
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.stack.get()
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        self.stack.get_mut()
+    }
+}
+
+/// Operation counts of an [`UndoStack`], collected by using `Metrics` as the hook of a
+/// [`HookedUndoStack`], see [`MeteredUndoStack`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// Amount of successful saves
+    pub saves: u64,
+    /// Amount of successful pushes
+    pub pushes: u64,
+    /// Amount of successful undos
+    pub undos: u64,
+    /// Amount of successful redos
+    pub redos: u64,
+    /// Amount of times future values were dropped
+    pub truncations: u64,
+    /// Total amount of future values dropped
+    pub truncated: u64,
+    /// Total amount of old values evicted from history
+    pub evicted: u64,
+    /// The greatest history length observed, including the current value
+    pub peak_len: usize,
+    /// The greatest amount of consecutive undos observed, measured as the greatest distance the
+    /// current value has been from the newest value
+    pub peak_undo_depth: usize,
+}
+
+impl Metrics {
+    /// Records the length and position of the stack after an event
+    fn observe<T>(&mut self, stack: &UndoStack<T>) {
+        self.peak_len = self.peak_len.max(stack.len());
+        self.peak_undo_depth = self.peak_undo_depth.max(stack.len() - stack.position() - 1);
+    }
+}
+
+impl<T> HistoryHook<T> for Metrics {
+    fn on_save(&mut self, stack: &UndoStack<T>) {
+        self.saves += 1;
+        self.observe(stack);
+    }
+
+    fn on_push(&mut self, stack: &UndoStack<T>) {
+        self.pushes += 1;
+        self.observe(stack);
+    }
+
+    fn on_undo(&mut self, stack: &UndoStack<T>) {
+        self.undos += 1;
+        self.observe(stack);
+    }
+
+    fn on_redo(&mut self, stack: &UndoStack<T>) {
+        self.redos += 1;
+        self.observe(stack);
+    }
+
+    fn on_truncate(&mut self, _: &UndoStack<T>, dropped: usize) {
+        self.truncations += 1;
+        self.truncated += dropped as u64;
+    }
+
+    fn on_evict(&mut self, _: &UndoStack<T>, evicted: &[usize]) {
+        self.evicted += evicted.len() as u64;
+    }
+}
+
+/// An [`UndoStack`] that counts its operations, see [`Metrics`]
+/// ```rust
+/// # use history_stack::{MeteredUndoStack, Metrics};
+/// let mut undo = MeteredUndoStack::new(0u8, Metrics::default());
+///
+/// undo.save();
+/// undo.save();
+/// undo.undo().unwrap();
+///
+/// assert_eq!(undo.metrics().saves, 2);
+/// assert_eq!(undo.metrics().peak_len, 3);
+/// ```
+pub type MeteredUndoStack<T> = HookedUndoStack<T, Metrics>;
+
+impl<T> HookedUndoStack<T, Metrics> {
+    /// Returns the metrics collected so far
+    #[must_use]
+    pub fn metrics(&self) -> &Metrics {
+        &self.hook
+    }
+
+    /// Resets the collected metrics, returning the previous ones
+    pub fn reset_metrics(&mut self) -> Metrics {
+        core::mem::take(&mut self.hook)
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, H: HistoryHook<T>> ops::Deref for HookedUndoStack<T, H> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, H: HistoryHook<T>> ops::DerefMut for HookedUndoStack<T, H> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn hook_events() {
+    use alloc::vec;
+
+    #[derive(Default)]
+    struct Log(Vec<(&'static str, usize)>);
+
+    impl<T> HistoryHook<T> for Log {
+        fn on_save(&mut self, stack: &UndoStack<T>) {
+            self.0.push(("save", stack.len()));
+        }
+
+        fn on_push(&mut self, stack: &UndoStack<T>) {
+            self.0.push(("push", stack.len()));
+        }
+
+        fn on_undo(&mut self, stack: &UndoStack<T>) {
+            self.0.push(("undo", stack.position()));
+        }
+
+        fn on_redo(&mut self, stack: &UndoStack<T>) {
+            self.0.push(("redo", stack.position()));
+        }
+
+        fn on_truncate(&mut self, stack: &UndoStack<T>, dropped: usize) {
+            self.0.push(("truncate", dropped));
+            self.0.push(("len", stack.len()));
+        }
+
+        fn on_evict(&mut self, _: &UndoStack<T>, evicted: &[usize]) {
+            self.0.push(("evict", evicted.len()));
+        }
+    }
+
+    let mut undo: HookedUndoStack<u8, Box<dyn HistoryHook<u8>>> =
+        HookedUndoStack::new(0, Box::new(()));
+
+    *undo.save() += 1;
+
+    let mut log = Log::default();
+    let mut undo = HookedUndoStack::from_stack(undo.into_parts().0, &mut log);
+
+    undo.save();
+    undo.push(9);
+    assert!(undo.undo().is_ok());
+    assert!(undo.undo().is_ok());
+    assert!(undo.redo().is_ok());
+    undo.save();
+    assert_eq!(undo.keep_last(3), 1);
+    assert_eq!(undo.keep_last(3), 0);
+
+    assert_eq!(
+        log.0,
+        vec![
+            ("save", 3),
+            ("push", 4),
+            ("undo", 2),
+            ("undo", 1),
+            ("redo", 2),
+            ("truncate", 1),
+            ("len", 3),
+            ("save", 4),
+            ("evict", 1),
+        ]
+    );
+
+    // entries dropped by the limits of the stack are reported
+    let mut stack = UndoStack::new(0u8);
+    stack.set_undo_limit(Some(1));
+    stack.set_redo_limit(Some(0));
+
+    let mut log = Log::default();
+    let mut undo = HookedUndoStack::from_stack(stack, &mut log);
+
+    undo.save();
+    undo.save();
+    assert!(undo.undo().is_ok());
+
+    assert_eq!(
+        log.0,
+        vec![
+            ("save", 2),
+            ("save", 2),
+            ("evict", 1),
+            ("undo", 0),
+            ("truncate", 1),
+            ("len", 1),
+        ]
+    );
+}
+
+#[test]
+fn combined_hooks() {
+    struct Veto;
+
+    impl HistoryHook<u8> for Veto {
+        fn allow_undo(&mut self, stack: &UndoStack<u8>) -> bool {
+            stack.position() > 1
+        }
+
+        fn coalesce_save(&mut self, stack: &UndoStack<u8>) -> bool {
+            *stack.get() >= 4
+        }
+
+        fn evict(&mut self, stack: &UndoStack<u8>) -> Vec<usize> {
+            if stack.len() > 3 {
+                alloc::vec![0, 0, 9]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    let mut undo = HookedUndoStack::new(0u8, (Veto, Metrics::default()));
+
+    for i in 1..=5 {
+        *undo.save() = i;
+    }
+
+    // the oldest entry is evicted once history grows past 3 entries, and saves from 4 onwards
+    // are coalesced
+    assert_eq!(undo.stack().len(), 3);
+    assert_eq!(undo.hook().1.evicted, 2);
+    assert_eq!(undo.hook().1.saves, 4);
+
+    *undo.save() = 6;
+    assert_eq!(undo.stack().len(), 3);
+    assert_eq!(undo.undo(), Ok(&mut 3));
+    assert_eq!(undo.undo(), Err(&mut 3));
+    assert!(undo.stack().can_undo());
+    assert_eq!(undo.hook().1.undos, 1);
+}
+
+#[test]
+fn metrics() {
+    let mut undo = MeteredUndoStack::new(0u8, Metrics::default());
+
+    undo.save();
+    undo.push(3);
+    undo.save();
+    assert!(undo.undo().is_ok());
+    assert!(undo.undo().is_ok());
+    assert!(undo.redo().is_ok());
+    undo.save();
+    undo.keep_last(2);
+
+    let metrics = undo.reset_metrics();
+
+    assert_eq!(metrics.saves, 3);
+    assert_eq!(metrics.pushes, 1);
+    assert_eq!(metrics.undos, 2);
+    assert_eq!(metrics.redos, 1);
+    assert_eq!(metrics.truncations, 1);
+    assert_eq!(metrics.truncated, 1);
+    assert_eq!(metrics.evicted, 2);
+    assert_eq!(metrics.peak_len, 4);
+    assert_eq!(metrics.peak_undo_depth, 2);
+
+    assert_eq!(*undo.metrics(), Metrics::default());
+}
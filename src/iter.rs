@@ -0,0 +1,264 @@
+//! Iterating over the entries of an undo stack
+
+#[cfg(feature = "alloc")]
+use alloc::vec::{self, Vec};
+use core::{cmp::Ordering, iter::FusedIterator};
+
+use crate::{HistoryStorage, UndoStack};
+
+/// An entry of history, marked with where it is relative to the current value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Marked<T> {
+    /// An entry before the current value, that can be undone to
+    Past(T),
+    /// The current value
+    Current(T),
+    /// An entry after the current value, that can be redone to
+    Future(T),
+}
+
+impl<T> Marked<T> {
+    /// Marks the entry at `index` relative to the current value at `current`
+    fn new(index: usize, current: usize, value: T) -> Self {
+        match index.cmp(&current) {
+            Ordering::Less => Self::Past(value),
+            Ordering::Equal => Self::Current(value),
+            Ordering::Greater => Self::Future(value),
+        }
+    }
+
+    /// Returns the entry, discarding where it is
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Past(v) | Self::Current(v) | Self::Future(v) => v,
+        }
+    }
+
+    /// Returns `true` if this is the current value
+    #[must_use]
+    pub fn is_current(&self) -> bool {
+        matches!(self, Self::Current(_))
+    }
+}
+
+/// A borrowing iterator over every entry of an [`UndoStack`], oldest first, created by iterating
+/// over `&UndoStack`
+#[derive(Debug)]
+pub struct Iter<'a, T, S> {
+    /// The stack being iterated over
+    stack: &'a UndoStack<T, S>,
+    /// The index of the next entry from the front
+    front: usize,
+    /// The index after the next entry from the back
+    back: usize,
+}
+
+impl<T, S> Clone for Iter<'_, T, S> {
+    fn clone(&self) -> Self {
+        Self {
+            stack: self.stack,
+            front: self.front,
+            back: self.back,
+        }
+    }
+}
+
+impl<'a, T, S: HistoryStorage<T>> Iterator for Iter<'a, T, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.front += 1;
+
+        Some(self.stack.at(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+
+        (len, Some(len))
+    }
+}
+
+impl<T, S: HistoryStorage<T>> DoubleEndedIterator for Iter<'_, T, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+
+        Some(self.stack.at(self.back))
+    }
+}
+
+impl<T, S: HistoryStorage<T>> ExactSizeIterator for Iter<'_, T, S> {}
+
+impl<T, S: HistoryStorage<T>> FusedIterator for Iter<'_, T, S> {}
+
+// an inherent `iter` method would shadow the one of the current value reached through `Deref`
+#[allow(clippy::into_iter_without_iter)]
+impl<'a, T, S: HistoryStorage<T>> IntoIterator for &'a UndoStack<T, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, S>;
+
+    /// Iterates over every entry of history oldest first, including the current value and
+    /// anything after it. Use [`rev`](Iterator::rev) to iterate newest first.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(0u8);
+    /// *undo.save() = 1;
+    /// *undo.save() = 2;
+    /// undo.undo().unwrap();
+    ///
+    /// assert!((&undo).into_iter().eq(&[0, 1, 2]));
+    /// assert!((&undo).into_iter().rev().eq(&[2, 1, 0]));
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        Iter {
+            stack: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+}
+
+/// A consuming iterator over every entry of an [`UndoStack`], oldest first, created by iterating
+/// over `UndoStack`
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    /// The entries left to yield
+    inner: vec::IntoIter<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.inner.next_back()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+#[cfg(feature = "alloc")]
+impl<T> FusedIterator for IntoIter<T> {}
+
+#[cfg(feature = "alloc")]
+impl<T> IntoIterator for UndoStack<T, Vec<T>> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    /// Consumes the stack, iterating over every entry of history oldest first, including the
+    /// current value and anything after it
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.history.into_iter(),
+        }
+    }
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns an iterator over every entry of history oldest first, each [`Marked`] with where it
+    /// is relative to the current value
+    /// ```rust
+    /// # use history_stack::{Marked, UndoStack};
+    /// let mut undo = UndoStack::new('a');
+    /// undo.push('b');
+    /// undo.push('c');
+    /// undo.undo().unwrap();
+    ///
+    /// let rendered: String = undo
+    ///     .iter_marked()
+    ///     .map(|m| match m {
+    ///         Marked::Current(c) => c.to_ascii_uppercase(),
+    ///         other => *other.into_inner(),
+    ///     })
+    ///     .collect();
+    ///
+    /// assert_eq!(rendered, "aBc");
+    /// ```
+    pub fn iter_marked(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = Marked<&T>> + ExactSizeIterator + '_ {
+        let current = self.current;
+
+        self.into_iter()
+            .enumerate()
+            .map(move |(i, v)| Marked::new(i, current, v))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> UndoStack<T, Vec<T>> {
+    /// Consumes the stack, iterating over every entry of history oldest first, each [`Marked`]
+    /// with where it is relative to the current value
+    #[must_use]
+    pub fn into_iter_marked(
+        self,
+    ) -> impl DoubleEndedIterator<Item = Marked<T>> + ExactSizeIterator {
+        let current = self.current;
+
+        self.history
+            .into_iter()
+            .enumerate()
+            .map(move |(i, v)| Marked::new(i, current, v))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn iterators() {
+    let mut undo = UndoStack::new(0u8);
+    for i in 1..=3 {
+        undo.push(i);
+    }
+    undo.set_position(1).unwrap();
+
+    let mut iter = (&undo).into_iter();
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.next(), Some(&0));
+    assert_eq!(iter.next_back(), Some(&3));
+    assert_eq!(iter.clone().len(), 2);
+    assert!(iter.eq(&[1, 2]));
+
+    let mut sum = 0;
+    for v in &undo {
+        sum += v;
+    }
+    assert_eq!(sum, 6);
+
+    assert!(undo
+        .iter_marked()
+        .rev()
+        .map(|m| m.is_current())
+        .eq([false, false, true, false]));
+
+    assert_eq!(
+        undo.clone().into_iter_marked().collect::<Vec<_>>(),
+        [
+            Marked::Past(0),
+            Marked::Current(1),
+            Marked::Future(2),
+            Marked::Future(3)
+        ]
+    );
+    assert_eq!(undo.into_iter().rev().collect::<Vec<_>>(), [3, 2, 1, 0]);
+}
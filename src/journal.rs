@@ -0,0 +1,333 @@
+//! The [`JournalBackend`] trait, and an undo stack that spills old history into one
+
+use alloc::collections::VecDeque;
+#[cfg(feature = "deref")]
+use core::ops;
+
+/// External storage that a [`JournaledUndoStack`] spills its oldest history entries into, such as
+/// a file, flash memory or a database.
+///
+/// The backend holds a contiguous prefix of the history, entry `0` being the oldest. Entries are
+/// always appended at the end, and loaded back from the end before being truncated away, so a
+/// backend can be as simple as an append only log with a length.
+pub trait JournalBackend<T> {
+    /// The error returned when the backend fails
+    type Error;
+
+    /// Appends `entry` to the end of the journal, its index is the amount of entries that were in
+    /// the journal before it
+    ///
+    /// # Errors
+    /// If the entry could not be stored, the journal must be left unchanged
+    fn append(&mut self, entry: &T) -> Result<(), Self::Error>;
+
+    /// Loads the entry at `index`
+    ///
+    /// # Errors
+    /// If the entry could not be loaded
+    fn load(&mut self, index: usize) -> Result<T, Self::Error>;
+
+    /// Drops every entry at or after `len`
+    ///
+    /// # Errors
+    /// If the journal could not be truncated
+    fn truncate(&mut self, len: usize) -> Result<(), Self::Error>;
+}
+
+/// An undo stack that keeps only a window of its most recent history in memory, spilling older
+/// entries into a [`JournalBackend`] and loading them back when they are undone to.
+///
+/// At most `capacity` entries up to and including the current value are kept in memory, entries
+/// that were undone from stay in memory until they are redone to or invalidated, as only the
+/// oldest entries are ever spilled.
+///
+/// Operations that touch the backend return a nested `Result`, where the outer `Err` is a backend
+/// failure and the inner `Result` has the same meaning as for [`UndoStack`](crate::UndoStack).
+/// No history is ever lost to a backend failure.
+/// ```rust
+/// # use history_stack::{JournalBackend, JournaledUndoStack};
+/// # use core::convert::Infallible;
+/// struct Disk(Vec<u32>);
+///
+/// impl JournalBackend<u32> for Disk {
+///     type Error = Infallible;
+///
+///     fn append(&mut self, entry: &u32) -> Result<(), Infallible> {
+///         Ok(self.0.push(*entry))
+///     }
+///
+///     fn load(&mut self, index: usize) -> Result<u32, Infallible> {
+///         Ok(self.0[index])
+///     }
+///
+///     fn truncate(&mut self, len: usize) -> Result<(), Infallible> {
+///         Ok(self.0.truncate(len))
+///     }
+/// }
+///
+/// let mut undo = JournaledUndoStack::new(0u32, Disk(Vec::new()), 2);
+///
+/// for _ in 0..5 {
+///     *undo.save().unwrap() += 1;
+/// }
+///
+/// assert_eq!(undo.backend().0, [0, 1, 2, 3]);
+///
+/// for i in (0..5).rev() {
+///     assert_eq!(*undo.undo().unwrap().unwrap(), i);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct JournaledUndoStack<T, B> {
+    /// The in memory window of history, the entry at index 0 is the entry after the last spilled
+    /// one
+    window: VecDeque<T>,
+    /// Index of the current value within window
+    current: usize,
+    /// Amount of entries held by the backend
+    spilled: usize,
+    /// Maximum amount of entries up to and including current to keep in memory
+    capacity: usize,
+    /// The backend spilled entries are held in
+    backend: B,
+}
+
+impl<T, B: JournalBackend<T>> JournaledUndoStack<T, B> {
+    /// Creates a new `JournaledUndoStack` with a starting value to act as the current value,
+    /// keeping at most `capacity` entries up to and including the current value in memory, and
+    /// spilling older ones into an empty `backend`.
+    ///
+    /// A capacity of `0` is treated as `1`, the current value is always in memory.
+    pub fn new(start: T, backend: B, capacity: usize) -> Self {
+        Self {
+            window: VecDeque::from([start]),
+            current: 0,
+            spilled: 0,
+            capacity: capacity.max(1),
+            backend,
+        }
+    }
+
+    /// Spills the oldest in memory entries until the window fits capacity
+    fn spill(&mut self) -> Result<(), B::Error> {
+        while self.current + 1 > self.capacity {
+            self.backend.append(&self.window[0])?;
+
+            self.window.pop_front();
+            self.spilled += 1;
+            self.current -= 1;
+        }
+
+        Ok(())
+    }
+
+    /// Saves the current value to history, invalidating any future values and spilling the
+    /// oldest entries that no longer fit in memory, see [`UndoStack::save`](crate::UndoStack::save)
+    ///
+    /// # Errors
+    /// If the backend failed to append a spilled entry, the save still happened but more entries
+    /// than capacity are kept in memory until the next successful spill
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> Result<&mut T, B::Error>
+    where
+        T: Clone,
+    {
+        let value = self.window[self.current].clone();
+        self.push(value)
+    }
+
+    /// Pushes a new current value, invalidating any future values and spilling the oldest entries
+    /// that no longer fit in memory, see [`UndoStack::push`](crate::UndoStack::push)
+    ///
+    /// # Errors
+    /// If the backend failed to append a spilled entry, the push still happened but more entries
+    /// than capacity are kept in memory until the next successful spill
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> Result<&mut T, B::Error> {
+        self.window.truncate(self.current + 1);
+        self.window.push_back(new_current);
+        self.current += 1;
+
+        self.spill()?;
+
+        Ok(&mut self.window[self.current])
+    }
+
+    /// Undoes to the previous state, loading it from the backend if it was spilled, see
+    /// [`UndoStack::undo`](crate::UndoStack::undo)
+    ///
+    /// # Errors
+    /// If the backend failed to load or truncate away the previous entry, the stack is unchanged
+    #[allow(clippy::type_complexity)]
+    pub fn undo(&mut self) -> Result<Result<&mut T, &mut T>, B::Error> {
+        if let Some(n) = self.current.checked_sub(1) {
+            self.current = n;
+        } else if let Some(last) = self.spilled.checked_sub(1) {
+            let entry = self.backend.load(last)?;
+            self.backend.truncate(last)?;
+
+            self.window.push_front(entry);
+            self.spilled = last;
+        } else {
+            return Ok(Err(&mut self.window[self.current]));
+        }
+
+        Ok(Ok(&mut self.window[self.current]))
+    }
+
+    /// Redoes to the next state, spilling the oldest entry if it no longer fits in memory, see
+    /// [`UndoStack::redo`](crate::UndoStack::redo)
+    ///
+    /// # Errors
+    /// If the backend failed to append a spilled entry, the redo still happened but more entries
+    /// than capacity are kept in memory until the next successful spill
+    #[allow(clippy::type_complexity)]
+    pub fn redo(&mut self) -> Result<Result<&mut T, &mut T>, B::Error> {
+        if self.current + 1 == self.window.len() {
+            return Ok(Err(&mut self.window[self.current]));
+        }
+
+        self.current += 1;
+        self.spill()?;
+
+        Ok(Ok(&mut self.window[self.current]))
+    }
+
+    /// Returns the amount of values in history including spilled ones and the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.spilled + self.window.len()
+    }
+
+    /// Always returns `false`, as the in memory window always holds the current value
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in the whole history, including spilled entries
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.spilled + self.current
+    }
+
+    /// Returns the amount of entries held by the backend
+    #[must_use]
+    pub fn spilled(&self) -> usize {
+        self.spilled
+    }
+
+    /// Returns a reference to the backend
+    #[must_use]
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Returns a reference to the current value, which is always within the in memory window
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.window[self.current]
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.window[self.current]
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, B: JournalBackend<T>> ops::Deref for JournaledUndoStack<T, B> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, B: JournalBackend<T>> ops::DerefMut for JournaledUndoStack<T, B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn journaled_undo_stack() {
+    use alloc::vec::Vec;
+
+    struct Flaky {
+        entries: Vec<u8>,
+        fail: bool,
+    }
+
+    impl JournalBackend<u8> for Flaky {
+        type Error = ();
+
+        fn append(&mut self, entry: &u8) -> Result<(), ()> {
+            if self.fail {
+                return Err(());
+            }
+            self.entries.push(*entry);
+            Ok(())
+        }
+
+        fn load(&mut self, index: usize) -> Result<u8, ()> {
+            if self.fail {
+                return Err(());
+            }
+            Ok(self.entries[index])
+        }
+
+        fn truncate(&mut self, len: usize) -> Result<(), ()> {
+            self.entries.truncate(len);
+            Ok(())
+        }
+    }
+
+    let backend = Flaky {
+        entries: Vec::new(),
+        fail: false,
+    };
+    let mut undo = JournaledUndoStack::new(0u8, backend, 2);
+
+    for i in 1..=4 {
+        undo.push(i).unwrap();
+    }
+
+    assert_eq!(undo.spilled(), 3);
+    assert_eq!(undo.len(), 5);
+
+    assert_eq!(undo.undo(), Ok(Ok(&mut 3)));
+    assert_eq!(undo.undo(), Ok(Ok(&mut 2)));
+    assert_eq!(undo.undo(), Ok(Ok(&mut 1)));
+    assert_eq!(undo.position(), 1);
+
+    undo.backend.fail = true;
+    assert_eq!(undo.undo(), Err(()));
+    assert_eq!(*undo.get(), 1);
+    undo.backend.fail = false;
+
+    assert_eq!(undo.undo(), Ok(Ok(&mut 0)));
+    assert_eq!(undo.undo(), Ok(Err(&mut 0)));
+
+    for mut i in 1..=4 {
+        assert_eq!(undo.redo(), Ok(Ok(&mut i)));
+    }
+
+    assert_eq!(undo.redo(), Ok(Err(&mut 4)));
+    assert_eq!(undo.spilled(), 3);
+
+    undo.backend.fail = true;
+    assert_eq!(undo.push(5), Err(()));
+    assert_eq!(undo.len(), 6);
+    assert_eq!(undo.spilled(), 3);
+    undo.backend.fail = false;
+
+    undo.push(6).unwrap();
+    assert_eq!(undo.spilled(), 5);
+    assert_eq!(undo.backend.entries, [0, 1, 2, 3, 4]);
+}
@@ -0,0 +1,258 @@
+//! An undo stack that defers cloning on save until the current value is mutated
+
+#[cfg(feature = "deref")]
+use core::ops;
+use core::{cmp, fmt, hash};
+
+use alloc::rc::Rc;
+
+use crate::UndoStack;
+
+/// A variant of [`UndoStack`] that does not clone on [`save`](LazyUndoStack::save), instead
+/// sharing the saved state with the current value until the current value is first mutated
+/// through [`get_mut`](LazyUndoStack::get_mut).
+///
+/// Saves that are never followed by a mutation never clone, which makes saving "just in case"
+/// cheap.
+/// ```rust
+/// # use history_stack::LazyUndoStack;
+/// let mut undo = LazyUndoStack::new(vec![1, 2, 3]);
+///
+/// // no clone of the vec happens here
+/// undo.save();
+///
+/// // the vec is cloned here, as the saved state still shares it
+/// undo.get_mut().push(4);
+///
+/// assert_eq!(*undo.undo().unwrap(), [1, 2, 3]);
+/// ```
+///
+/// `LazyUndoStack` is also "transparently T", meaning the default traits it implements all act
+/// like the current value of T, so hashing `LazyUndoStack<T>` and T produce the same hash, Eq and
+/// Ord work the same etc. This also includes `Display`, but does not include `Debug`.
+#[derive(Clone, Debug)]
+pub struct LazyUndoStack<T> {
+    /// The underlying stack, each entry is shared with its neighbours until it is mutated
+    inner: UndoStack<Rc<T>>,
+}
+
+impl<T: Default> Default for LazyUndoStack<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for LazyUndoStack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl<T> LazyUndoStack<T> {
+    /// Creates a new `LazyUndoStack` with a starting value to act as the current value
+    pub fn new(start: T) -> Self {
+        Self {
+            inner: UndoStack::new(Rc::new(start)),
+        }
+    }
+
+    /// Saves the current T to history and invalidates any data that may be used to redo, without
+    /// cloning the current T. This will [`Drop`] any T that exist later in history than the
+    /// current edit point.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) {
+        self.inner.save();
+    }
+
+    /// Pushes the given value to the stack, making it the new current value and invalidating
+    /// future history, returns a reference to the new current value
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        match Rc::get_mut(self.inner.push(Rc::new(new_current))) {
+            Some(v) => v,
+            None => unreachable!("LazyUndoStack: newly pushed value was shared"),
+        }
+    }
+
+    /// If there is a previous state in the history stack, backtrack to that and return `Ok(&T)`
+    /// to the new current value, otherwise return `Err(&T)` to the unchanged current value.
+    ///
+    /// Unlike [`UndoStack::undo`] this only hands out a shared reference, so that states that are
+    /// only looked at are never cloned.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&T, &T> {
+        match self.inner.undo() {
+            Ok(v) => Ok(&**v),
+            Err(v) => Err(&**v),
+        }
+    }
+
+    /// If there is a future state in the history stack that has been undone from, redo to that
+    /// position and return `Ok(&T)` of the new current value after advancing, else return
+    /// `Err(&T)` of the current unchanged value, if there was no future history.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&T, &T> {
+        match self.inner.redo() {
+            Ok(v) => Ok(&**v),
+            Err(v) => Err(&**v),
+        }
+    }
+
+    /// Returns `true` if there is a previous state to [`undo`](LazyUndoStack::undo) to
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        self.inner.can_undo()
+    }
+
+    /// Returns `true` if there is a future state to [`redo`](LazyUndoStack::redo) to
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        self.inner.can_redo()
+    }
+
+    /// Gets a reference to the current value
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.inner.get()
+    }
+
+    /// Gets a mutable reference to the current value, cloning it first if it is still shared with
+    /// another entry in history
+    pub fn get_mut(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        Rc::make_mut(self.inner.get_mut())
+    }
+
+    /// Creates a child stack sharing the current value, see [`UndoStack::fork`]. No clone happens
+    /// until the current value of either stack is mutated.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn fork(&self) -> Self {
+        Self {
+            inner: self.inner.fork(),
+        }
+    }
+
+    /// Adopts the timeline of a child created by [`fork`](LazyUndoStack::fork), see
+    /// [`UndoStack::merge_back`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn merge_back(&mut self, child: Self) {
+        self.inner.merge_back(child.inner);
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::Deref for LazyUndoStack<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T: Clone> ops::DerefMut for LazyUndoStack<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+impl<T: PartialEq> PartialEq<T> for LazyUndoStack<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.get() == other
+    }
+}
+
+impl<T: PartialEq> PartialEq for LazyUndoStack<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Eq> Eq for LazyUndoStack<T> {}
+
+impl<T: PartialOrd> PartialOrd for LazyUndoStack<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.get().partial_cmp(other.get())
+    }
+}
+
+impl<T: PartialOrd> PartialOrd<T> for LazyUndoStack<T> {
+    fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+        self.get().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for LazyUndoStack<T> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.get().cmp(other.get())
+    }
+}
+
+impl<T: hash::Hash> hash::Hash for LazyUndoStack<T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
+
+#[test]
+fn lazy_undo_stack() {
+    use core::cell::Cell;
+
+    #[derive(Debug)]
+    struct Counted<'a>(&'a Cell<u8>, u8);
+
+    impl Clone for Counted<'_> {
+        fn clone(&self) -> Self {
+            self.0.set(self.0.get() + 1);
+            Self(self.0, self.1)
+        }
+    }
+
+    let clones = Cell::new(0);
+    let mut undo = LazyUndoStack::new(Counted(&clones, 0));
+
+    undo.save();
+    undo.save();
+    assert_eq!(clones.get(), 0);
+
+    undo.get_mut().1 += 1;
+    assert_eq!(clones.get(), 1);
+
+    assert_eq!(undo.undo().unwrap().1, 0);
+    assert_eq!(undo.redo().unwrap().1, 1);
+    assert_eq!(clones.get(), 1);
+}
+
+#[test]
+fn lazy_fork() {
+    let undo = LazyUndoStack::new(alloc::vec![1, 2, 3]);
+    let mut child = undo.fork();
+
+    assert!(Rc::ptr_eq(undo.inner.get(), child.inner.get()));
+
+    child.get_mut().push(4);
+    assert_eq!(undo.get(), &[1, 2, 3]);
+}
+
+#[test]
+fn lazy_push_without_clone() {
+    #[derive(Debug, PartialEq)]
+    struct Unique(u8);
+
+    let mut undo = LazyUndoStack::new(Unique(0));
+
+    undo.push(Unique(1)).0 += 1;
+    assert_eq!(undo.get(), &Unique(2));
+    assert_eq!(undo.undo(), Ok(&Unique(0)));
+}
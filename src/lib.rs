@@ -2,45 +2,301 @@
 //! state and reversible computations
 
 #![no_std]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![forbid(unsafe_code)]
 #![warn(clippy::alloc_instead_of_core, clippy::std_instead_of_alloc)]
 #![warn(clippy::pedantic, clippy::cargo)]
 #![allow(clippy::module_name_repetitions)]
 #![warn(missing_docs, clippy::missing_docs_in_private_items)]
+#[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
-use core::{cmp, fmt, hash, ops};
+use core::{borrow, cmp, fmt, hash, marker::PhantomData, ops};
 
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
-/// A wrapper over a `T` that provides a primitive history mechanism by use of a stack of `T`. It
-/// can be pushed to or popped from to save the current value or pop out a previously saved value
-/// in LIFO (stack) order.
-///
-/// `HistoryStack` is also "transparently T", meaning the default traits it implements all act like
-/// the current value of T, so hashing `HistoryStack<T>` and T produce the same hash, Eq and Ord work
-/// the same etc. This also includes `Display`, but does not include `Debug`.
-#[derive(Clone, Default, Debug)]
-pub struct HistoryStack<T> {
-    /// The history stack, this starts out empty and should only be modified via pushing and popping
-    stack: Vec<T>,
-    /// The current value, since `HistoryStack<T>` acts like a T, this is always initialized to
-    /// some value
-    current: T,
-}
-
-impl<T: fmt::Display> fmt::Display for HistoryStack<T> {
+/// Declares a stack type whose storage defaults to [`Vec`] when the `alloc` feature is enabled,
+/// and has no default otherwise
+macro_rules! default_storage {
+    ($(#[$attr:meta])* pub struct $name:ident<T, S> $body:tt) => {
+        #[cfg(feature = "alloc")]
+        $(#[$attr])*
+        pub struct $name<T, S = Vec<T>> $body
+
+        #[cfg(not(feature = "alloc"))]
+        $(#[$attr])*
+        pub struct $name<T, S> $body
+    };
+}
+
+#[cfg(feature = "alloc")]
+mod adaptive;
+#[cfg(feature = "alloc")]
+mod annotated;
+#[cfg(feature = "alloc")]
+mod autosave;
+mod batch;
+#[cfg(feature = "alloc")]
+mod branch;
+#[cfg(feature = "std")]
+mod channel;
+mod clean;
+#[cfg(feature = "alloc")]
+mod coalesce;
+#[cfg(feature = "alloc")]
+mod codec;
+#[cfg(feature = "alloc")]
+mod collab;
+#[cfg(feature = "alloc")]
+mod compact;
+mod compare;
+#[cfg(feature = "std")]
+mod concurrent;
+#[cfg(feature = "alloc")]
+mod cursor;
+#[cfg(feature = "alloc")]
+mod diff;
+#[cfg(feature = "alloc")]
+mod erased;
+#[cfg(feature = "alloc")]
+mod export;
+#[cfg(feature = "alloc")]
+mod fork;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+mod frozen;
+mod full;
+#[cfg(feature = "alloc")]
+mod generation;
+#[cfg(feature = "alloc")]
+mod guard;
+mod history;
+#[cfg(feature = "alloc")]
+mod hook;
+mod iter;
+#[cfg(feature = "alloc")]
+mod journal;
+#[cfg(feature = "alloc")]
+mod lazy;
+mod limits;
+#[cfg(feature = "alloc")]
+mod list;
+#[cfg(feature = "alloc")]
+mod memory;
+#[cfg(feature = "alloc")]
+mod merge;
+#[cfg(feature = "alloc")]
+mod mergeable;
+#[cfg(feature = "operators")]
+mod operators;
+#[cfg(feature = "alloc")]
+mod oplog;
+#[cfg(feature = "alloc")]
+mod persist;
+#[cfg(feature = "alloc")]
+mod policy;
+mod position;
+#[cfg(feature = "alloc")]
+mod prune;
+#[cfg(feature = "alloc")]
+mod range;
+#[cfg(feature = "alloc")]
+mod rc;
+mod rebase;
+#[cfg(feature = "alloc")]
+mod scoped;
+#[cfg(feature = "alloc")]
+mod script;
+mod search;
+#[cfg(feature = "std")]
+mod shared;
+mod snapshot;
+mod storage;
+mod summary;
+#[cfg(feature = "alloc")]
+mod sync;
+#[cfg(feature = "text-diff")]
+mod text;
+#[cfg(feature = "alloc")]
+mod thin;
+#[cfg(feature = "alloc")]
+mod time;
+mod timeline;
+mod transaction;
+mod view;
+
+#[cfg(feature = "alloc")]
+pub use adaptive::AdaptiveUndoStack;
+#[cfg(feature = "alloc")]
+pub use annotated::{AnnotatedUndoStack, Savepoint, Tags};
+#[cfg(feature = "alloc")]
+pub use autosave::AutoSaver;
+#[cfg(feature = "alloc")]
+pub use branch::{BranchId, BranchingUndoStack};
+#[cfg(feature = "std")]
+pub use channel::HistoryEvent;
+pub use clean::CleanState;
+#[cfg(feature = "alloc")]
+pub use coalesce::CoalescingUndoStack;
+#[cfg(feature = "alloc")]
+pub use codec::{Codec, CompressedUndoStack};
+#[cfg(feature = "alloc")]
+pub use collab::{CollabUndoStack, Resolver};
+#[cfg(feature = "alloc")]
+pub use compact::CompactingUndoStack;
+#[cfg(feature = "std")]
+pub use concurrent::{locked_history, HistoryReader, HistorySnapshot, HistoryWriter};
+#[cfg(feature = "alloc")]
+pub use cursor::HistoryCursor;
+#[cfg(feature = "alloc")]
+pub use diff::{Diff, Patch, Projections};
+#[cfg(feature = "alloc")]
+pub use erased::HistoryOps;
+#[cfg(feature = "alloc")]
+pub use export::{Exported, FromPartsError, ImportError};
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use frozen::FrozenHistory;
+pub use full::FullCmp;
+#[cfg(feature = "alloc")]
+pub use generation::{Generation, GenerationalUndoStack};
+#[cfg(feature = "alloc")]
+pub use guard::RepeatGuard;
+pub use history::History;
+#[cfg(feature = "derive")]
+pub use history_stack_derive::{Historied, Snapshot};
+#[cfg(feature = "alloc")]
+pub use hook::{HistoryHook, HookedUndoStack, MeteredUndoStack, Metrics};
+#[cfg(feature = "alloc")]
+pub use iter::IntoIter;
+pub use iter::{Iter, Marked};
+#[cfg(feature = "alloc")]
+pub use journal::{JournalBackend, JournaledUndoStack};
+#[cfg(feature = "alloc")]
+pub use lazy::LazyUndoStack;
+#[cfg(feature = "alloc")]
+pub use list::{HistoryListModel, HistoryRow, RowChange};
+#[cfg(feature = "alloc")]
+pub use memory::MemoryReport;
+#[cfg(feature = "alloc")]
+pub use mergeable::Mergeable;
+#[cfg(feature = "alloc")]
+pub use oplog::{LoggedOp, Op, OpLog, SequenceError};
+#[cfg(feature = "alloc")]
+pub use persist::PersistedUndoStack;
+#[cfg(feature = "alloc")]
+pub use policy::{CountLimit, EvictionPolicy, PinToken, PolicyHook, PolicyUndoStack, SizeLimit};
+pub use position::OutOfRange;
+#[cfg(feature = "alloc")]
+pub use scoped::ScopedUndoStack;
+#[cfg(feature = "alloc")]
+pub use script::Script;
+#[cfg(feature = "std")]
+pub use shared::SharedUndoStack;
+pub use snapshot::Snapshot;
+pub use storage::{ArrayStorage, HistoryStorage};
+pub use summary::HistorySummary;
+#[cfg(feature = "alloc")]
+pub use sync::{SyncDelta, SyncError, SyncedUndoStack};
+#[cfg(feature = "text-diff")]
+pub use text::StringUndoStack;
+#[cfg(feature = "std")]
+pub use time::StdClock;
+#[cfg(feature = "alloc")]
+pub use time::{Clock, CoalesceWindow, TimedUndoStack};
+pub use timeline::Timeline;
+pub use transaction::UnwindPolicy;
+pub use view::{FutureView, PastView};
+
+default_storage! {
+    /// A wrapper over a `T` that provides a primitive history mechanism by use of a stack of `T`.
+    /// It can be pushed to or popped from to save the current value or pop out a previously saved
+    /// value in LIFO (stack) order.
+    ///
+    /// `HistoryStack` is also "transparently T", meaning the default traits it implements all act
+    /// like the current value of T, so hashing `HistoryStack<T>` and T produce the same hash, Eq
+    /// and Ord work the same etc. This also includes `Display`, but does not include `Debug`.
+    ///
+    /// The history is held in a [`Vec`] by default, but any [`HistoryStorage`] may be used instead
+    /// via [`with_storage`](HistoryStack::with_storage).
+    #[derive(Default, Debug)]
+    pub struct HistoryStack<T, S> {
+        /// The history stack, this starts out empty and should only be modified via pushing and
+        /// popping
+        stack: S,
+        /// The current value, since `HistoryStack<T>` acts like a T, this is always initialized to
+        /// some value
+        current: T,
+        /// Whether a panic unwound through a transaction, leaving the current value in an unknown
+        /// state
+        poisoned: bool,
+        /// What happens when a panic unwinds through a transaction
+        unwind: UnwindPolicy,
+    }
+}
+
+impl<T: Clone, S: Clone> Clone for HistoryStack<T, S> {
+    fn clone(&self) -> Self {
+        Self {
+            stack: self.stack.clone(),
+            current: self.current.clone(),
+            poisoned: self.poisoned,
+            unwind: self.unwind,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.stack.clone_from(&source.stack);
+        self.current.clone_from(&source.current);
+        self.poisoned = source.poisoned;
+        self.unwind = source.unwind;
+    }
+}
+
+impl<T: fmt::Display, S> fmt::Display for HistoryStack<T, S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.current.fmt(f)
     }
 }
 
+impl<T, S: HistoryStorage<T> + Default> From<T> for HistoryStack<T, S> {
+    fn from(v: T) -> Self {
+        Self::with_storage(v, S::default())
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl<T> HistoryStack<T> {
     /// Create a new `HistoryStack` whose current value is set to `v`, with no history
     pub const fn new(v: T) -> Self {
         Self {
             stack: Vec::new(),
             current: v,
+            poisoned: false,
+            unwind: UnwindPolicy::Poison,
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T, A: core::alloc::Allocator> HistoryStack<T, Vec<T, A>> {
+    /// Create a new `HistoryStack` whose current value is set to `v`, with no history, allocating
+    /// its history with `alloc`
+    pub fn new_in(v: T, alloc: A) -> Self {
+        Self::with_storage(v, Vec::new_in(alloc))
+    }
+}
+
+impl<T, S: HistoryStorage<T>> HistoryStack<T, S> {
+    /// Create a new `HistoryStack` whose current value is set to `v`, using `storage` as its initial
+    /// history. Values already held in `storage` can be popped back to in newest to oldest order.
+    pub fn with_storage(v: T, storage: S) -> Self {
+        Self {
+            stack: storage,
+            current: v,
+            poisoned: false,
+            unwind: UnwindPolicy::Poison,
         }
     }
 
@@ -62,6 +318,56 @@ impl<T> HistoryStack<T> {
         self.stack.push(core::mem::replace(&mut self.current, v));
     }
 
+    /// Pushes every value as the new current value in order, like calling
+    /// [`push_value`](HistoryStack::push_value) for each of them, reserving room in the stack for
+    /// them up front. This is useful to replay a recorded sequence of values into a stack.
+    /// ```rust
+    /// # use history_stack::HistoryStack;
+    /// let mut hs = HistoryStack::new(0u8);
+    /// hs.push_many([1, 2, 3]);
+    ///
+    /// assert_eq!(*hs.get(), 3);
+    /// assert_eq!(hs.pop(), Some(3));
+    /// assert_eq!(*hs.get(), 2);
+    /// ```
+    pub fn push_many(&mut self, values: impl IntoIterator<Item = T>) {
+        let values = values.into_iter();
+        self.stack.reserve(values.size_hint().0);
+
+        for v in values {
+            self.push_value(v);
+        }
+    }
+
+    /// Returns an iterator over the current value and every value held in the stack along with
+    /// their depth from the current value, starting with the current value at depth `0` followed
+    /// by the value [`pop`](HistoryStack::pop) would return at depth `1`, and so on.
+    /// ```rust
+    /// # use history_stack::HistoryStack;
+    /// let mut hs = HistoryStack::new("draft");
+    /// hs.push_value("edited");
+    /// hs.push_value("final");
+    ///
+    /// let labels: Vec<_> = hs
+    ///     .entries()
+    ///     .map(|(depth, v)| match depth {
+    ///         0 => format!("{v} (current)"),
+    ///         1 => format!("{v}, 1 step ago"),
+    ///         n => format!("{v}, {n} steps ago"),
+    ///     })
+    ///     .collect();
+    ///
+    /// assert_eq!(labels, ["final (current)", "edited, 1 step ago", "draft, 2 steps ago"]);
+    /// ```
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = (usize, &T)> {
+        let len = self.stack.len();
+
+        (0..=len).filter_map(move |depth| match depth {
+            0 => Some((0, &self.current)),
+            _ => self.stack.get(len - depth).map(|v| (depth, v)),
+        })
+    }
+
     /// Makes a [`Clone::clone`] of the current value and pushes it to the stack, leaving the
     /// current value untouched
     pub fn push(&mut self)
@@ -72,7 +378,45 @@ impl<T> HistoryStack<T> {
     }
 }
 
-impl<T> ops::Deref for HistoryStack<T> {
+impl<T, S> HistoryStack<T, S> {
+    /// Returns a reference to the current value, which is also what [`Deref`](ops::Deref)
+    /// resolves to when the `deref` feature is enabled
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.current
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.current
+    }
+
+    /// Returns a reference to the current value, the same as [`get`](HistoryStack::get)
+    #[must_use]
+    pub fn current(&self) -> &T {
+        &self.current
+    }
+
+    /// Returns a mutable reference to the current value, the same as
+    /// [`get_mut`](HistoryStack::get_mut)
+    pub fn current_mut(&mut self) -> &mut T {
+        &mut self.current
+    }
+
+    /// Exchanges the current values of `self` and `other` without cloning either, leaving the
+    /// stack of both untouched
+    pub fn swap_current<S2>(&mut self, other: &mut HistoryStack<T, S2>) {
+        core::mem::swap(&mut self.current, &mut other.current);
+    }
+
+    /// Exchanges the current value with `value` in place, like [`core::mem::swap`]
+    pub fn swap_value(&mut self, value: &mut T) {
+        core::mem::swap(&mut self.current, value);
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, S> ops::Deref for HistoryStack<T, S> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -80,115 +424,256 @@ impl<T> ops::Deref for HistoryStack<T> {
     }
 }
 
-impl<T> ops::DerefMut for HistoryStack<T> {
+#[cfg(feature = "deref")]
+impl<T, S> ops::DerefMut for HistoryStack<T, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.current
     }
 }
 
-impl<T: PartialEq> PartialEq<T> for HistoryStack<T> {
+impl<T, S> AsRef<T> for HistoryStack<T, S> {
+    fn as_ref(&self) -> &T {
+        &self.current
+    }
+}
+
+impl<T, S> AsMut<T> for HistoryStack<T, S> {
+    fn as_mut(&mut self) -> &mut T {
+        &mut self.current
+    }
+}
+
+impl<T, S> borrow::Borrow<T> for HistoryStack<T, S> {
+    fn borrow(&self) -> &T {
+        &self.current
+    }
+}
+
+impl<T, S> borrow::BorrowMut<T> for HistoryStack<T, S> {
+    fn borrow_mut(&mut self) -> &mut T {
+        &mut self.current
+    }
+}
+
+impl<T: fmt::Write, S> fmt::Write for HistoryStack<T, S> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.current.write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.current.write_char(c)
+    }
+}
+
+impl<T: Extend<A>, S, A> Extend<A> for HistoryStack<T, S> {
+    fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) {
+        self.current.extend(iter);
+    }
+}
+
+impl<T: ops::Index<I>, S, I> ops::Index<I> for HistoryStack<T, S> {
+    type Output = T::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.current[index]
+    }
+}
+
+impl<T: ops::IndexMut<I>, S, I> ops::IndexMut<I> for HistoryStack<T, S> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.current[index]
+    }
+}
+
+impl<T: PartialEq, S> PartialEq<T> for HistoryStack<T, S> {
     fn eq(&self, other: &T) -> bool {
         &self.current == other
     }
 }
 
-impl<T: PartialEq> PartialEq for HistoryStack<T> {
+impl<T: PartialEq, S> PartialEq for HistoryStack<T, S> {
     fn eq(&self, other: &Self) -> bool {
         self.current == other.current
     }
 }
 
-impl<T: Eq> Eq for HistoryStack<T> {}
+impl<T: Eq, S> Eq for HistoryStack<T, S> {}
 
-impl<T: PartialOrd> PartialOrd for HistoryStack<T> {
+impl<T: PartialOrd, S> PartialOrd for HistoryStack<T, S> {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         self.current.partial_cmp(&other.current)
     }
 }
 
-impl<T: PartialOrd> PartialOrd<T> for HistoryStack<T> {
+impl<T: PartialOrd, S> PartialOrd<T> for HistoryStack<T, S> {
     fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
         self.current.partial_cmp(other)
     }
 }
 
-impl<T: Ord> Ord for HistoryStack<T> {
+impl<T: Ord, S> Ord for HistoryStack<T, S> {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
         self.current.cmp(&other.current)
     }
 }
 
-impl<T: hash::Hash> hash::Hash for HistoryStack<T> {
+impl<T: hash::Hash, S> hash::Hash for HistoryStack<T, S> {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         self.current.hash(state);
     }
 }
 
-/// A structure which allows you to undo and redo changes based on saved states of `T`.
-///
-/// To use, simply [`save`](UndoStack::save), [`undo`](UndoStack::undo), and
-/// [`redo`](UndoStack::redo) later if needed;
-/// ```rust
-/// # use history_stack::UndoStack;
-/// // Create with initial state
-/// let mut undo = UndoStack::new(5u8);
-///
-/// // make a savepoint and get a reference to the new current value
-/// // our stack looks like [5, 5] currently, our current value being the second
-/// let newref = undo.save();
-///
-/// // we modified the new current value, our stack looks like [5, 10] now
-/// *newref *= 2;
-///
-/// // but we made a mistake! we want to go back now, and since we are
-/// // sure we saved earlier we can unwrap here to get the Ok variant
-/// // our stack still looks like [5, 10], but now we point to the 5
-/// let oldref = undo.undo().unwrap();
-///
-/// // turns out it wasnt a mistake, lets redo and unwrap to be sure we got the newer value
-/// undo.redo().unwrap();
-///
-/// // UndoStack implements Deref and DerefMut, we can make sure we got the new value like this
-/// assert_eq!(undo, 10);
-/// ```
-///
-/// This is useful when you want to be able to make changes in a way where you can undo a change,
-/// and then reapply it later, but do not wish to write a complex incremental structure that could
-/// track changes like that. This type provides a generic (read: you can use it on anything)
-/// interface to achieve that effect, even if it may use more memory than a more targeted approach.
-///
-/// `UndoStack` is also "transparently T", meaning the default traits it implements all act like
-/// the current value of T, so hashing `UndoStack<T>` and T produce the same hash, Eq and Ord work
-/// the same etc. This also includes `Display`, but does not include `Debug`.
-#[derive(Clone, Debug)]
-pub struct UndoStack<T> {
-    /// History of the undostack that includes the current value somewhere within
-    history: Vec<T>,
-    /// Index into history that represents the current value
-    current: usize,
-}
-
-impl<T: Default> Default for UndoStack<T> {
+default_storage! {
+    /// A structure which allows you to undo and redo changes based on saved states of `T`.
+    ///
+    /// To use, simply [`save`](UndoStack::save), [`undo`](UndoStack::undo), and
+    /// [`redo`](UndoStack::redo) later if needed;
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// // Create with initial state
+    /// let mut undo = UndoStack::new(5u8);
+    ///
+    /// // make a savepoint and get a reference to the new current value
+    /// // our stack looks like [5, 5] currently, our current value being the second
+    /// let newref = undo.save();
+    ///
+    /// // we modified the new current value, our stack looks like [5, 10] now
+    /// *newref *= 2;
+    ///
+    /// // but we made a mistake! we want to go back now, and since we are
+    /// // sure we saved earlier we can unwrap here to get the Ok variant
+    /// // our stack still looks like [5, 10], but now we point to the 5
+    /// let oldref = undo.undo().unwrap();
+    ///
+    /// // turns out it wasnt a mistake, lets redo and unwrap to be sure we got the newer value
+    /// undo.redo().unwrap();
+    ///
+    /// // UndoStack implements Deref and DerefMut, we can make sure we got the new value like this
+    /// assert_eq!(undo, 10);
+    /// ```
+    ///
+    /// This is useful when you want to be able to make changes in a way where you can undo a
+    /// change, and then reapply it later, but do not wish to write a complex incremental structure
+    /// that could track changes like that. This type provides a generic (read: you can use it on
+    /// anything) interface to achieve that effect, even if it may use more memory than a more
+    /// targeted approach.
+    ///
+    /// `UndoStack` is also "transparently T", meaning the default traits it implements all act like
+    /// the current value of T, so hashing `UndoStack<T>` and T produce the same hash, Eq and Ord
+    /// work the same etc. This also includes `Display`, but does not include `Debug`.
+    ///
+    /// The history is held in a [`Vec`] by default, but any [`HistoryStorage`] may be used instead
+    /// via [`with_storage`](UndoStack::with_storage).
+    #[derive(Debug)]
+    pub struct UndoStack<T, S> {
+        /// History of the undostack that includes the current value somewhere within
+        history: S,
+        /// Index into history that represents the current value
+        current: usize,
+        /// Index into history of the state marked clean, `None` if that state was dropped
+        clean: Option<usize>,
+        /// Counts every change to the position or entries of history, wrapping on overflow
+        version: u64,
+        /// Whether a batch edit is running, turning saves and pushes into edits of the current
+        /// value
+        batching: bool,
+        /// Undo and redo limits enforced whenever the position or entries of history change
+        limits: limits::Limits<T>,
+        /// Marks that the storage holds values of `T`
+        marker: PhantomData<T>,
+    }
+}
+
+impl<T: Default, S: HistoryStorage<T> + Default> Default for UndoStack<T, S> {
     fn default() -> Self {
+        Self::with_storage(T::default(), S::default())
+    }
+}
+
+impl<T: Clone, S: Clone> Clone for UndoStack<T, S> {
+    fn clone(&self) -> Self {
         Self {
-            history: alloc::vec![T::default()],
-            current: 0,
+            history: self.history.clone(),
+            current: self.current,
+            clean: self.clean,
+            version: self.version,
+            batching: false,
+            limits: self.limits,
+            marker: PhantomData,
         }
     }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.history.clone_from(&source.history);
+        self.current = source.current;
+        self.clean = source.clean;
+        self.version = source.version;
+        self.batching = false;
+        self.limits = source.limits;
+    }
 }
 
-impl<T: fmt::Display> fmt::Display for UndoStack<T> {
+impl<T: fmt::Display, S: HistoryStorage<T>> fmt::Display for UndoStack<T, S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.inner().fmt(f)
     }
 }
 
+impl<T, S: HistoryStorage<T> + Default> From<T> for UndoStack<T, S> {
+    fn from(start: T) -> Self {
+        Self::with_storage(start, S::default())
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl<T> UndoStack<T> {
     /// Creates a new `UndoStack` with a starting value to act as the current value
     pub fn new(start: T) -> Self {
         Self {
             history: alloc::vec![start],
             current: 0,
+            clean: Some(0),
+            version: 0,
+            batching: false,
+            limits: limits::Limits::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T, A: core::alloc::Allocator> UndoStack<T, Vec<T, A>> {
+    /// Creates a new `UndoStack` with a starting value to act as the current value, allocating its
+    /// history with `alloc`
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn new_in(start: T, alloc: A) -> Self {
+        Self::with_storage(start, Vec::new_in(alloc))
+    }
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Creates a new `UndoStack` with a starting value to act as the current value, using `storage`
+    /// to hold its history. Values already held in `storage` are kept as undo history, oldest
+    /// first, so the new stack can immediately undo back through them.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn with_storage(start: T, mut storage: S) -> Self {
+        storage.push(start);
+
+        // storage is nonempty after pushing start, so this cannot underflow
+        let current = storage.len() - 1;
+
+        Self {
+            current,
+            clean: Some(current),
+            version: 0,
+            batching: false,
+            limits: limits::Limits::default(),
+            history: storage,
+            marker: PhantomData,
         }
     }
 
@@ -200,6 +685,8 @@ impl<T> UndoStack<T> {
         if self.current + 1 != self.history.len() {
             // see above for +1 safety
             self.history.truncate(self.current + 1);
+            self.clean_truncated(self.current + 1);
+            self.bump_version();
         }
     }
 
@@ -210,14 +697,16 @@ impl<T> UndoStack<T> {
 
         // +1 safety: current is always less than history.len(), which would panic on overflow
         self.current += 1;
+        self.bump_version();
 
-        &mut self.history[self.current]
+        self.at_mut(self.current)
     }
 
     /// Saves the current T to history and invalidates any data that may be used to redo
     /// This will [`Drop`] any T that exist later in history than the current edit point.
     ///
-    /// Returns a reference to the new current value
+    /// Returns a reference to the new current value. Within an
+    /// [`edit_batch`](UndoStack::edit_batch) this saves nothing and returns the current value.
     ///
     /// # Panics
     /// This will panic if allocation failed
@@ -227,28 +716,44 @@ impl<T> UndoStack<T> {
     {
         self.invariant_ck();
 
+        if self.batching {
+            return self.get_mut();
+        }
+
         self.invalidate_future();
 
-        // safe to unwrap here because history is always nonempty
-        let val = self.history.last().unwrap().clone();
+        // after invalidating the future the current value is always the last value
+        let val = self.at(self.current).clone();
 
-        self.push_unchecked(val)
+        self.push_unchecked(val);
+        self.enforce_limits();
+
+        self.get_mut()
     }
 
     /// Pushes the given value to the stack, making it the new current value and invalidating
     /// future history, returns a reference to the new current value
     ///
     /// This is functionally identical to [`save`](UndoStack::save) but does not have a `Clone`
-    /// bound, instead sourcing its new value from the caller.
+    /// bound, instead sourcing its new value from the caller. Within an
+    /// [`edit_batch`](UndoStack::edit_batch) this replaces the current value instead.
     ///
     /// # Panics
     /// This will panic if allocation failed
     pub fn push(&mut self, new_current: T) -> &mut T {
         self.invariant_ck();
 
+        if self.batching {
+            *self.get_mut() = new_current;
+            return self.get_mut();
+        }
+
         self.invalidate_future();
 
-        self.push_unchecked(new_current)
+        self.push_unchecked(new_current);
+        self.enforce_limits();
+
+        self.get_mut()
     }
 
     /// If there is a previous state in the history stack, backtrack to that and return `Ok(&mut T)`
@@ -259,12 +764,12 @@ impl<T> UndoStack<T> {
 
         match self.current.checked_sub(1) {
             Some(n) => {
-                self.current = n;
-                Ok(&mut self.history[self.current])
+                self.move_to(n);
+                Ok(self.get_mut())
             }
             None => {
                 // current was 0
-                Err(&mut self.history[0])
+                Err(self.at_mut(0))
             }
         }
     }
@@ -277,14 +782,157 @@ impl<T> UndoStack<T> {
         self.invariant_ck();
 
         if self.current + 1 == self.history.len() {
-            Err(&mut self.history[self.current])
+            Err(self.at_mut(self.current))
         } else {
-            self.current += 1;
+            // +1 safety: current is less than history.len() - 1 here
+            self.move_to(self.current + 1);
 
-            Ok(&mut self.history[self.current])
+            Ok(self.get_mut())
         }
     }
 
+    /// Drops the oldest values in history until at most `n` values remain, returning the amount
+    /// of values that were dropped. The current value is never dropped, so if the current value
+    /// is older than the last `n` values only the values before it are dropped.
+    pub fn keep_last(&mut self, n: usize) -> usize {
+        self.invariant_ck();
+
+        // current is always less than len, so we never evict it or anything after it
+        let evict = self.history.len().saturating_sub(n).min(self.current);
+
+        self.history.remove_oldest(evict);
+        self.current -= evict;
+        self.clean_removed_oldest(evict);
+
+        if evict > 0 {
+            self.bump_version();
+        }
+
+        evict
+    }
+
+    /// Drops the newest values after the current value until at most `n` of them remain,
+    /// returning the amount of values that were dropped. This limits how far can be redone
+    /// without affecting how far can be undone.
+    pub fn keep_future(&mut self, n: usize) -> usize {
+        self.invariant_ck();
+
+        // +1 safety: current is always less than history.len()
+        let len = (self.current + 1).saturating_add(n);
+        let evict = self.history.len().saturating_sub(len);
+
+        if evict > 0 {
+            self.history.truncate(len);
+            self.clean_truncated(len);
+            self.bump_version();
+        }
+
+        evict
+    }
+
+    /// Drops the oldest values in history until the sum of `estimator` over all values held in
+    /// history is at most `budget`, returning the amount of values that were dropped.
+    ///
+    /// `estimator` should return the approximate size of a value in bytes (or any other unit
+    /// `budget` is given in). The current value is never dropped, so the budget can still be
+    /// exceeded when the current value and anything after it are larger than it on their own.
+    pub fn evict_to_budget(&mut self, budget: usize, estimator: impl Fn(&T) -> usize) -> usize {
+        self.invariant_ck();
+
+        let mut total = (0..self.history.len())
+            .map(|i| estimator(self.at(i)))
+            .fold(0usize, usize::saturating_add);
+
+        let mut evict = 0;
+
+        while total > budget && evict < self.current {
+            total = total.saturating_sub(estimator(self.at(evict)));
+            evict += 1;
+        }
+
+        self.history.remove_oldest(evict);
+        self.current -= evict;
+        self.clean_removed_oldest(evict);
+
+        if evict > 0 {
+            self.bump_version();
+        }
+
+        evict
+    }
+
+    /// Returns the amount of values held in history, including the current value
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Always returns `false`, as the history of an `UndoStack` always holds its current value
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in history, this is also the amount of times
+    /// [`undo`](UndoStack::undo) can succeed in a row
+    pub fn position(&self) -> usize {
+        self.current
+    }
+
+    /// Returns `true` if there is a previous state to [`undo`](UndoStack::undo) to
+    pub fn can_undo(&self) -> bool {
+        self.current != 0
+    }
+
+    /// Returns `true` if there is a future state to [`redo`](UndoStack::redo) to
+    pub fn can_redo(&self) -> bool {
+        self.current + 1 != self.history.len()
+    }
+
+    /// Returns a reference to the current value, which is also what [`Deref`](ops::Deref)
+    /// resolves to when the `deref` feature is enabled
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.at(self.current)
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        self.at_mut(self.current)
+    }
+
+    /// Returns a reference to the current value, the same as [`get`](UndoStack::get)
+    #[must_use]
+    pub fn current(&self) -> &T {
+        self.get()
+    }
+
+    /// Returns a mutable reference to the current value, the same as
+    /// [`get_mut`](UndoStack::get_mut)
+    pub fn current_mut(&mut self) -> &mut T {
+        self.get_mut()
+    }
+
+    /// Exchanges the current values of `self` and `other` without cloning either, leaving the
+    /// rest of both histories untouched. Neither change is saved, as if both current values had
+    /// been edited in place.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut a = UndoStack::new(1u8);
+    /// *a.save() = 2;
+    /// let mut b = UndoStack::new(10u8);
+    ///
+    /// a.swap_current(&mut b);
+    /// assert_eq!(a, 10);
+    /// assert_eq!(b.undo(), Err(&mut 2));
+    /// ```
+    pub fn swap_current<S2: HistoryStorage<T>>(&mut self, other: &mut UndoStack<T, S2>) {
+        core::mem::swap(self.get_mut(), other.get_mut());
+    }
+
+    /// Exchanges the current value with `value` in place, like [`core::mem::swap`]
+    pub fn swap_value(&mut self, value: &mut T) {
+        core::mem::swap(self.get_mut(), value);
+    }
+
     /// function that runs in debug and checks all trivial invariants of `UndoStack`
     fn invariant_ck(&self) {
         debug_assert!(
@@ -292,67 +940,184 @@ impl<T> UndoStack<T> {
             "UndoStack: history was empty, this indicates a bug in UndoStack"
         );
         debug_assert!(self.current < self.history.len(), "UndoStack: current was not less than history length, this indicates a bug in UndoStack");
+        debug_assert!(
+            self.clean.map_or(true, |c| c < self.history.len()),
+            "UndoStack: clean was not less than history length, this indicates a bug in UndoStack"
+        );
+    }
+
+    /// Records a change to the position or entries of history, see
+    /// [`version`](UndoStack::version)
+    pub(crate) fn bump_version(&mut self) {
+        self.version = self.version.wrapping_add(1);
+    }
+
+    /// Gets a reference to the value at `index` in history, panicking if it is out of bounds
+    fn at(&self, index: usize) -> &T {
+        self.history
+            .get(index)
+            .expect("UndoStack: index was out of bounds, this indicates a bug in UndoStack")
+    }
+
+    /// Gets a mutable reference to the value at `index` in history, panicking if it is out of
+    /// bounds
+    fn at_mut(&mut self, index: usize) -> &mut T {
+        self.history
+            .get_mut(index)
+            .expect("UndoStack: index was out of bounds, this indicates a bug in UndoStack")
     }
 
     /// Gets a reference to the current value
     /// used to implement traits via T without accidental recursion
     fn inner(&self) -> &T {
-        self
+        self.get()
     }
 }
 
-impl<T> ops::Deref for UndoStack<T> {
+#[cfg(feature = "deref")]
+impl<T, S: HistoryStorage<T>> ops::Deref for UndoStack<T, S> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.history[self.current]
+        self.get()
     }
 }
 
-impl<T> ops::DerefMut for UndoStack<T> {
+#[cfg(feature = "deref")]
+impl<T, S: HistoryStorage<T>> ops::DerefMut for UndoStack<T, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.history[self.current]
+        self.get_mut()
+    }
+}
+
+impl<T, S: HistoryStorage<T>> AsRef<T> for UndoStack<T, S> {
+    fn as_ref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T, S: HistoryStorage<T>> AsMut<T> for UndoStack<T, S> {
+    fn as_mut(&mut self) -> &mut T {
+        self.get_mut()
+    }
+}
+
+impl<T, S: HistoryStorage<T>> borrow::Borrow<T> for UndoStack<T, S> {
+    fn borrow(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T, S: HistoryStorage<T>> borrow::BorrowMut<T> for UndoStack<T, S> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self.get_mut()
     }
 }
 
-impl<T: PartialEq> PartialEq<T> for UndoStack<T> {
+impl<T: fmt::Write, S: HistoryStorage<T>> fmt::Write for UndoStack<T, S> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.get_mut().write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.get_mut().write_char(c)
+    }
+}
+
+impl<T, S: HistoryStorage<T> + Default> FromIterator<T> for UndoStack<T, S> {
+    /// Creates an `UndoStack` holding every item as history oldest first, with the last item as
+    /// the current value
+    ///
+    /// # Panics
+    /// This will panic if the iterator was empty, or if allocation failed
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut history = S::default();
+
+        for item in iter {
+            history.push(item);
+        }
+
+        assert!(
+            !history.is_empty(),
+            "UndoStack::from_iter: iterator was empty"
+        );
+
+        let current = history.len() - 1;
+
+        Self {
+            current,
+            clean: Some(current),
+            version: 0,
+            batching: false,
+            limits: limits::Limits::default(),
+            history,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T, S: HistoryStorage<T>> Extend<T> for UndoStack<T, S> {
+    /// Pushes every item as the new current value in order, see [`UndoStack::push`]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T: ops::Index<I>, S: HistoryStorage<T>, I> ops::Index<I> for UndoStack<T, S> {
+    type Output = T::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.inner()[index]
+    }
+}
+
+impl<T: ops::IndexMut<I>, S: HistoryStorage<T>, I> ops::IndexMut<I> for UndoStack<T, S> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.get_mut()[index]
+    }
+}
+
+impl<T: PartialEq, S: HistoryStorage<T>> PartialEq<T> for UndoStack<T, S> {
     fn eq(&self, other: &T) -> bool {
         self.inner() == other
     }
 }
 
-impl<T: PartialEq> PartialEq for UndoStack<T> {
+impl<T: PartialEq, S: HistoryStorage<T>> PartialEq for UndoStack<T, S> {
     fn eq(&self, other: &Self) -> bool {
         self.inner() == other.inner()
     }
 }
 
-impl<T: Eq> Eq for UndoStack<T> {}
+impl<T: Eq, S: HistoryStorage<T>> Eq for UndoStack<T, S> {}
 
-impl<T: PartialOrd> PartialOrd for UndoStack<T> {
+impl<T: PartialOrd, S: HistoryStorage<T>> PartialOrd for UndoStack<T, S> {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         self.inner().partial_cmp(other.inner())
     }
 }
 
-impl<T: PartialOrd> PartialOrd<T> for UndoStack<T> {
+impl<T: PartialOrd, S: HistoryStorage<T>> PartialOrd<T> for UndoStack<T, S> {
     fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
         self.inner().partial_cmp(other)
     }
 }
 
-impl<T: Ord> Ord for UndoStack<T> {
+impl<T: Ord, S: HistoryStorage<T>> Ord for UndoStack<T, S> {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
         self.inner().cmp(other.inner())
     }
 }
 
-impl<T: hash::Hash> hash::Hash for UndoStack<T> {
+impl<T: hash::Hash, S: HistoryStorage<T>> hash::Hash for UndoStack<T, S> {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         self.inner().hash(state);
     }
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn undo_stack() {
     let mut g = UndoStack::new(0u8);
@@ -372,6 +1137,153 @@ fn undo_stack() {
     assert!(g.redo().is_err());
 }
 
+#[cfg(feature = "alloc")]
+#[test]
+fn borrow_current() {
+    use alloc::{collections::BTreeSet, string::String};
+
+    let mut set = BTreeSet::new();
+    set.insert(UndoStack::new(1u8));
+    set.insert(UndoStack::new(2u8));
+
+    assert!(set.contains(&2));
+
+    let mut h = HistoryStack::new(String::from("a"));
+    h.as_mut().push('b');
+    assert_eq!(AsRef::<String>::as_ref(&h), "ab");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn index_current() {
+    let mut undo = UndoStack::new(alloc::vec![1u8, 2, 3]);
+
+    undo.save();
+    undo[1] = 5;
+    assert_eq!(undo[1], 5);
+    assert_eq!(undo[..2], [1, 5]);
+
+    let mut history = HistoryStack::new([0u8; 4]);
+    history[3] = 1;
+    assert_eq!(history[3], 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn write_current() {
+    use alloc::string::String;
+    use fmt::Write;
+
+    let mut undo = UndoStack::new(String::new());
+
+    write!(undo, "{}", 1).unwrap();
+    write!(undo.save(), "{}", 2).unwrap();
+    assert_eq!(undo.get(), "12");
+
+    let mut history = HistoryStack::new(String::from("a"));
+    history.push();
+    history.write_char('b').unwrap();
+    assert_eq!(history.pop().as_deref(), Some("ab"));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn collect_and_extend() {
+    let mut undo: UndoStack<u8> = [1, 2, 3].into_iter().collect();
+    assert_eq!(undo, 3);
+
+    undo.undo().unwrap();
+    undo.extend([4, 5]);
+    assert_eq!(undo.len(), 4);
+    assert_eq!(*undo.undo().unwrap(), 4);
+    assert_eq!(*undo.undo().unwrap(), 2);
+
+    let mut history = HistoryStack::new(alloc::string::String::new());
+    history.extend(['a', 'b']);
+    assert_eq!(history.get(), "ab");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn clone_from_reuses() {
+    use alloc::string::String;
+
+    let mut source = UndoStack::new(String::from("a"));
+    source.save().push('b');
+
+    let mut target = UndoStack::new(String::with_capacity(16));
+    target.save();
+    target.save();
+
+    let history = target.history.as_ptr();
+    let entry = target.history[0].as_ptr();
+
+    // a stack cloned into mid batch ends up like a fresh clone, which is never batching
+    target.batching = true;
+    target.clone_from(&source);
+
+    assert!(target.full_eq(&source));
+    assert_eq!(target.history.as_ptr(), history);
+    assert_eq!(target.history[0].as_ptr(), entry);
+
+    assert!(!target.batching);
+    assert_eq!(target.save().as_str(), "ab");
+    assert_eq!(target.len(), 3);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn from_value() {
+    let undo: UndoStack<u8> = 3.into();
+    assert_eq!(undo, 3);
+    assert_eq!(undo.len(), 1);
+
+    let history = HistoryStack::<u8>::from(4);
+    assert_eq!(history, 4);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn explicit_accessors() {
+    let mut undo = UndoStack::new(alloc::vec![1u8]);
+    undo.save().push(2);
+
+    // resolves to UndoStack::push rather than Vec::push through Deref
+    undo.push(alloc::vec![]);
+    undo.current_mut().push(3);
+    assert_eq!(undo.get(), &[3]);
+    assert_eq!(undo.undo().unwrap(), &[1, 2]);
+
+    let mut history = HistoryStack::new(1u8);
+    *history.get_mut() += 1;
+    assert_eq!(history.current(), &2);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn swap_current() {
+    let mut a = UndoStack::new(1u8);
+    *a.save() = 2;
+    let mut b = UndoStack::new(3u8);
+
+    a.swap_current(&mut b);
+    assert_eq!(a, 3);
+    assert_eq!(b, 2);
+    assert_eq!(a.undo(), Ok(&mut 1));
+
+    let mut value = 4;
+    b.swap_value(&mut value);
+    assert_eq!((b.len(), value), (1, 2));
+
+    let mut history = HistoryStack::new(5u8);
+    let mut other = HistoryStack::new(6u8);
+    history.push();
+    history.swap_current(&mut other);
+    assert_eq!(history.pop(), Some(6));
+    assert_eq!(other, 5);
+}
+
+#[cfg(feature = "alloc")]
 #[test]
 fn history_stack() {
     let mut g = HistoryStack::new(0u8);
@@ -384,3 +1296,36 @@ fn history_stack() {
 
     assert_eq!(g, 0);
 }
+
+#[cfg(feature = "alloc")]
+#[test]
+fn push_many() {
+    let mut g = HistoryStack::new(0u8);
+    g.push_many(1..=4);
+
+    assert_eq!(g.stack, [0, 1, 2, 3]);
+    assert_eq!(g, 4);
+
+    let mut g = HistoryStack::with_storage(0u8, ArrayStorage::<_, 2>::new());
+    g.push_many([1, 2].into_iter().filter(|_| true));
+
+    assert_eq!(g.pop(), Some(2));
+    assert_eq!(g.pop(), Some(1));
+    assert_eq!(g.pop(), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn entries() {
+    use alloc::vec::Vec;
+
+    let mut g = HistoryStack::new(0u8);
+    assert_eq!(g.entries().collect::<Vec<_>>(), [(0, &0)]);
+
+    g.push_many([1, 2]);
+    assert_eq!(g.entries().count(), 3);
+    assert_eq!(
+        g.entries().rev().collect::<Vec<_>>(),
+        [(2, &0), (1, &1), (0, &2)]
+    );
+}
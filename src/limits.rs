@@ -0,0 +1,281 @@
+//! Limits on how far an undo stack can be undone and redone and how large its history can grow,
+//! enforced by the stack itself
+
+use core::fmt;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// Estimates the size of a value in the unit of a size budget
+type Estimator<T> = fn(&T) -> usize;
+
+/// The limits configured on an [`UndoStack`], `None` means unlimited
+pub(crate) struct Limits<T> {
+    /// The maximum amount of entries before the current value
+    pub(crate) past: Option<usize>,
+    /// The maximum amount of entries after the current value
+    pub(crate) future: Option<usize>,
+    /// The budget the estimated size of history should fit in, along with the estimator
+    pub(crate) budget: Option<(usize, Estimator<T>)>,
+    /// Amount of the oldest entries evicted since the last call to `take_evicted`
+    pub(crate) evicted: usize,
+    /// Amount of the newest entries dropped by the redo limit since the last call to
+    /// `take_truncated`
+    pub(crate) truncated: usize,
+}
+
+// manual impls as derives would require T to implement the traits, while only fn(&T) is held
+
+impl<T> Clone for Limits<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Limits<T> {}
+
+impl<T> Default for Limits<T> {
+    fn default() -> Self {
+        Self {
+            past: None,
+            future: None,
+            budget: None,
+            evicted: 0,
+            truncated: 0,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Limits<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Limits")
+            .field("past", &self.past)
+            .field("future", &self.future)
+            .field("budget", &self.budget.map(|(budget, _)| budget))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Limits how many entries can be undone to, evicting the oldest entries whenever more than
+    /// `limit` are before the current value, or removes the limit if it is `None`. The limit is
+    /// enforced on every save, push and move through history, starting immediately.
+    ///
+    /// Setting both an undo and a redo limit allows keeping a deep undo history while only keeping
+    /// a few redo states around when they hold large buffers.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(0u8);
+    /// undo.set_undo_limit(Some(8));
+    /// undo.set_redo_limit(Some(1));
+    ///
+    /// for i in 1..=4 {
+    ///     *undo.save() = i;
+    /// }
+    ///
+    /// undo.undo().unwrap();
+    /// undo.undo().unwrap();
+    /// assert_eq!(undo.len(), 4);
+    ///
+    /// assert_eq!(undo.redo(), Ok(&mut 3));
+    /// assert!(undo.redo().is_err());
+    /// ```
+    pub fn set_undo_limit(&mut self, limit: Option<usize>) {
+        self.limits.past = limit;
+        self.enforce_limits();
+    }
+
+    /// Returns the maximum amount of entries that can be undone to, see
+    /// [`set_undo_limit`](UndoStack::set_undo_limit)
+    #[must_use]
+    pub fn undo_limit(&self) -> Option<usize> {
+        self.limits.past
+    }
+
+    /// Limits how many entries can be redone to, dropping the newest entries whenever more than
+    /// `limit` are after the current value as with [`keep_future`](UndoStack::keep_future), or
+    /// removes the limit if it is `None`. The limit is enforced on every move through history,
+    /// starting immediately, and is independent of the undo limit.
+    pub fn set_redo_limit(&mut self, limit: Option<usize>) {
+        self.limits.future = limit;
+        self.enforce_limits();
+    }
+
+    /// Returns the maximum amount of entries that can be redone to, see
+    /// [`set_redo_limit`](UndoStack::set_redo_limit)
+    #[must_use]
+    pub fn redo_limit(&self) -> Option<usize> {
+        self.limits.future
+    }
+
+    /// Gives history a budget and a size estimator, evicting the oldest entries whenever the
+    /// estimated size of the whole history exceeds the budget, see
+    /// [`evict_to_budget`](UndoStack::evict_to_budget). The budget is enforced on every save,
+    /// push and move through history, starting immediately, and can be combined with the undo and
+    /// redo limits.
+    ///
+    /// This is useful when the size of states varies too much for a limit on the amount of entries
+    /// to be meaningful.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// // keep at most 16 bytes worth of strings in history
+    /// let mut undo = UndoStack::new(String::from("12345"));
+    /// undo.set_size_budget(16, String::len);
+    ///
+    /// undo.save().push_str("678");
+    /// assert_eq!(undo.take_evicted(), 0);
+    ///
+    /// // 5 + 8 + 8 bytes is over budget, so the oldest state is evicted
+    /// undo.save();
+    /// assert_eq!(undo.take_evicted(), 1);
+    /// assert_eq!(undo.estimated_size(), Some(16));
+    /// assert!(undo.undo().is_ok());
+    /// assert!(undo.undo().is_err());
+    /// ```
+    pub fn set_size_budget(&mut self, budget: usize, estimator: fn(&T) -> usize) {
+        self.limits.budget = Some((budget, estimator));
+        self.enforce_limits();
+    }
+
+    /// Removes the size budget, see [`set_size_budget`](UndoStack::set_size_budget)
+    pub fn clear_size_budget(&mut self) {
+        self.limits.budget = None;
+    }
+
+    /// Returns the size budget, see [`set_size_budget`](UndoStack::set_size_budget)
+    #[must_use]
+    pub fn size_budget(&self) -> Option<usize> {
+        self.limits.budget.map(|(budget, _)| budget)
+    }
+
+    /// Returns the estimated size of all entries in history, or `None` if no size budget is set
+    #[must_use]
+    pub fn estimated_size(&self) -> Option<usize> {
+        let (_, estimator) = self.limits.budget?;
+
+        Some(
+            (0..self.history.len())
+                .map(|i| estimator(self.at(i)))
+                .fold(0, usize::saturating_add),
+        )
+    }
+
+    /// Returns the amount of the oldest entries that the undo limit and size budget evicted since
+    /// the last call to this method, and resets it
+    pub fn take_evicted(&mut self) -> usize {
+        core::mem::take(&mut self.limits.evicted)
+    }
+
+    /// Returns the amount of the newest entries that the redo limit dropped since the last call to
+    /// this method, and resets it
+    #[cfg(feature = "alloc")]
+    pub(crate) fn take_truncated(&mut self) -> usize {
+        core::mem::take(&mut self.limits.truncated)
+    }
+
+    /// Evicts the entries past the configured limits, the current value is never evicted
+    pub(crate) fn enforce_limits(&mut self) {
+        if let Some(past) = self.limits.past {
+            let keep = self.history.len() - self.current.saturating_sub(past);
+
+            self.limits.evicted += self.keep_last(keep);
+        }
+
+        if let Some((budget, estimator)) = self.limits.budget {
+            self.limits.evicted += self.evict_to_budget(budget, estimator);
+        }
+
+        if let Some(future) = self.limits.future {
+            self.limits.truncated += self.keep_future(future);
+        }
+    }
+
+    /// Moves the current value to the entry at `index` as if undoing or redoing to it, then
+    /// enforces the configured limits, which may move the current value to a lower index
+    pub(crate) fn move_to(&mut self, index: usize) {
+        if index != self.current {
+            self.current = index;
+            self.bump_version();
+            self.enforce_limits();
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn depth_limits() {
+    let mut undo = UndoStack::new(0u8);
+    undo.set_undo_limit(Some(3));
+    undo.set_redo_limit(Some(2));
+
+    for i in 1..=5 {
+        *undo.save() = i;
+    }
+    assert_eq!(undo.len(), 4);
+
+    for _ in 0..3 {
+        undo.undo().unwrap();
+    }
+    assert!(undo.undo().is_err());
+    assert_eq!(undo.len(), 3);
+
+    // redoing moves entries into the past without evicting while within the undo limit
+    assert_eq!(undo.redo(), Ok(&mut 3));
+    assert_eq!(undo.redo(), Ok(&mut 4));
+    assert!(undo.redo().is_err());
+    assert_eq!(undo.len(), 3);
+
+    undo.set_undo_limit(Some(0));
+    assert_eq!(undo.len(), 1);
+    assert!(undo.undo().is_err());
+
+    // moving through history in other ways enforces the limits as well
+    undo.set_undo_limit(None);
+    for i in 5..=8 {
+        undo.push(i);
+    }
+    undo.set_position(0).unwrap();
+    assert_eq!(undo.len(), 3);
+    assert_eq!(undo.redo_limit(), Some(2));
+
+    undo.set_redo_limit(Some(0));
+    assert!(undo.redo().is_err());
+    assert_eq!(undo.undo_limit(), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn size_budget() {
+    use alloc::{vec, vec::Vec};
+
+    let mut undo = UndoStack::new(vec![0u8; 4]);
+    assert_eq!(undo.estimated_size(), None);
+    undo.set_size_budget(10, Vec::len);
+
+    undo.save();
+    assert_eq!(undo.estimated_size(), Some(8));
+
+    undo.push(vec![0; 6]);
+    assert_eq!(undo.take_evicted(), 1);
+    assert_eq!(undo.estimated_size(), Some(10));
+
+    // the current value is never evicted, even if it is over budget on its own
+    undo.push(vec![0; 12]);
+    assert_eq!(undo.take_evicted(), 2);
+    assert_eq!(undo.len(), 1);
+
+    undo.set_size_budget(100, Vec::len);
+    undo.save();
+    undo.undo().unwrap();
+    undo.set_size_budget(12, Vec::len);
+
+    // future values count towards the budget but are never evicted
+    assert_eq!(undo.take_evicted(), 0);
+    assert_eq!(undo.estimated_size(), Some(24));
+    assert_eq!(undo.size_budget(), Some(12));
+
+    // the budget combines with the other limits
+    undo.set_redo_limit(Some(0));
+    undo.clear_size_budget();
+    assert_eq!(undo.len(), 1);
+    assert_eq!(undo.size_budget(), None);
+}
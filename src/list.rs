@@ -0,0 +1,331 @@
+//! A list model exposing history as rows, for binding to undo history menus
+
+use alloc::vec::Vec;
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::AnnotatedUndoStack;
+
+/// A row of a [`HistoryListModel`], one per entry of history
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HistoryRow<'a, M> {
+    /// The index of the entry in history, oldest first
+    pub index: usize,
+    /// The metadata of the entry, used as its label
+    pub label: &'a M,
+    /// Whether the entry is the current value
+    pub is_current: bool,
+}
+
+/// A change to the rows of a [`HistoryListModel`], in the order they should be applied to a view
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RowChange {
+    /// `count` rows were inserted starting at `index`
+    Inserted {
+        /// The index of the first inserted row
+        index: usize,
+        /// The amount of rows inserted
+        count: usize,
+    },
+    /// `count` rows were removed starting at `index`, later rows moved up by `count`
+    Removed {
+        /// The index of the first removed row
+        index: usize,
+        /// The amount of rows removed
+        count: usize,
+    },
+    /// The label of the row at `index` changed
+    Relabeled {
+        /// The index of the relabeled row
+        index: usize,
+    },
+    /// The current row moved from `from` to `to`
+    CurrentChanged {
+        /// The index of the previous current row
+        from: usize,
+        /// The index of the new current row
+        to: usize,
+    },
+}
+
+/// An [`AnnotatedUndoStack`] exposing its history as a list of [`HistoryRow`]s labeled by their
+/// metadata, which records a [`RowChange`] for every change made to the rows.
+///
+/// This is suited to binding an "undo history" dropdown in a UI, where
+/// [`take_changes`](HistoryListModel::take_changes) is polled to update the view, and
+/// [`select`](HistoryListModel::select) jumps to the entry the user picked.
+/// ```rust
+/// # use history_stack::{HistoryListModel, RowChange};
+/// let mut model = HistoryListModel::new(String::new(), "Open");
+///
+/// model.save_with_meta("Typing").push_str("hello");
+/// model.save_with_meta("Bold");
+///
+/// let labels: Vec<_> = model.rows().map(|row| *row.label).collect();
+/// assert_eq!(labels, ["Open", "Typing", "Bold"]);
+///
+/// model.take_changes();
+///
+/// // the user picked "Typing" in the menu
+/// assert!(model.select(1));
+/// assert_eq!(model.take_changes(), [RowChange::CurrentChanged { from: 2, to: 1 }]);
+/// assert!(model.row(1).unwrap().is_current);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HistoryListModel<T, M> {
+    /// The underlying stack
+    stack: AnnotatedUndoStack<T, M>,
+    /// Changes since the last call to `take_changes`, oldest first
+    changes: Vec<RowChange>,
+}
+
+impl<T, M> HistoryListModel<T, M> {
+    /// Creates a new `HistoryListModel` with a starting value and its label to act as the current
+    /// value
+    pub fn new(start: T, meta: M) -> Self {
+        Self {
+            stack: AnnotatedUndoStack::new(start, meta),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Records the changes of a new entry replacing any future entries
+    fn record_insert(&mut self, from: usize, old_len: usize) {
+        let dropped = old_len - from - 1;
+
+        if dropped != 0 {
+            self.changes.push(RowChange::Removed {
+                index: from + 1,
+                count: dropped,
+            });
+        }
+
+        self.changes.push(RowChange::Inserted {
+            index: from + 1,
+            count: 1,
+        });
+        self.changes
+            .push(RowChange::CurrentChanged { from, to: from + 1 });
+    }
+
+    /// Records a move of the current row if it moved
+    fn record_move(&mut self, from: usize) {
+        let to = self.stack.position();
+
+        if from != to {
+            self.changes.push(RowChange::CurrentChanged { from, to });
+        }
+    }
+
+    /// Saves the current value with a label for the new current value, see
+    /// [`AnnotatedUndoStack::save_with_meta`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save_with_meta(&mut self, meta: M) -> &mut T
+    where
+        T: Clone,
+    {
+        self.record_insert(self.stack.position(), self.stack.len());
+        self.stack.save_with_meta(meta)
+    }
+
+    /// Pushes a new current value with its label, see [`AnnotatedUndoStack::push_with_meta`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push_with_meta(&mut self, new_current: T, meta: M) -> &mut T {
+        self.record_insert(self.stack.position(), self.stack.len());
+        self.stack.push_with_meta(new_current, meta)
+    }
+
+    /// Undoes to the previous state, see [`AnnotatedUndoStack::undo`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        let from = self.stack.position();
+
+        if self.stack.undo().is_ok() {
+            self.record_move(from);
+            Ok(self.stack.get_mut())
+        } else {
+            Err(self.stack.get_mut())
+        }
+    }
+
+    /// Redoes to the next state, see [`AnnotatedUndoStack::redo`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        let from = self.stack.position();
+
+        if self.stack.redo().is_ok() {
+            self.record_move(from);
+            Ok(self.stack.get_mut())
+        } else {
+            Err(self.stack.get_mut())
+        }
+    }
+
+    /// Undoes or redoes until the entry at `index` is the current value, returning `false` and
+    /// changing nothing if `index` is out of bounds
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.stack.len() {
+            return false;
+        }
+
+        let from = self.stack.position();
+
+        while self.stack.position() > index && self.stack.undo().is_ok() {}
+        while self.stack.position() < index && self.stack.redo().is_ok() {}
+
+        self.record_move(from);
+
+        true
+    }
+
+    /// Drops the oldest entries until at most `n` remain, see [`AnnotatedUndoStack::keep_last`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn keep_last(&mut self, n: usize) -> usize {
+        let evicted = self.stack.keep_last(n);
+
+        if evicted != 0 {
+            self.changes.push(RowChange::Removed {
+                index: 0,
+                count: evicted,
+            });
+        }
+
+        evicted
+    }
+
+    /// Returns a mutable reference to the label of the current value, recording a relabel of its
+    /// row
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn meta_mut(&mut self) -> &mut M {
+        self.changes.push(RowChange::Relabeled {
+            index: self.stack.position(),
+        });
+
+        self.stack.meta_mut()
+    }
+
+    /// Returns the row at `index`, or `None` if `index` is out of bounds
+    #[must_use]
+    pub fn row(&self, index: usize) -> Option<HistoryRow<'_, M>> {
+        self.stack.meta_at(index).map(|label| HistoryRow {
+            index,
+            label,
+            is_current: index == self.stack.position(),
+        })
+    }
+
+    /// Returns an iterator over every row, oldest first
+    #[must_use]
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = HistoryRow<'_, M>> + ExactSizeIterator {
+        let current = self.stack.position();
+
+        self.stack
+            .iter()
+            .enumerate()
+            .map(move |(index, (_, label))| HistoryRow {
+                index,
+                label,
+                is_current: index == current,
+            })
+    }
+
+    /// Returns every change made to the rows since the last call to this method, oldest first,
+    /// and clears them
+    pub fn take_changes(&mut self) -> Vec<RowChange> {
+        core::mem::take(&mut self.changes)
+    }
+
+    /// Returns a reference to the underlying stack
+    #[must_use]
+    pub fn stack(&self) -> &AnnotatedUndoStack<T, M> {
+        &self.stack
+    }
+
+    /// Returns a reference to the value of the current row
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.stack.get()
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        self.stack.get_mut()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, M> ops::Deref for HistoryListModel<T, M> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, M> ops::DerefMut for HistoryListModel<T, M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn history_list_model() {
+    let mut model = HistoryListModel::new(0u8, 'a');
+
+    model.push_with_meta(1, 'b');
+    model.push_with_meta(2, 'c');
+    model.take_changes();
+
+    model.undo().unwrap();
+    model.undo().unwrap();
+    assert!(model.redo().is_ok());
+    model.push_with_meta(3, 'd');
+
+    assert_eq!(
+        model.take_changes(),
+        [
+            RowChange::CurrentChanged { from: 2, to: 1 },
+            RowChange::CurrentChanged { from: 1, to: 0 },
+            RowChange::CurrentChanged { from: 0, to: 1 },
+            RowChange::Removed { index: 2, count: 1 },
+            RowChange::Inserted { index: 2, count: 1 },
+            RowChange::CurrentChanged { from: 1, to: 2 },
+        ]
+    );
+
+    assert!(!model.select(3));
+    assert!(model.select(2));
+    assert_eq!(model.take_changes(), []);
+
+    *model.meta_mut() = 'e';
+    assert_eq!(model.keep_last(2), 1);
+    assert_eq!(
+        model.take_changes(),
+        [
+            RowChange::Relabeled { index: 2 },
+            RowChange::Removed { index: 0, count: 1 },
+        ]
+    );
+
+    let rows: Vec<_> = model.rows().map(|r| (*r.label, r.is_current)).collect();
+    assert_eq!(rows, [('b', false), ('e', true)]);
+}
@@ -0,0 +1,138 @@
+//! Estimates of the memory held by the history of an undo stack
+
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::UndoStack;
+
+/// A breakdown of the memory held by an [`UndoStack`], created by [`UndoStack::memory_usage`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct MemoryReport {
+    /// Estimated bytes held by entries before the current value
+    pub past: usize,
+    /// Estimated bytes held by the current value
+    pub current: usize,
+    /// Estimated bytes held by entries after the current value, that can be redone to
+    pub future: usize,
+    /// Bytes of the buffer backing history, including spare capacity
+    pub storage: usize,
+}
+
+impl MemoryReport {
+    /// Returns the sum of every part of the report
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.past
+            .saturating_add(self.current)
+            .saturating_add(self.future)
+            .saturating_add(self.storage)
+    }
+}
+
+impl<T> UndoStack<T, Vec<T>> {
+    /// Returns a [`MemoryReport`] of the memory held by this stack.
+    ///
+    /// `estimator` should return the amount of bytes a value owns outside of itself, such as the
+    /// heap allocation of a `String`, as the size of the values themselves is counted by
+    /// [`storage`](MemoryReport::storage).
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(String::from("hello"));
+    /// undo.save().push_str(" world");
+    ///
+    /// let report = undo.memory_usage(String::capacity);
+    ///
+    /// assert_eq!(report.past, 5);
+    /// assert!(report.current >= 11);
+    /// assert!(report.storage >= 2 * std::mem::size_of::<String>());
+    /// ```
+    pub fn memory_usage(&self, estimator: impl Fn(&T) -> usize) -> MemoryReport {
+        self.invariant_ck();
+
+        let sum = |entries: &[T]| {
+            entries
+                .iter()
+                .map(&estimator)
+                .fold(0usize, usize::saturating_add)
+        };
+
+        MemoryReport {
+            past: sum(&self.history[..self.current]),
+            current: estimator(&self.history[self.current]),
+            future: sum(&self.history[self.current + 1..]),
+            storage: self.history.capacity().saturating_mul(mem::size_of::<T>()),
+        }
+    }
+
+    /// Returns an iterator over the estimated size of every entry of history oldest first,
+    /// including the current value and anything after it, to find which states hold the most
+    /// memory. See [`memory_usage`](UndoStack::memory_usage) for what `estimator` should return.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(vec![0u8; 4]);
+    /// undo.push(vec![0; 1024]);
+    /// undo.push(vec![0; 8]);
+    ///
+    /// let (largest, _) = undo
+    ///     .entry_sizes(Vec::len)
+    ///     .enumerate()
+    ///     .max_by_key(|&(_, size)| size)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(largest, 1);
+    /// ```
+    pub fn entry_sizes<'a>(
+        &'a self,
+        estimator: impl Fn(&T) -> usize + 'a,
+    ) -> impl DoubleEndedIterator<Item = usize> + ExactSizeIterator + 'a {
+        self.history.iter().map(estimator)
+    }
+
+    /// Counts how many entries of history have an estimated size within each bucket, where
+    /// `bounds` are the ascending sizes buckets start at. The returned counts hold one more
+    /// bucket than `bounds`, as the first bucket counts the entries smaller than the first bound.
+    /// See [`memory_usage`](UndoStack::memory_usage) for what `estimator` should return.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(String::new());
+    /// for len in [3, 40, 50, 900] {
+    ///     undo.push("a".repeat(len));
+    /// }
+    ///
+    /// // entries under 10 bytes, under 100 bytes, and of 100 bytes or more
+    /// assert_eq!(undo.size_histogram(&[10, 100], String::len), [2, 2, 1]);
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn size_histogram(&self, bounds: &[usize], estimator: impl Fn(&T) -> usize) -> Vec<usize> {
+        let mut counts = alloc::vec![0; bounds.len() + 1];
+
+        for size in self.entry_sizes(estimator) {
+            counts[bounds.partition_point(|&b| b <= size)] += 1;
+        }
+
+        counts
+    }
+}
+
+#[test]
+fn memory_usage() {
+    use alloc::vec;
+
+    let mut undo = UndoStack::new(vec![0u8; 4]);
+    undo.push(vec![0; 8]);
+    undo.push(vec![0; 16]);
+    undo.undo().unwrap();
+
+    let report = undo.memory_usage(Vec::len);
+
+    assert_eq!((report.past, report.current, report.future), (4, 8, 16));
+    assert!(report.storage >= 3 * mem::size_of::<Vec<u8>>());
+    assert_eq!(report.total(), 28 + report.storage);
+
+    assert!(undo.entry_sizes(Vec::len).eq([4, 8, 16]));
+    assert_eq!(undo.size_histogram(&[], Vec::len), [3]);
+    assert_eq!(undo.size_histogram(&[4, 9, 16], Vec::len), [0, 2, 0, 1]);
+}
@@ -0,0 +1,122 @@
+//! Three way merging of [`UndoStack`]s that diverged from a common origin
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::{HistoryStorage, Resolver, UndoStack};
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns the amount of leading entries of history that are equal in both stacks, for stacks
+    /// cloned from the same origin this is one past the index of the entry they diverged from
+    #[must_use]
+    pub fn common_prefix_len<S2: HistoryStorage<T>>(&self, other: &UndoStack<T, S2>) -> usize
+    where
+        T: PartialEq,
+    {
+        (0..self.len().min(other.len()))
+            .take_while(|&i| self.at(i) == other.at(i))
+            .count()
+    }
+}
+
+impl<T: Clone> UndoStack<T> {
+    /// Merges two stacks that diverged from the entry at `base_index` into one combined history.
+    ///
+    /// The combined history holds every entry up to `base_index`, followed by the entries of
+    /// `ours` after it, followed by the entries of `theirs` after it each merged on top of the
+    /// current value of `ours` with `resolver`. The last entry is the current value, so undo walks
+    /// back through the changes of `theirs`, then the changes of `ours`, then the common history.
+    ///
+    /// Only entries up to the current value of each stack are merged, any redo history is
+    /// dropped. The entry `base_index` can be found with
+    /// [`common_prefix_len`](UndoStack::common_prefix_len).
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut ours = UndoStack::new([0u8, 0]);
+    /// let mut theirs = ours.clone();
+    ///
+    /// ours.save()[0] = 1;
+    /// theirs.save()[1] = 2;
+    ///
+    /// let base = ours.common_prefix_len(&theirs) - 1;
+    /// let merge = |base: &[u8; 2], ours: &[u8; 2], theirs: &[u8; 2]| {
+    ///     core::array::from_fn(|i| if ours[i] == base[i] { theirs[i] } else { ours[i] })
+    /// };
+    ///
+    /// let mut merged = UndoStack::merge(base, &ours, &theirs, &merge);
+    ///
+    /// assert_eq!(merged, [1, 2]);
+    /// assert_eq!(*merged.undo().unwrap(), [1, 0]);
+    /// assert_eq!(*merged.undo().unwrap(), [0, 0]);
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if `base_index` is past the current value of either stack, or if
+    /// allocation failed
+    pub fn merge<R: Resolver<T>>(
+        base_index: usize,
+        ours: &UndoStack<T>,
+        theirs: &UndoStack<T>,
+        resolver: &R,
+    ) -> Self {
+        assert!(
+            base_index <= ours.current && base_index <= theirs.current,
+            "UndoStack::merge: base_index was past the current value of a stack"
+        );
+
+        let base = &ours.history[base_index];
+        let ours_current = &ours.history[ours.current];
+
+        let mut history: Vec<T> = ours.history[..=ours.current].to_vec();
+
+        history.extend(
+            theirs.history[base_index + 1..=theirs.current]
+                .iter()
+                .map(|t| resolver.resolve(base, ours_current, t)),
+        );
+
+        Self {
+            current: history.len() - 1,
+            history,
+            // the history of ours up to its current value is kept as is
+            clean: ours.clean.filter(|&c| c <= ours.current),
+            version: 0,
+            batching: false,
+            limits: crate::limits::Limits::default(),
+            marker: PhantomData,
+        }
+    }
+}
+
+#[test]
+fn merge() {
+    let mut ours = UndoStack::new(0u8);
+    *ours.save() = 1;
+
+    let mut theirs = ours.clone();
+
+    *ours.save() = 2;
+    *ours.save() = 3;
+    ours.undo().unwrap();
+
+    *theirs.save() = 10;
+    *theirs.save() = 20;
+
+    assert_eq!(ours.common_prefix_len(&theirs), 2);
+
+    // take theirs if ours is unchanged from base, else add both
+    let resolver =
+        |base: &u8, ours: &u8, theirs: &u8| if ours == base { *theirs } else { ours + theirs };
+
+    let mut merged = UndoStack::merge(1, &ours, &theirs, &resolver);
+
+    assert_eq!(merged.len(), 5);
+    assert!(!merged.can_redo());
+    assert_eq!(merged, 22);
+    assert_eq!(*merged.undo().unwrap(), 12);
+    assert_eq!(*merged.undo().unwrap(), 2);
+    assert_eq!(*merged.undo().unwrap(), 1);
+
+    let same = UndoStack::merge(3, &theirs, &theirs.clone(), &resolver);
+    assert_eq!(same.len(), 4);
+}
@@ -0,0 +1,146 @@
+//! The [`Mergeable`] trait, for combining adjacent history entries instead of dropping them
+
+use alloc::vec::Vec;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// A value that can absorb the entry directly after it in history, such as a state carrying an
+/// accumulated change log, or a set of dirty regions that should be unioned when the states they
+/// belong to are combined.
+///
+/// This is used by [`UndoStack::squash`], [`UndoStack::keep_last_merging`] and
+/// [`CoalescingUndoStack::push_coalesced`](crate::CoalescingUndoStack::push_coalesced) to combine
+/// entries semantically instead of keeping only the newer one.
+pub trait Mergeable {
+    /// Merges `newer`, which is the entry directly after `self`, into one entry
+    #[must_use]
+    fn merge(self, newer: Self) -> Self;
+}
+
+impl<T: Mergeable, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Merges every entry from `start` to `end` inclusive into a single entry at `start`, oldest
+    /// first. If the current value was within the squashed range it becomes the merged entry.
+    ///
+    /// ```rust
+    /// # use history_stack::{Mergeable, UndoStack};
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// struct Edits(Vec<&'static str>);
+    ///
+    /// impl Mergeable for Edits {
+    ///     fn merge(mut self, newer: Self) -> Self {
+    ///         self.0.extend(newer.0);
+    ///         self
+    ///     }
+    /// }
+    ///
+    /// let mut undo = UndoStack::new(Edits(vec!["open"]));
+    /// undo.push(Edits(vec!["type"]));
+    /// undo.push(Edits(vec!["save"]));
+    ///
+    /// undo.squash(1, 2);
+    /// assert_eq!(undo.len(), 2);
+    /// assert_eq!(undo.get().0, ["type", "save"]);
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if `start` is greater than `end`, if `end` is out of bounds, or if
+    /// allocation failed
+    pub fn squash(&mut self, start: usize, end: usize) {
+        self.invariant_ck();
+
+        assert!(
+            start <= end && end < self.history.len(),
+            "UndoStack::squash: range was out of bounds"
+        );
+
+        if start == end {
+            return;
+        }
+
+        let mut tail = Vec::new();
+
+        while self.history.len() > end + 1 {
+            tail.extend(self.history.pop());
+        }
+
+        let mut squashed = Vec::with_capacity(end - start + 1);
+
+        while self.history.len() > start {
+            squashed.extend(self.history.pop());
+        }
+
+        // squashed holds the range newest first, so the fold starts from its back
+        let merged = squashed
+            .into_iter()
+            .rev()
+            .reduce(Mergeable::merge)
+            .expect("UndoStack::squash: squashed range was empty");
+
+        self.history.push(merged);
+
+        for entry in tail.into_iter().rev() {
+            self.history.push(entry);
+        }
+
+        if self.current > end {
+            self.current -= end - start;
+        } else if self.current > start {
+            self.current = start;
+        }
+
+        self.clean_squashed(start, end);
+        self.bump_version();
+    }
+
+    /// Drops the oldest values in history until at most `n` values remain like
+    /// [`keep_last`](UndoStack::keep_last), but merges the dropped values into the oldest
+    /// remaining value instead of discarding them, returning the amount of values that were
+    /// merged away.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn keep_last_merging(&mut self, n: usize) -> usize {
+        self.invariant_ck();
+
+        let evict = self.history.len().saturating_sub(n).min(self.current);
+
+        self.squash(0, evict);
+
+        evict
+    }
+}
+
+#[test]
+fn squash() {
+    #[derive(Debug, PartialEq)]
+    struct Digits(u16);
+
+    impl Mergeable for Digits {
+        fn merge(self, newer: Self) -> Self {
+            Digits(self.0 * 10 + newer.0)
+        }
+    }
+
+    let mut undo = UndoStack::new(Digits(1));
+    for i in 2..=5 {
+        undo.push(Digits(i));
+    }
+    undo.undo().unwrap();
+
+    undo.squash(1, 2);
+    assert_eq!(undo.len(), 4);
+    assert_eq!(undo.get().0, 4);
+    assert_eq!(undo.undo(), Ok(&mut Digits(23)));
+
+    undo.squash(1, 2);
+    assert_eq!(undo.get().0, 234);
+
+    let mut undo = UndoStack::new(Digits(1));
+    for i in 2..=4 {
+        undo.push(Digits(i));
+    }
+
+    assert_eq!(undo.keep_last_merging(2), 2);
+    assert_eq!(undo.len(), 2);
+    assert_eq!(undo.undo(), Ok(&mut Digits(123)));
+}
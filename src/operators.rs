@@ -0,0 +1,54 @@
+//! Compound assignment operators for both stack types, delegating to their current value
+
+use core::ops;
+
+use crate::{HistoryStack, HistoryStorage, UndoStack};
+
+/// Implements compound assignment operator traits for both stack types, assigning to the current
+/// value
+macro_rules! assign_ops {
+    ($($trait:ident::$method:ident),*) => {$(
+        impl<T: ops::$trait<Rhs>, S, Rhs> ops::$trait<Rhs> for HistoryStack<T, S> {
+            fn $method(&mut self, rhs: Rhs) {
+                self.get_mut().$method(rhs);
+            }
+        }
+
+        impl<T: ops::$trait<Rhs>, S: HistoryStorage<T>, Rhs> ops::$trait<Rhs> for UndoStack<T, S> {
+            fn $method(&mut self, rhs: Rhs) {
+                self.get_mut().$method(rhs);
+            }
+        }
+    )*};
+}
+
+assign_ops!(
+    AddAssign::add_assign,
+    SubAssign::sub_assign,
+    MulAssign::mul_assign,
+    DivAssign::div_assign,
+    RemAssign::rem_assign,
+    BitAndAssign::bitand_assign,
+    BitOrAssign::bitor_assign,
+    BitXorAssign::bitxor_assign,
+    ShlAssign::shl_assign,
+    ShrAssign::shr_assign
+);
+
+#[cfg(feature = "alloc")]
+#[test]
+fn assign_operators() {
+    let mut register = UndoStack::new(0b0001u8);
+
+    register.save();
+    register |= 0b0100;
+    register <<= 1;
+
+    let mut counter = HistoryStack::new(10i32);
+    counter.push();
+    counter -= 3;
+    counter *= 2;
+
+    assert_eq!(register, 0b1010);
+    assert_eq!(counter, 14);
+}
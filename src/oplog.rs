@@ -0,0 +1,356 @@
+//! An undo stack that records every operation done on it into a numbered log
+
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::UndoStack;
+
+/// An operation done on an [`OpLog`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Op<T> {
+    /// The current value was saved, see [`UndoStack::save`]
+    Save,
+    /// A new current value was pushed, see [`UndoStack::push`]
+    Push(T),
+    /// The current value was replaced in place without creating a new entry
+    Replace(T),
+    /// An undo was attempted, see [`UndoStack::undo`]
+    Undo,
+    /// A redo was attempted, see [`UndoStack::redo`]
+    Redo,
+}
+
+/// An [`Op`] along with its sequence number in the log
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LoggedOp<T> {
+    /// The sequence number of this operation, the first operation of a log is numbered `0` and
+    /// every following one is numbered one higher than the previous
+    pub seq: u64,
+    /// The operation
+    pub op: Op<T>,
+}
+
+/// The error returned when an operation cannot be applied to an [`OpLog`] because its sequence
+/// number is not the next one expected, in which case the log is left unchanged
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SequenceError {
+    /// The sequence number that was expected
+    pub expected: u64,
+    /// The sequence number of the operation
+    pub found: u64,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected operation number {} but got {}",
+            self.expected, self.found
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SequenceError {}
+
+/// An [`UndoStack`] that records every save, push, undo and redo done on it as a numbered
+/// [`LoggedOp`], so a replica starting from the same value can reconstruct the exact same state by
+/// [`apply`](OpLog::apply)ing the log in order.
+///
+/// As every change must be logged, the current value can only be changed through
+/// [`replace`](OpLog::replace) or [`modify`](OpLog::modify), which log an [`Op::Replace`] with the
+/// resulting value, and no mutable reference to it is ever handed out.
+/// ```rust
+/// # use history_stack::OpLog;
+/// let mut origin = OpLog::new(0u8);
+///
+/// origin.save();
+/// origin.modify(|v| *v += 1);
+/// origin.push(5);
+/// origin.undo().unwrap();
+///
+/// let replica = OpLog::replay(0u8, origin.log().iter().cloned()).unwrap();
+///
+/// assert_eq!(*replica.get(), 1);
+/// assert_eq!(replica.stack().len(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct OpLog<T> {
+    /// The underlying stack
+    stack: UndoStack<T>,
+    /// Operations that have not been compacted away yet, oldest first
+    log: Vec<LoggedOp<T>>,
+    /// The sequence number of the next operation
+    next_seq: u64,
+}
+
+impl<T: Default> Default for OpLog<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> OpLog<T> {
+    /// Creates a new `OpLog` with a starting value to act as the current value and an empty log
+    pub fn new(start: T) -> Self {
+        Self {
+            stack: UndoStack::new(start),
+            log: Vec::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Creates a new `OpLog` from a starting value and applies every operation of `ops` in order,
+    /// see [`apply`](OpLog::apply)
+    ///
+    /// # Errors
+    /// If the operations were not numbered consecutively starting from `0`
+    pub fn replay(
+        start: T,
+        ops: impl IntoIterator<Item = LoggedOp<T>>,
+    ) -> Result<Self, SequenceError>
+    where
+        T: Clone,
+    {
+        let mut log = Self::new(start);
+
+        for op in ops {
+            log.apply(op)?;
+        }
+
+        Ok(log)
+    }
+
+    /// Records an operation that was just done
+    fn record(&mut self, op: Op<T>) {
+        self.log.push(LoggedOp {
+            seq: self.next_seq,
+            op,
+        });
+        self.next_seq += 1;
+    }
+
+    /// Applies a single operation to the stack without recording it
+    fn run(&mut self, op: &Op<T>) -> &T
+    where
+        T: Clone,
+    {
+        match op {
+            Op::Save => self.stack.save(),
+            Op::Push(v) => self.stack.push(v.clone()),
+            Op::Replace(v) => {
+                *self.stack.get_mut() = v.clone();
+                self.stack.get_mut()
+            }
+            Op::Undo => self.stack.undo().unwrap_or_else(|v| v),
+            Op::Redo => self.stack.redo().unwrap_or_else(|v| v),
+        }
+    }
+
+    /// Applies and records an operation received from another log, such as one this log is a
+    /// replica of. The operation must be numbered as the next operation of this log.
+    ///
+    /// # Errors
+    /// If the sequence number of `op` is not the next one expected
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn apply(&mut self, op: LoggedOp<T>) -> Result<&T, SequenceError>
+    where
+        T: Clone,
+    {
+        if op.seq != self.next_seq {
+            return Err(SequenceError {
+                expected: self.next_seq,
+                found: op.seq,
+            });
+        }
+
+        self.run(&op.op);
+        self.record(op.op);
+
+        Ok(self.stack.get())
+    }
+
+    /// Saves the current value to history and logs an [`Op::Save`], see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &T
+    where
+        T: Clone,
+    {
+        self.apply_local(Op::Save)
+    }
+
+    /// Pushes a new current value and logs an [`Op::Push`] of it, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &T
+    where
+        T: Clone,
+    {
+        self.apply_local(Op::Push(new_current))
+    }
+
+    /// Replaces the current value in place without creating a new entry, and logs an
+    /// [`Op::Replace`] of it
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn replace(&mut self, value: T) -> &T
+    where
+        T: Clone,
+    {
+        self.apply_local(Op::Replace(value))
+    }
+
+    /// Modifies the current value in place with `f` without creating a new entry, and logs an
+    /// [`Op::Replace`] of the resulting value
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn modify(&mut self, f: impl FnOnce(&mut T)) -> &T
+    where
+        T: Clone,
+    {
+        f(self.stack.get_mut());
+
+        let value = self.stack.get().clone();
+        self.record(Op::Replace(value));
+
+        self.stack.get()
+    }
+
+    /// Undoes to the previous state and logs an [`Op::Undo`], see [`UndoStack::undo`]. The
+    /// attempt is logged even if there was nothing to undo.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&T, &T> {
+        self.record(Op::Undo);
+
+        match self.stack.undo() {
+            Ok(v) => Ok(v),
+            Err(v) => Err(v),
+        }
+    }
+
+    /// Redoes to the next state and logs an [`Op::Redo`], see [`UndoStack::redo`]. The attempt
+    /// is logged even if there was nothing to redo.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&T, &T> {
+        self.record(Op::Redo);
+
+        match self.stack.redo() {
+            Ok(v) => Ok(v),
+            Err(v) => Err(v),
+        }
+    }
+
+    /// Applies and records an operation done on this log
+    fn apply_local(&mut self, op: Op<T>) -> &T
+    where
+        T: Clone,
+    {
+        self.run(&op);
+        self.record(op);
+
+        self.stack.get()
+    }
+
+    /// Returns every operation that has not been compacted away, oldest first
+    #[must_use]
+    pub fn log(&self) -> &[LoggedOp<T>] {
+        &self.log
+    }
+
+    /// Returns every operation numbered `seq` or higher that has not been compacted away, oldest
+    /// first
+    #[must_use]
+    pub fn ops_since(&self, seq: u64) -> &[LoggedOp<T>] {
+        let start = self.log.partition_point(|op| op.seq < seq);
+
+        &self.log[start..]
+    }
+
+    /// Drops every logged operation numbered lower than `seq`, such as ones that every replica
+    /// has acknowledged. Sequence numbers of later operations are unchanged.
+    pub fn compact(&mut self, seq: u64) {
+        let end = self.log.partition_point(|op| op.seq < seq);
+
+        self.log.drain(..end);
+    }
+
+    /// Returns the sequence number the next operation will be given
+    #[must_use]
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Returns a reference to the underlying stack
+    #[must_use]
+    pub fn stack(&self) -> &UndoStack<T> {
+        &self.stack
+    }
+
+    /// Returns a reference to the current value, the result of every operation in the log up to
+    /// the current position
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.stack.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::Deref for OpLog<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[test]
+fn op_log() {
+    let mut origin = OpLog::new(0u8);
+    let mut replica = OpLog::new(0u8);
+
+    origin.save();
+    origin.modify(|v| *v = 3);
+    assert!(origin.redo().is_err());
+    origin.push(7);
+    origin.undo().unwrap();
+    origin.replace(4);
+
+    assert_eq!(origin.next_seq(), 6);
+    assert_eq!(origin.ops_since(4)[0].op, Op::Undo);
+
+    for op in origin.ops_since(0).to_vec() {
+        replica.apply(op).unwrap();
+    }
+
+    assert_eq!(*replica.get(), 4);
+    assert_eq!(replica.stack().len(), 3);
+    assert_eq!(*replica.redo().unwrap(), 7);
+
+    origin.compact(5);
+    assert_eq!(origin.log().len(), 1);
+
+    let missing = origin.log()[0].clone();
+    assert_eq!(
+        OpLog::replay(0u8, [missing]).unwrap_err(),
+        SequenceError {
+            expected: 0,
+            found: 5
+        }
+    );
+}
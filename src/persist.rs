@@ -0,0 +1,238 @@
+//! An undo stack tracking which entries of its history were persisted externally
+
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::UndoStack;
+
+/// A value in history along with the id it was persisted under, if any
+#[derive(Clone, Debug)]
+struct Tracked<T, P> {
+    /// The value
+    value: T,
+    /// The external id the value was persisted under
+    persisted: Option<P>,
+}
+
+/// An [`UndoStack`] where entries can be marked as persisted under an external id `P`, such as a
+/// file revision or the sequence number of a sync server, so a sync engine can tell exactly which
+/// prefix of history is already durable.
+///
+/// Editing the current value through [`get_mut`](PersistedUndoStack::get_mut) clears its mark,
+/// as the value no longer matches what was persisted. Marks are stored with their entries, so they
+/// are dropped along with them when history is truncated by a save or trimmed by
+/// [`keep_last`](PersistedUndoStack::keep_last).
+/// ```rust
+/// # use history_stack::PersistedUndoStack;
+/// let mut undo = PersistedUndoStack::new(String::from("a"));
+/// undo.mark_persisted(1u32);
+///
+/// undo.save().push('b');
+/// undo.save().push('c');
+///
+/// assert_eq!(undo.last_persisted(), Some((0, &1)));
+/// assert!(undo.entries_since_persisted().eq(["ab", "abc"]));
+///
+/// undo.mark_persisted(2);
+/// assert_eq!(undo.entries_since_persisted().len(), 0);
+///
+/// // editing the current value makes it unpersisted again
+/// undo.get_mut().push_str("d");
+/// assert_eq!(undo.last_persisted(), Some((0, &1)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PersistedUndoStack<T, P> {
+    /// The underlying stack holding values along with their marks
+    stack: UndoStack<Tracked<T, P>>,
+}
+
+impl<T: Default, P> Default for PersistedUndoStack<T, P> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T, P> PersistedUndoStack<T, P> {
+    /// Creates a new `PersistedUndoStack` with a starting value to act as the current value, that
+    /// is not persisted
+    pub fn new(start: T) -> Self {
+        Self {
+            stack: UndoStack::new(Tracked {
+                value: start,
+                persisted: None,
+            }),
+        }
+    }
+
+    /// Saves the current value to history, see [`UndoStack::save`]. The new current value is not
+    /// persisted.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        let value = self.stack.get().value.clone();
+
+        self.push(value)
+    }
+
+    /// Pushes a new current value that is not persisted, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        &mut self
+            .stack
+            .push(Tracked {
+                value: new_current,
+                persisted: None,
+            })
+            .value
+    }
+
+    /// Undoes to the previous state, which keeps the id it was persisted under, see
+    /// [`UndoStack::undo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        match self.stack.undo() {
+            Ok(v) => Ok(&mut v.value),
+            Err(v) => Err(&mut v.value),
+        }
+    }
+
+    /// Redoes to the next state, which keeps the id it was persisted under, see
+    /// [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        match self.stack.redo() {
+            Ok(v) => Ok(&mut v.value),
+            Err(v) => Err(&mut v.value),
+        }
+    }
+
+    /// Drops the oldest entries until at most `n` remain, see [`UndoStack::keep_last`]
+    pub fn keep_last(&mut self, n: usize) -> usize {
+        self.stack.keep_last(n)
+    }
+
+    /// Marks the current value as persisted under `id`, returning the id it was previously
+    /// persisted under
+    pub fn mark_persisted(&mut self, id: P) -> Option<P> {
+        self.stack.get_mut().persisted.replace(id)
+    }
+
+    /// Returns the id the entry at `index` in history was persisted under, or `None` if it was
+    /// not persisted or `index` is out of bounds
+    #[must_use]
+    pub fn persisted_at(&self, index: usize) -> Option<&P> {
+        self.stack
+            .history
+            .get(index)
+            .and_then(|v| v.persisted.as_ref())
+    }
+
+    /// Returns the index and id of the newest persisted entry in history, or `None` if no entry
+    /// is persisted
+    #[must_use]
+    pub fn last_persisted(&self) -> Option<(usize, &P)> {
+        self.stack
+            .history
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, v)| v.persisted.as_ref().map(|p| (i, p)))
+    }
+
+    /// Returns an iterator over every entry after the newest persisted entry, oldest first, which
+    /// is all of history if no entry is persisted. This includes entries after the current value.
+    #[must_use]
+    pub fn entries_since_persisted(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        let start = self.last_persisted().map_or(0, |(i, _)| i + 1);
+
+        self.stack.history[start..].iter().map(|v| &v.value)
+    }
+
+    /// Returns the amount of values held in history, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Always returns `false`, as the current value counts towards
+    /// [`len`](PersistedUndoStack::len) whether or not it was persisted
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in history
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.stack.position()
+    }
+
+    /// Returns a reference to the current value, without the id it was persisted under
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.stack.get().value
+    }
+
+    /// Returns a mutable reference to the current value, clearing the id it was persisted under as it
+    /// may no longer match what was persisted
+    pub fn get_mut(&mut self) -> &mut T {
+        let current = self.stack.get_mut();
+        current.persisted = None;
+
+        &mut current.value
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, P> ops::Deref for PersistedUndoStack<T, P> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, P> ops::DerefMut for PersistedUndoStack<T, P> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn persisted_undo_stack() {
+    let mut undo = PersistedUndoStack::new(0u8);
+    assert_eq!(undo.last_persisted(), None);
+    assert_eq!(undo.entries_since_persisted().len(), 1);
+
+    for i in 1..=4 {
+        undo.push(i);
+        if i % 2 == 0 {
+            assert_eq!(undo.mark_persisted(i), None);
+        }
+    }
+
+    assert_eq!(undo.mark_persisted(5), Some(4));
+    assert_eq!(undo.persisted_at(2), Some(&2));
+    assert_eq!(undo.persisted_at(3), None);
+
+    undo.undo().unwrap();
+    assert_eq!(undo.last_persisted(), Some((4, &5)));
+    assert_eq!(undo.entries_since_persisted().len(), 0);
+
+    // saving drops the persisted entry after the current value
+    *undo.save() = 9;
+    assert_eq!(undo.last_persisted(), Some((2, &2)));
+    assert!(undo.entries_since_persisted().eq(&[3, 9]));
+
+    assert_eq!(undo.keep_last(2), 3);
+    assert_eq!(undo.last_persisted(), None);
+}
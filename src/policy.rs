@@ -0,0 +1,345 @@
+//! The [`EvictionPolicy`] trait, for pluggable strategies deciding which old history to drop
+
+use alloc::vec::Vec;
+
+use crate::{HistoryHook, HookedUndoStack, OutOfRange, UndoStack};
+
+/// A strategy deciding which entries of history a [`PolicyHook`] drops, invoked after every save
+/// and push.
+///
+/// Any `FnMut(&[T], usize) -> Vec<usize>` closure is a policy, returning the same indices
+/// [`evict`](EvictionPolicy::evict) would.
+pub trait EvictionPolicy<T> {
+    /// Returns the indices of the entries of `history` to drop, given the index of the current
+    /// value within it.
+    ///
+    /// Indices of the current value, any value after it, or out of bounds indices are ignored, as
+    /// are duplicates.
+    fn evict(&mut self, history: &[T], current: usize) -> Vec<usize>;
+}
+
+impl<T, F: FnMut(&[T], usize) -> Vec<usize>> EvictionPolicy<T> for F {
+    fn evict(&mut self, history: &[T], current: usize) -> Vec<usize> {
+        self(history, current)
+    }
+}
+
+/// An [`EvictionPolicy`] that drops the oldest entries until at most the given amount remain, see
+/// [`UndoStack::keep_last`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CountLimit(pub usize);
+
+impl<T> EvictionPolicy<T> for CountLimit {
+    fn evict(&mut self, history: &[T], _: usize) -> Vec<usize> {
+        (0..history.len().saturating_sub(self.0)).collect()
+    }
+}
+
+/// An [`EvictionPolicy`] that drops the oldest entries until the estimated size of history fits
+/// in a budget, see [`UndoStack::evict_to_budget`]
+#[derive(Clone, Copy, Debug)]
+pub struct SizeLimit<T> {
+    /// The budget the estimated size of history should fit in
+    pub budget: usize,
+    /// Estimates the size of a value in the same unit as the budget
+    pub estimator: fn(&T) -> usize,
+}
+
+impl<T> EvictionPolicy<T> for SizeLimit<T> {
+    fn evict(&mut self, history: &[T], current: usize) -> Vec<usize> {
+        let mut total = history
+            .iter()
+            .map(self.estimator)
+            .fold(0usize, usize::saturating_add);
+
+        let mut evict = 0;
+
+        while total > self.budget && evict < current {
+            total = total.saturating_sub((self.estimator)(&history[evict]));
+            evict += 1;
+        }
+
+        (0..evict).collect()
+    }
+}
+
+/// A handle to an entry pinned by [`PolicyUndoStack::pin`], which follows the entry as older
+/// entries are evicted
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct PinToken(u64);
+
+/// A [`HistoryHook`] that lets an [`EvictionPolicy`] drop entries of history after every save or
+/// push, keeping any entry that was pinned, see [`PolicyUndoStack`].
+#[derive(Clone, Debug)]
+pub struct PolicyHook<P> {
+    /// The policy deciding what to evict
+    policy: P,
+    /// Amount of entries evicted since the last call to `take_evicted`
+    evicted: usize,
+    /// The id of every pin along with the index of the entry it pins
+    pins: Vec<(u64, usize)>,
+    /// The id of the next pin
+    next_pin: u64,
+    /// Amount of pinned entries the policy chose since the last call to `take_blocked`
+    blocked: usize,
+}
+
+impl<P> PolicyHook<P> {
+    /// Creates a new `PolicyHook` evicting the entries `policy` chooses
+    pub fn new(policy: P) -> Self {
+        Self {
+            policy,
+            evicted: 0,
+            pins: Vec::new(),
+            next_pin: 0,
+            blocked: 0,
+        }
+    }
+}
+
+impl<T, P: EvictionPolicy<T>> HistoryHook<T> for PolicyHook<P> {
+    fn evict(&mut self, stack: &UndoStack<T>) -> Vec<usize> {
+        let current = stack.current;
+        let mut evict = self.policy.evict(&stack.history, current);
+
+        evict.retain(|&i| i < current);
+        evict.sort_unstable();
+        evict.dedup();
+
+        let chosen = evict.len();
+        evict.retain(|i| !self.pins.iter().any(|(_, pinned)| pinned == i));
+        self.blocked += chosen - evict.len();
+
+        evict
+    }
+
+    fn on_truncate(&mut self, stack: &UndoStack<T>, _: usize) {
+        let len = stack.len();
+
+        self.pins.retain(|&(_, pinned)| pinned < len);
+    }
+
+    fn on_evict(&mut self, _: &UndoStack<T>, evicted: &[usize]) {
+        // pins do not protect from limits configured on the stack
+        self.pins
+            .retain(|(_, pinned)| evicted.binary_search(pinned).is_err());
+
+        for (_, pinned) in &mut self.pins {
+            *pinned -= evicted.partition_point(|&i| i < *pinned);
+        }
+
+        self.evicted += evicted.len();
+    }
+}
+
+/// An [`UndoStack`] that lets an [`EvictionPolicy`] drop entries of its history after every save
+/// or push, see [`PolicyHook`].
+///
+/// Unlike the [size budget](UndoStack::set_size_budget) of an `UndoStack`, which always drops the
+/// oldest entries, a policy may drop any past entry, so strategies such as thinning out older
+/// history can be implemented.
+/// ```rust
+/// # use history_stack::PolicyUndoStack;
+/// // only keep past entries holding whole tens
+/// let mut undo = PolicyUndoStack::with_policy(0u8, |history: &[u8], current| {
+///     (0..current).filter(|&i| history[i] % 10 != 0).collect()
+/// });
+///
+/// for i in 1..=20 {
+///     *undo.save() = i;
+/// }
+///
+/// assert_eq!(undo.take_evicted(), 18);
+/// assert_eq!(undo.undo(), Ok(&mut 10));
+/// assert_eq!(undo.undo(), Ok(&mut 0));
+/// ```
+pub type PolicyUndoStack<T, P> = HookedUndoStack<T, PolicyHook<P>>;
+
+impl<T, P: EvictionPolicy<T>> HookedUndoStack<T, PolicyHook<P>> {
+    /// Creates a new `PolicyUndoStack` with a starting value to act as the current value, and the
+    /// policy deciding which entries to evict
+    pub fn with_policy(start: T, policy: P) -> Self {
+        Self::new(start, PolicyHook::new(policy))
+    }
+
+    /// Pins the entry at `index` in history so the policy can never evict it, such as the state
+    /// last saved to disk, returning a token that can later [`unpin`](PolicyUndoStack::unpin)
+    /// it.
+    ///
+    /// Eviction skips pinned entries even when the policy chose them, which may leave history
+    /// over its limit, see [`take_blocked`](PolicyUndoStack::take_blocked). Pins only protect
+    /// from the policy, so saving or pushing after undoing past a pinned entry still drops it
+    /// along with the rest of the redo history, releasing its pin, as do the limits configured
+    /// on the stack.
+    /// ```rust
+    /// # use history_stack::{CountLimit, PolicyUndoStack};
+    /// let mut undo = PolicyUndoStack::with_policy(0u8, CountLimit(2));
+    /// let saved = undo.pin(0).unwrap();
+    ///
+    /// for i in 1..=3 {
+    ///     undo.push(i);
+    /// }
+    ///
+    /// // the pinned entry was chosen for eviction every time, but kept
+    /// assert_eq!(undo.take_blocked(), 2);
+    /// assert_eq!(undo.stack().len(), 3);
+    /// assert_eq!(undo.pinned_position(&saved), Some(0));
+    ///
+    /// assert!(undo.unpin(saved));
+    /// undo.enforce();
+    /// assert_eq!(undo.stack().len(), 2);
+    /// ```
+    ///
+    /// # Errors
+    /// If `index` is not less than the length of history
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn pin(&mut self, index: usize) -> Result<PinToken, OutOfRange> {
+        let len = self.stack().len();
+
+        if index >= len {
+            return Err(OutOfRange { index, len });
+        }
+
+        let hook = self.hook_mut();
+
+        let id = hook.next_pin;
+        hook.next_pin += 1;
+        hook.pins.push((id, index));
+
+        Ok(PinToken(id))
+    }
+
+    /// Releases a pin, returning `false` if its entry was already dropped from history
+    // the token is taken by value so it can not be used again once released
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn unpin(&mut self, token: PinToken) -> bool {
+        let pins = &mut self.hook_mut().pins;
+        let len = pins.len();
+
+        pins.retain(|&(id, _)| id != token.0);
+
+        pins.len() != len
+    }
+
+    /// Returns the index in history of the entry pinned by `token`, or `None` if it was dropped
+    /// from history
+    #[must_use]
+    pub fn pinned_position(&self, token: &PinToken) -> Option<usize> {
+        self.hook()
+            .pins
+            .iter()
+            .find(|&&(id, _)| id == token.0)
+            .map(|&(_, pinned)| pinned)
+    }
+
+    /// Returns `true` if the entry at `index` in history is pinned
+    #[must_use]
+    pub fn is_pinned(&self, index: usize) -> bool {
+        self.hook().pins.iter().any(|&(_, pinned)| pinned == index)
+    }
+
+    /// Returns the amount of times the policy chose a pinned entry for eviction since the last
+    /// call to this method, and resets it. A nonzero amount means pins kept history from meeting
+    /// the limit of the policy.
+    pub fn take_blocked(&mut self) -> usize {
+        core::mem::take(&mut self.hook_mut().blocked)
+    }
+
+    /// Returns the amount of entries evicted since the last call to this method, and resets it
+    pub fn take_evicted(&mut self) -> usize {
+        core::mem::take(&mut self.hook_mut().evicted)
+    }
+
+    /// Returns a reference to the policy
+    #[must_use]
+    pub fn policy(&self) -> &P {
+        &self.hook().policy
+    }
+
+    /// Returns a mutable reference to the policy, changes take effect on the next save or push,
+    /// or call to [`enforce`](HookedUndoStack::enforce)
+    pub fn policy_mut(&mut self) -> &mut P {
+        &mut self.hook_mut().policy
+    }
+}
+
+#[test]
+fn eviction_policies() {
+    let mut undo = PolicyUndoStack::with_policy(0u8, CountLimit(3));
+
+    for i in 1..=5 {
+        undo.push(i);
+    }
+
+    assert_eq!(undo.take_evicted(), 3);
+    assert_eq!(undo.stack().len(), 3);
+
+    undo.undo().unwrap();
+    undo.undo().unwrap();
+    undo.policy_mut().0 = 1;
+    undo.enforce();
+
+    // entries after the current value are never evicted
+    assert_eq!(undo.take_evicted(), 0);
+
+    let mut undo = PolicyUndoStack::with_policy(
+        alloc::vec![0u8; 4],
+        SizeLimit {
+            budget: 10,
+            estimator: alloc::vec::Vec::len,
+        },
+    );
+
+    undo.save();
+    undo.push(alloc::vec![0; 6]);
+    assert_eq!(undo.take_evicted(), 1);
+    assert_eq!(undo.stack().len(), 2);
+
+    // out of bounds and duplicate indices are ignored
+    let mut undo = PolicyUndoStack::with_policy(0u8, |_: &[u8], _| alloc::vec![0, 0, 7]);
+    undo.save();
+    undo.save();
+    assert_eq!(undo.take_evicted(), 2);
+    assert_eq!(undo.stack().len(), 1);
+}
+
+#[test]
+fn pinned_entries() {
+    let mut undo = PolicyUndoStack::with_policy(0u8, CountLimit(1));
+
+    undo.push(1);
+    let one = undo.pin(0).unwrap();
+    for i in 2..=4 {
+        undo.push(i);
+    }
+
+    assert_eq!(undo.take_evicted(), 3);
+    assert_eq!(undo.take_blocked(), 3);
+    assert_eq!(undo.pinned_position(&one), Some(0));
+    assert_eq!(undo.pin(2), Err(OutOfRange { index: 2, len: 2 }));
+
+    let four = undo.pin(1).unwrap();
+    undo.undo().unwrap();
+    assert!(undo.is_pinned(1));
+
+    // saving drops the redo history along with its pins
+    undo.save();
+    assert_eq!(undo.pinned_position(&four), None);
+    assert!(!undo.unpin(four));
+    assert!(undo.is_pinned(0));
+
+    // limits configured on the stack drop pinned entries, releasing their pins
+    let mut stack = UndoStack::new(0u8);
+    stack.set_undo_limit(Some(1));
+
+    let mut undo = HookedUndoStack::from_stack(stack, PolicyHook::new(CountLimit(usize::MAX)));
+    let zero = undo.pin(0).unwrap();
+    undo.push(1);
+    let one = undo.pin(1).unwrap();
+    undo.push(2);
+
+    assert_eq!(undo.pinned_position(&zero), None);
+    assert_eq!(undo.pinned_position(&one), Some(0));
+}
@@ -0,0 +1,83 @@
+//! Bounds checked repositioning of an undo stack
+
+use core::fmt;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// The error returned by [`UndoStack::set_position`] when the requested position is not within
+/// history
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OutOfRange {
+    /// The position that was requested
+    pub index: usize,
+    /// The amount of entries held in history at the time
+    pub len: usize,
+}
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "position {} was out of range for a history of {} entries",
+            self.index, self.len
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OutOfRange {}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Moves the current value to the entry at `index` in history, as if undoing or redoing until
+    /// [`position`](UndoStack::position) equals `index`. Nothing is dropped unless the new
+    /// position exceeds an [undo](UndoStack::set_undo_limit) or
+    /// [redo](UndoStack::set_redo_limit) limit. This never panics and does not touch any value,
+    /// so the new current value can be read later.
+    /// ```rust
+    /// # use history_stack::{OutOfRange, UndoStack};
+    /// let mut undo = UndoStack::new(0u8);
+    /// *undo.save() = 1;
+    /// *undo.save() = 2;
+    ///
+    /// assert_eq!(undo.set_position(0), Ok(()));
+    /// assert_eq!(undo, 0);
+    /// assert_eq!(undo.set_position(3), Err(OutOfRange { index: 3, len: 3 }));
+    /// assert_eq!(undo.position(), 0);
+    /// ```
+    ///
+    /// # Errors
+    /// If `index` is not less than [`len`](UndoStack::len), in which case the position is left
+    /// unchanged
+    pub fn set_position(&mut self, index: usize) -> Result<(), OutOfRange> {
+        let len = self.history.len();
+
+        if index < len {
+            self.move_to(index);
+
+            Ok(())
+        } else {
+            Err(OutOfRange { index, len })
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn set_position() {
+    use alloc::string::ToString;
+
+    let mut undo = UndoStack::new(0u8);
+    *undo.save() = 1;
+    undo.mark_clean();
+
+    undo.set_position(0).unwrap();
+    assert!(undo.is_dirty());
+    assert_eq!(undo.redo(), Ok(&mut 1));
+
+    let err = undo.set_position(5).unwrap_err();
+    assert_eq!(undo.position(), 1);
+    assert_eq!(
+        err.to_string(),
+        "position 5 was out of range for a history of 2 entries"
+    );
+}
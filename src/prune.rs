@@ -0,0 +1,89 @@
+//! Selectively dropping states that can be redone to
+
+use alloc::vec::Vec;
+
+use crate::{HistoryStorage, UndoStack};
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Drops every state after the current value for which `pred` returns `false`, keeping the
+    /// rest available to [`redo`](UndoStack::redo) in their original order, and returns the
+    /// amount of states that were dropped. `pred` is called once for each state after the current
+    /// value, oldest first.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new("draft");
+    /// undo.push("uses deleted asset");
+    /// undo.push("final");
+    /// undo.set_position(0).unwrap();
+    ///
+    /// assert_eq!(undo.retain_future(|s| !s.contains("deleted")), 1);
+    /// assert_eq!(undo.redo(), Ok(&mut "final"));
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn retain_future(&mut self, mut pred: impl FnMut(&T) -> bool) -> usize {
+        self.invariant_ck();
+
+        let current = self.current;
+        let keep: Vec<bool> = (current + 1..self.history.len())
+            .map(|i| pred(self.at(i)))
+            .collect();
+
+        let dropped = keep.iter().filter(|&&k| !k).count();
+
+        if dropped == 0 {
+            return 0;
+        }
+
+        self.clean_retain(|i| i <= current || keep[i - current - 1]);
+
+        let mut future = Vec::with_capacity(keep.len());
+
+        while self.history.len() > current + 1 {
+            future.extend(self.history.pop());
+        }
+
+        // future holds the states newest first, so it is reversed to line up with keep
+        for (entry, keep) in future.into_iter().rev().zip(keep) {
+            if keep {
+                self.history.push(entry);
+            }
+        }
+
+        self.bump_version();
+
+        dropped
+    }
+}
+
+#[test]
+fn retain_future() {
+    let mut undo = UndoStack::new(0u8);
+    for i in 1..=6 {
+        undo.push(i);
+    }
+    undo.set_position(4).unwrap();
+    undo.mark_clean();
+    undo.set_position(1).unwrap();
+
+    let mut seen = Vec::new();
+    assert_eq!(
+        undo.retain_future(|&v| {
+            seen.push(v);
+            v % 2 == 0
+        }),
+        2
+    );
+    assert_eq!(seen, [2, 3, 4, 5, 6]);
+
+    assert_eq!(undo.len(), 5);
+    assert_eq!(undo.clean_position(), Some(3));
+    assert_eq!(undo.redo(), Ok(&mut 2));
+    assert_eq!(undo.redo(), Ok(&mut 4));
+    assert!(!undo.is_dirty());
+    assert_eq!(undo.redo(), Ok(&mut 6));
+
+    assert_eq!(undo.retain_future(|_| false), 0);
+    assert_eq!(undo.len(), 5);
+}
@@ -0,0 +1,135 @@
+//! Working with arbitrary ranges of the timeline of an undo stack
+
+use alloc::vec::Vec;
+use core::ops::{Bound, Range, RangeBounds};
+
+use crate::UndoStack;
+
+/// Resolves `range` into the indices it covers within a history of `len` entries, which may be
+/// empty or out of bounds
+pub(crate) fn resolve(range: &impl RangeBounds<usize>, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.saturating_add(1),
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+
+    start..end
+}
+
+impl<T> UndoStack<T, Vec<T>> {
+    /// Returns the entries of history within `range` oldest first, where index 0 is the oldest
+    /// entry and [`position`](UndoStack::position) is the current value
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(0u8);
+    /// for i in 1..=4 {
+    ///     undo.push(i);
+    /// }
+    ///
+    /// assert_eq!(undo.slice(1..3), [1, 2]);
+    /// assert_eq!(undo.slice(undo.position()..), [4]);
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if `range` is out of bounds or its start is after its end
+    #[must_use]
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> &[T] {
+        &self.history[resolve(&range, self.history.len())]
+    }
+
+    /// Removes the entries of history within `range`, returning them oldest first.
+    ///
+    /// Entries after the range keep being undone or redone to as before. If the current value is
+    /// within the range, the newest entry before the range becomes the current value, or the
+    /// oldest entry after it if the range starts at the oldest entry. The clean state is
+    /// invalidated if it is within the range, see [`UndoStack::clean_state`].
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(0u8);
+    /// for i in 1..=5 {
+    ///     undo.push(i);
+    /// }
+    /// undo.undo().unwrap();
+    ///
+    /// assert_eq!(undo.drain_range(1..3), [1, 2]);
+    /// assert_eq!(undo, 4);
+    /// assert_eq!(*undo.undo().unwrap(), 3);
+    /// assert_eq!(*undo.undo().unwrap(), 0);
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if `range` is out of bounds, its start is after its end, or it covers all
+    /// of history
+    pub fn drain_range(&mut self, range: impl RangeBounds<usize>) -> Vec<T> {
+        self.invariant_ck();
+
+        let Range { start, end } = resolve(&range, self.history.len());
+
+        assert!(
+            start <= end && end <= self.history.len(),
+            "UndoStack::drain_range: range was out of bounds"
+        );
+        assert!(
+            end - start < self.history.len(),
+            "UndoStack::drain_range: range covered all of history"
+        );
+
+        if start != end {
+            if self.current >= end {
+                self.current -= end - start;
+            } else if self.current >= start {
+                self.current = start.saturating_sub(1);
+            }
+
+            self.clean_retain(|i| !(start..end).contains(&i));
+            self.bump_version();
+        }
+
+        // collected so the stack is valid again before the caller can observe it, even if a
+        // drain would have been leaked
+        self.history.drain(start..end).collect()
+    }
+}
+
+#[test]
+fn slice_and_drain() {
+    let mut undo = UndoStack::new(0u8);
+    for i in 1..=6 {
+        undo.push(i);
+    }
+    undo.set_position(2).unwrap();
+    undo.mark_clean();
+
+    assert_eq!(undo.slice(..), [0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(undo.slice(5..=6), [5, 6]);
+    assert!(undo.slice(3..3).is_empty());
+
+    // draining after the current value leaves it and the clean state in place
+    assert_eq!(undo.drain_range(4..6), [4, 5]);
+    assert_eq!(undo.position(), 2);
+    assert!(!undo.is_dirty());
+    assert_eq!(*undo.redo().unwrap(), 3);
+    assert_eq!(*undo.redo().unwrap(), 6);
+
+    // draining the current value moves it before the range and invalidates the clean state
+    undo.set_position(2).unwrap();
+    assert_eq!(undo.drain_range(2..=3).len(), 2);
+    assert_eq!(undo, 1);
+    assert_eq!(undo.clean_position(), None);
+
+    // draining from the start moves the current value after the range
+    assert_eq!(undo.drain_range(..2), [0, 1]);
+    assert_eq!(undo, 6);
+    assert_eq!(undo.len(), 1);
+
+    let version = undo.version();
+    assert_eq!(undo.drain_range(0..0).len(), 0);
+    assert_eq!(undo.version(), version);
+}
@@ -0,0 +1,106 @@
+//! Structural sharing helpers for stacks holding [`Rc`] or [`Arc`] wrapped states
+//!
+//! Saving a `Rc<T>` only clones the pointer, and [`Rc::make_mut`] then clones the inner `T` the
+//! first time it is mutated while shared. If `T` itself holds its large parts behind `Rc`/`Arc`,
+//! only the parts that are actually changed get copied, and everything else stays shared between
+//! history entries.
+
+use alloc::rc::Rc;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+
+use crate::{HistoryStack, HistoryStorage, UndoStack};
+
+impl<T: Clone, S: HistoryStorage<Rc<T>>> UndoStack<Rc<T>, S> {
+    /// Saves the current value by cloning its `Rc`, and returns a mutable reference to the inner
+    /// value of the new current state via [`Rc::make_mut`].
+    ///
+    /// The inner `T` is only cloned because the saved entry still shares it, so the cost of a save
+    /// is deferred to this call instead of being paid by [`save`](UndoStack::save).
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save_make_mut(&mut self) -> &mut T {
+        Rc::make_mut(self.save())
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: Clone, S: HistoryStorage<Arc<T>>> UndoStack<Arc<T>, S> {
+    /// Saves the current value by cloning its `Arc`, and returns a mutable reference to the inner
+    /// value of the new current state via [`Arc::make_mut`].
+    ///
+    /// This is the [`Arc`] equivalent of [`save_make_mut`](UndoStack::save_make_mut) for `Rc`.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save_make_mut(&mut self) -> &mut T {
+        Arc::make_mut(self.save())
+    }
+}
+
+impl<T: Clone, S: HistoryStorage<Rc<T>>> HistoryStack<Rc<T>, S> {
+    /// Pushes a clone of the current `Rc` to the stack, and returns a mutable reference to the
+    /// inner current value via [`Rc::make_mut`].
+    pub fn push_make_mut(&mut self) -> &mut T {
+        self.push();
+        Rc::make_mut(&mut self.current)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: Clone, S: HistoryStorage<Arc<T>>> HistoryStack<Arc<T>, S> {
+    /// Pushes a clone of the current `Arc` to the stack, and returns a mutable reference to the
+    /// inner current value via [`Arc::make_mut`].
+    pub fn push_make_mut(&mut self) -> &mut T {
+        self.push();
+        Arc::make_mut(&mut self.current)
+    }
+}
+
+#[test]
+fn rc_make_mut() {
+    #[derive(Clone, Debug)]
+    struct Doc {
+        big: Rc<[u8; 64]>,
+        small: u8,
+    }
+
+    let mut undo = UndoStack::new(Rc::new(Doc {
+        big: Rc::new([0; 64]),
+        small: 0,
+    }));
+
+    undo.save_make_mut().small += 1;
+
+    let new_big = Rc::clone(&undo.get().big);
+    let old_big = Rc::clone(&undo.undo().unwrap().big);
+
+    assert!(Rc::ptr_eq(&new_big, &old_big));
+    assert_eq!(undo.get().small, 0);
+    assert_eq!(undo.redo().unwrap().small, 1);
+
+    let mut hist = HistoryStack::new(Rc::new(1u8));
+
+    *hist.push_make_mut() += 1;
+    assert_eq!(**hist.get(), 2);
+    assert_eq!(hist.pop().as_deref(), Some(&2));
+    assert_eq!(**hist.get(), 1);
+}
+
+#[cfg(target_has_atomic = "ptr")]
+#[test]
+fn arc_make_mut() {
+    let mut undo = UndoStack::new(Arc::new(5u8));
+
+    *undo.save_make_mut() *= 2;
+
+    assert_eq!(**undo.get(), 10);
+    assert_eq!(**undo.undo().unwrap(), 5);
+
+    let mut hist = HistoryStack::new(Arc::new(1u8));
+
+    *hist.push_make_mut() += 1;
+    assert_eq!(**hist.get(), 2);
+    assert_eq!(hist.pop().as_deref(), Some(&2));
+}
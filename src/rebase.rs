@@ -0,0 +1,61 @@
+//! Replacing the root state of an undo stack while keeping the history built on top of it
+
+use crate::{HistoryStorage, UndoStack};
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Replaces the oldest entry of history with `new_root`, then calls `adjust` on every newer
+    /// entry oldest first, returning the replaced root. The position is left unchanged.
+    ///
+    /// This allows keeping the undo chain when the baseline it started from changed externally,
+    /// such as a document being reloaded from disk, by using `adjust` to carry the external change
+    /// into every later state. The clean marker is cleared, as any state may have changed.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(String::from("header"));
+    /// undo.save().push_str(" body");
+    /// undo.save().push_str(" footer");
+    ///
+    /// // the file was edited externally, and now starts with a capital
+    /// let old = undo.rebase(String::from("Header"), |s| s.replace_range(..1, "H"));
+    ///
+    /// assert_eq!(old, "header");
+    /// assert_eq!(undo.get(), "Header body footer");
+    /// assert_eq!(undo.undo().unwrap(), "Header body");
+    /// ```
+    pub fn rebase(&mut self, new_root: T, mut adjust: impl FnMut(&mut T)) -> T {
+        self.invariant_ck();
+
+        let old = core::mem::replace(self.at_mut(0), new_root);
+
+        for i in 1..self.history.len() {
+            adjust(self.at_mut(i));
+        }
+
+        self.clean = None;
+        self.bump_version();
+
+        old
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn rebase() {
+    let mut undo = UndoStack::new(1u8);
+    *undo.save() += 1;
+    *undo.save() += 1;
+    undo.undo().unwrap();
+
+    assert_eq!(undo.rebase(10, |v| *v += 9), 1);
+    assert_eq!(undo, 11);
+    assert_eq!(undo.position(), 1);
+    assert!(undo.is_dirty());
+
+    assert_eq!(undo.undo(), Ok(&mut 10));
+    assert_eq!(undo.redo(), Ok(&mut 11));
+    assert_eq!(undo.redo(), Ok(&mut 12));
+
+    let mut single = UndoStack::new(0u8);
+    single.rebase(5, |_| unreachable!());
+    assert_eq!(single, 5);
+}
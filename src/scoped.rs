@@ -0,0 +1,188 @@
+//! An undo stack where every change is bound to a scope key, allowing selective undo per scope
+
+use alloc::vec::Vec;
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::Resolver;
+
+/// A change made within a scope, the state before and after it was made
+#[derive(Clone, Debug)]
+struct ScopedChange<T, K> {
+    /// The scope the change touched
+    key: K,
+    /// The state before the change
+    before: T,
+    /// The state after the change
+    after: T,
+}
+
+/// A history manager where every change records the scope it touched, such as a layer of a
+/// drawing or a region of a document, so the most recent change of one scope can be undone while
+/// keeping every change made to other scopes since.
+///
+/// Selectively undoing a change that later changes were made on top of is done with a
+/// [`Resolver`], merging the state before the change into the current state using the state
+/// after the change as the base. Every later change is rebuilt the same way, so they can still be
+/// selectively undone afterwards.
+/// ```rust
+/// # use history_stack::ScopedUndoStack;
+/// // a drawing of two independent layers
+/// let merge = |base: &[u8; 2], ours: &[u8; 2], theirs: &[u8; 2]| {
+///     core::array::from_fn(|i| if ours[i] == base[i] { theirs[i] } else { ours[i] })
+/// };
+///
+/// let mut drawing = ScopedUndoStack::new([0, 0], merge);
+///
+/// drawing.commit("background", [1, 0]);
+/// drawing.commit("sketch", [1, 7]);
+///
+/// // only the background change is reverted
+/// assert_eq!(*drawing.selective_undo(&"background").unwrap(), [0, 7]);
+/// assert!(!drawing.can_undo(&"background"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScopedUndoStack<T, K, R> {
+    /// The current state, including changes to every scope
+    current: T,
+    /// Changes that can be selectively undone, oldest first
+    changes: Vec<ScopedChange<T, K>>,
+    /// The merge used to revert a change on top of the changes made after it
+    resolver: R,
+}
+
+impl<T, K: PartialEq, R: Resolver<T>> ScopedUndoStack<T, K, R> {
+    /// Creates a new `ScopedUndoStack` with a starting state and the resolver used to revert
+    /// changes on top of the changes made after them
+    pub fn new(start: T, resolver: R) -> Self {
+        Self {
+            current: start,
+            changes: Vec::new(),
+            resolver,
+        }
+    }
+
+    /// Makes `new` the current state as a change to the scope `key` that can be selectively
+    /// undone
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn commit(&mut self, key: K, new: T) -> &T
+    where
+        T: Clone,
+    {
+        let before = core::mem::replace(&mut self.current, new);
+
+        self.changes.push(ScopedChange {
+            key,
+            before,
+            after: self.current.clone(),
+        });
+
+        &self.current
+    }
+
+    /// Modifies the current state with `f` as a change to the scope `key`, see
+    /// [`commit`](ScopedUndoStack::commit)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn modify(&mut self, key: K, f: impl FnOnce(&mut T)) -> &T
+    where
+        T: Clone,
+    {
+        let mut new = self.current.clone();
+        f(&mut new);
+
+        self.commit(key, new)
+    }
+
+    /// If the scope `key` has a change to undo, reverts its most recent change while keeping
+    /// every change made to other scopes since and returns `Ok(&T)` of the new current state,
+    /// otherwise returns `Err(&T)` of the unchanged current state.
+    ///
+    /// Changes made after the reverted one are rebuilt on top of the state before it, so they
+    /// remain selectively undoable.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn selective_undo(&mut self, key: &K) -> Result<&T, &T> {
+        let Some(index) = self.changes.iter().rposition(|c| c.key == *key) else {
+            return Err(&self.current);
+        };
+
+        let change = self.changes.remove(index);
+        let revert = |state: &T| self.resolver.resolve(&change.after, state, &change.before);
+
+        for later in &mut self.changes[index..] {
+            later.before = revert(&later.before);
+            later.after = revert(&later.after);
+        }
+
+        self.current = revert(&self.current);
+
+        Ok(&self.current)
+    }
+
+    /// Returns `true` if the scope `key` has a change to
+    /// [`selective_undo`](ScopedUndoStack::selective_undo)
+    #[must_use]
+    pub fn can_undo(&self, key: &K) -> bool {
+        self.changes.iter().any(|c| c.key == *key)
+    }
+
+    /// Returns an iterator over the scope of every change that can be undone, oldest first
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.changes.iter().map(|c| &c.key)
+    }
+
+    /// Returns a reference to the resolver
+    #[must_use]
+    pub fn resolver(&self) -> &R {
+        &self.resolver
+    }
+
+    /// Returns a reference to the current value, with the changes of every scope applied
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.current
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, K: PartialEq, R: Resolver<T>> ops::Deref for ScopedUndoStack<T, K, R> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[test]
+fn scoped_undo_stack() {
+    let merge = |base: &[u8; 3], ours: &[u8; 3], theirs: &[u8; 3]| {
+        core::array::from_fn(|i| {
+            if ours[i] == base[i] {
+                theirs[i]
+            } else {
+                ours[i]
+            }
+        })
+    };
+
+    let mut doc = ScopedUndoStack::new([0u8; 3], merge);
+
+    doc.commit(0, [1, 0, 0]);
+    doc.modify(1, |d| d[1] = 2);
+    doc.commit(0, [3, 2, 0]);
+    doc.modify(2, |d| d[2] = 4);
+
+    assert!(doc.keys().eq(&[0, 1, 0, 2]));
+
+    assert_eq!(*doc.selective_undo(&0).unwrap(), [1, 2, 4]);
+    assert_eq!(*doc.selective_undo(&1).unwrap(), [1, 0, 4]);
+    assert_eq!(*doc.selective_undo(&0).unwrap(), [0, 0, 4]);
+    assert!(doc.selective_undo(&0).is_err());
+
+    assert!(doc.can_undo(&2));
+    assert_eq!(*doc.selective_undo(&2).unwrap(), [0; 3]);
+    assert_eq!(doc.keys().len(), 0);
+}
@@ -0,0 +1,228 @@
+//! Replayable scripts of operations, for reproducing recorded sessions on any undo stack
+
+use alloc::vec::Vec;
+
+use crate::{HistoryStorage, Op, OpLog, UndoStack};
+
+/// A single step of a [`Script`]
+#[derive(Clone, Debug)]
+enum Step<T> {
+    /// A recorded operation with its values
+    Op(Op<T>),
+    /// Pushes the value generated from the current value
+    PushWith(fn(&T) -> T),
+    /// Modifies the current value in place
+    Modify(fn(&mut T)),
+}
+
+/// A sequence of operations that can be run on any [`UndoStack`] to reproduce a session, such as
+/// one recorded by an [`OpLog`] and attached to a bug report.
+///
+/// Besides recorded operations holding concrete values, steps may generate their value from the
+/// current value when the script is run, which keeps scripts written for tests short.
+/// ```rust
+/// # use history_stack::{OpLog, Script, UndoStack};
+/// // record a session
+/// let mut session = OpLog::new(1u8);
+/// session.push(2);
+/// session.modify(|v| *v *= 10);
+/// session.undo().unwrap();
+///
+/// // and reproduce it elsewhere
+/// let mut undo = UndoStack::new(1u8);
+/// session.script().run(&mut undo);
+/// assert_eq!(undo, 1);
+///
+/// // or write one by hand
+/// let script = Script::new().save().modify(|v| *v += 1).push_with(|v| v * 2);
+/// script.run(&mut undo);
+/// assert_eq!(undo, 4);
+/// assert_eq!(undo.len(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Script<T> {
+    /// The steps of the script, in order
+    steps: Vec<Step<T>>,
+}
+
+impl<T> Default for Script<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Script<T> {
+    /// Creates an empty `Script`
+    #[must_use]
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// Creates a `Script` running every operation of `ops` in order
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn from_ops(ops: impl IntoIterator<Item = Op<T>>) -> Self {
+        Self {
+            steps: ops.into_iter().map(Step::Op).collect(),
+        }
+    }
+
+    /// Appends a step to the script
+    fn step(mut self, step: Step<T>) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Appends an [`Op`] to the script
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn op(self, op: Op<T>) -> Self {
+        self.step(Step::Op(op))
+    }
+
+    /// Appends a save, see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn save(self) -> Self {
+        self.op(Op::Save)
+    }
+
+    /// Appends a push of `value`, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn push(self, value: T) -> Self {
+        self.op(Op::Push(value))
+    }
+
+    /// Appends a push of the value `generate` returns given the current value when the script is
+    /// run
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn push_with(self, generate: fn(&T) -> T) -> Self {
+        self.step(Step::PushWith(generate))
+    }
+
+    /// Appends a replacement of the current value in place with `value`
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn replace(self, value: T) -> Self {
+        self.op(Op::Replace(value))
+    }
+
+    /// Appends a modification of the current value in place
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn modify(self, f: fn(&mut T)) -> Self {
+        self.step(Step::Modify(f))
+    }
+
+    /// Appends an undo attempt, see [`UndoStack::undo`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn undo(self) -> Self {
+        self.op(Op::Undo)
+    }
+
+    /// Appends a redo attempt, see [`UndoStack::redo`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn redo(self) -> Self {
+        self.op(Op::Redo)
+    }
+
+    /// Returns the amount of steps in the script
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns `true` if the script has no steps
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Runs every step of the script on `stack` in order, undo and redo attempts that had nothing
+    /// to undo or redo are skipped like they would have been when recorded
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn run<S: HistoryStorage<T>>(&self, stack: &mut UndoStack<T, S>)
+    where
+        T: Clone,
+    {
+        for step in &self.steps {
+            match step {
+                Step::Op(Op::Save) => {
+                    stack.save();
+                }
+                Step::Op(Op::Push(v)) => {
+                    stack.push(v.clone());
+                }
+                Step::Op(Op::Replace(v)) => *stack.get_mut() = v.clone(),
+                Step::Op(Op::Undo) => {
+                    let _ = stack.undo();
+                }
+                Step::Op(Op::Redo) => {
+                    let _ = stack.redo();
+                }
+                Step::PushWith(generate) => {
+                    let value = generate(stack.get());
+                    stack.push(value);
+                }
+                Step::Modify(f) => f(stack.get_mut()),
+            }
+        }
+    }
+}
+
+impl<T: Clone> OpLog<T> {
+    /// Returns a [`Script`] of every operation that has not been compacted away, oldest first
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn script(&self) -> Script<T> {
+        Script::from_ops(self.log().iter().map(|logged| logged.op.clone()))
+    }
+}
+
+#[test]
+fn script() {
+    let script = Script::new()
+        .push(1u8)
+        .push_with(|v| v + 1)
+        .undo()
+        .undo()
+        .undo()
+        .redo()
+        .replace(9)
+        .save();
+
+    assert_eq!(script.len(), 8);
+
+    let mut undo = UndoStack::new(0u8);
+    script.run(&mut undo);
+
+    assert_eq!(undo, 9);
+    assert_eq!(undo.len(), 3);
+    assert_eq!(undo.undo(), Ok(&mut 9));
+    assert_eq!(undo.undo(), Ok(&mut 0));
+}
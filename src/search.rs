@@ -0,0 +1,121 @@
+//! Searching the history of an undo stack for states matching a predicate
+
+use crate::{HistoryStorage, UndoStack};
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns the index in history of the newest state at or before the current value for which
+    /// `pred` returns `true`, scanning backward from the current value. States after the current
+    /// value are not considered.
+    pub fn position_of(&self, mut pred: impl FnMut(&T) -> bool) -> Option<usize> {
+        self.invariant_ck();
+
+        (0..=self.current).rev().find(|&i| pred(self.at(i)))
+    }
+
+    /// Undoes to the newest state at or before the current value for which `pred` returns `true`,
+    /// returning `Ok(&mut T)` of the new current value. If the current value already matches it is
+    /// left as is and returned in `Ok`.
+    ///
+    /// Returns `Err(&mut T)` of the unchanged current value if no state up to the current value
+    /// matched. States that were undone past remain available to [`redo`](UndoStack::redo).
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new("fn main() {}");
+    /// undo.push("fn main() {");
+    /// undo.push("fn main() { x");
+    ///
+    /// let compiles = |src: &&str| src.ends_with('}');
+    ///
+    /// assert_eq!(undo.position_of(compiles), Some(0));
+    /// assert_eq!(undo.undo_to_matching(compiles), Ok(&mut "fn main() {}"));
+    /// assert!(undo.can_redo());
+    /// ```
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo_to_matching(&mut self, pred: impl FnMut(&T) -> bool) -> Result<&mut T, &mut T> {
+        match self.position_of(pred) {
+            Some(index) => {
+                self.move_to(index);
+
+                Ok(self.get_mut())
+            }
+            None => Err(self.get_mut()),
+        }
+    }
+
+    /// Returns an iterator over every state in history for which `pred` returns `true` along
+    /// with its index, oldest first. Unlike [`position_of`](UndoStack::position_of) this scans
+    /// the whole timeline, including states after the current value.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(vec!["circle"]);
+    /// undo.save().push("square");
+    /// undo.save().retain(|&s| s != "circle");
+    /// undo.undo().unwrap();
+    ///
+    /// let with_circle: Vec<_> = undo.find(|s| s.contains(&"circle")).map(|(i, _)| i).collect();
+    /// assert_eq!(with_circle, [0, 1]);
+    /// ```
+    pub fn find<'a>(
+        &'a self,
+        mut pred: impl FnMut(&T) -> bool + 'a,
+    ) -> impl DoubleEndedIterator<Item = (usize, &'a T)> + 'a {
+        (0..self.history.len())
+            .map(move |i| (i, self.at(i)))
+            .filter(move |(_, v)| pred(v))
+    }
+
+    /// Returns an iterator over the index of every state in history along with the result of
+    /// `f`, for every state where `f` returns `Some`, oldest first. This scans the whole timeline
+    /// like [`find`](UndoStack::find).
+    pub fn find_map<'a, R>(
+        &'a self,
+        mut f: impl FnMut(&T) -> Option<R> + 'a,
+    ) -> impl DoubleEndedIterator<Item = (usize, R)> + 'a {
+        (0..self.history.len()).filter_map(move |i| f(self.at(i)).map(|r| (i, r)))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn undo_to_matching() {
+    let mut undo = UndoStack::new(1u8);
+    for i in 2..=5 {
+        undo.push(i);
+    }
+    undo.undo().unwrap();
+
+    // the current value and the future are not skipped past or searched
+    assert_eq!(undo.position_of(|&v| v == 4), Some(3));
+    assert_eq!(undo.position_of(|&v| v == 5), None);
+
+    assert_eq!(undo.undo_to_matching(|&v| v % 2 == 0), Ok(&mut 4));
+    assert_eq!(undo.undo_to_matching(|&v| v < 3), Ok(&mut 2));
+    assert_eq!(undo.position(), 1);
+
+    assert_eq!(undo.undo_to_matching(|&v| v > 2), Err(&mut 2));
+    assert_eq!(undo.position(), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn find() {
+    use alloc::vec::Vec;
+
+    let mut undo = UndoStack::new(1u8);
+    for i in 2..=6 {
+        undo.push(i);
+    }
+    undo.set_position(1).unwrap();
+
+    assert_eq!(
+        undo.find(|&v| v % 2 == 0).collect::<Vec<_>>(),
+        [(1, &2), (3, &4), (5, &6)]
+    );
+    assert_eq!(undo.find(|&v| v > 6).next(), None);
+    assert_eq!(undo.find(|_| true).next_back(), Some((5, &6)));
+
+    assert_eq!(
+        undo.find_map(|&v| v.checked_sub(4)).collect::<Vec<_>>(),
+        [(3, 0), (4, 1), (5, 2)]
+    );
+}
@@ -0,0 +1,142 @@
+//! A thread safe, shareable [`UndoStack`] behind a lock
+
+use alloc::sync::Arc;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::UndoStack;
+
+/// A handle to an [`UndoStack`] shared between threads, internally an
+/// `Arc<RwLock<UndoStack<T>>>`. Cloning a `SharedUndoStack` creates a new handle to the same
+/// history.
+///
+/// Every method takes the lock for exactly the duration of the call, so single calls never
+/// deadlock. Calling any method of a `SharedUndoStack` from within the closure passed to
+/// [`read`](SharedUndoStack::read), [`read_stack`](SharedUndoStack::read_stack) or
+/// [`write`](SharedUndoStack::write) on a handle to the same history **will deadlock** (or panic,
+/// depending on the platform), as the lock is not reentrant.
+///
+/// Lock poisoning is ignored, a panic while the lock is held can only happen while the caller is
+/// mutating the current value or inside a `Clone` implementation, neither of which can leave the
+/// `UndoStack` itself in an invalid state.
+/// ```rust
+/// # use history_stack::SharedUndoStack;
+/// let shared = SharedUndoStack::new(0u32);
+/// let handle = shared.clone();
+///
+/// std::thread::spawn(move || {
+///     handle.save();
+///     handle.write(|stack| *stack.get_mut() += 1);
+/// })
+/// .join()
+/// .unwrap();
+///
+/// assert_eq!(shared.read(|v| *v), 1);
+/// assert!(shared.undo());
+/// assert_eq!(shared.read(|v| *v), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct SharedUndoStack<T> {
+    /// The shared stack
+    inner: Arc<RwLock<UndoStack<T>>>,
+}
+
+impl<T> Clone for SharedUndoStack<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> From<UndoStack<T>> for SharedUndoStack<T> {
+    fn from(stack: UndoStack<T>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(stack)),
+        }
+    }
+}
+
+impl<T> SharedUndoStack<T> {
+    /// Creates a new `SharedUndoStack` with a starting value to act as the current value
+    pub fn new(start: T) -> Self {
+        UndoStack::new(start).into()
+    }
+
+    /// Takes the read lock, ignoring poisoning
+    fn read_lock(&self) -> RwLockReadGuard<'_, UndoStack<T>> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Takes the write lock, ignoring poisoning
+    fn write_lock(&self) -> RwLockWriteGuard<'_, UndoStack<T>> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Saves the current value, see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&self)
+    where
+        T: Clone,
+    {
+        self.write_lock().save();
+    }
+
+    /// Pushes a new current value, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&self, new_current: T) {
+        self.write_lock().push(new_current);
+    }
+
+    /// Undoes to the previous state, returning `true` if there was a previous state to undo to,
+    /// see [`UndoStack::undo`]
+    #[allow(clippy::must_use_candidate)]
+    pub fn undo(&self) -> bool {
+        self.write_lock().undo().is_ok()
+    }
+
+    /// Redoes to the next state, returning `true` if there was a future state to redo to, see
+    /// [`UndoStack::redo`]
+    #[allow(clippy::must_use_candidate)]
+    pub fn redo(&self) -> bool {
+        self.write_lock().redo().is_ok()
+    }
+
+    /// Runs `f` with a shared reference to the current value while holding the read lock
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(self.read_lock().get())
+    }
+
+    /// Runs `f` with a shared reference to the whole stack while holding the read lock
+    pub fn read_stack<R>(&self, f: impl FnOnce(&UndoStack<T>) -> R) -> R {
+        f(&self.read_lock())
+    }
+
+    /// Runs `f` with a mutable reference to the whole stack while holding the write lock, this
+    /// allows multiple operations to be made without another thread observing the stack in
+    /// between
+    pub fn write<R>(&self, f: impl FnOnce(&mut UndoStack<T>) -> R) -> R {
+        f(&mut self.write_lock())
+    }
+}
+
+#[test]
+fn shared_undo_stack() {
+    let shared = SharedUndoStack::new(1u8);
+    let other = shared.clone();
+
+    other.save();
+    other.write(|stack| *stack.get_mut() += 1);
+
+    assert_eq!(shared.read(|v| *v), 2);
+    assert!(shared.undo());
+    assert!(!other.undo());
+    assert!(other.redo());
+    assert!(!shared.redo());
+
+    shared.push(7);
+    assert_eq!(other.read(|v| *v), 7);
+}
@@ -0,0 +1,21 @@
+//! The [`Snapshot`] trait for capturing and restoring part of the state of a value
+
+/// A value that can capture part of its state into a [`State`](Snapshot::State), and later be
+/// restored from it.
+///
+/// This allows keeping the history of only the parts of a value that matter, such as the document
+/// of an editor but not its render caches or file handles, by holding states in an
+/// [`UndoStack`](crate::UndoStack) instead of the value itself. With the `derive` feature,
+/// `#[derive(Snapshot)]` implements this for structs whose fields marked `#[snapshot]` make up the
+/// state.
+pub trait Snapshot {
+    /// The captured part of the state
+    type State;
+
+    /// Captures the current state
+    fn snapshot(&self) -> Self::State;
+
+    /// Restores a state that was previously captured by [`snapshot`](Snapshot::snapshot), leaving
+    /// anything that is not part of the state untouched
+    fn restore(&mut self, state: Self::State);
+}
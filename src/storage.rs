@@ -0,0 +1,307 @@
+//! The [`HistoryStorage`] trait, used to abstract over the container that backs the history of
+//! [`HistoryStack`](crate::HistoryStack) and [`UndoStack`](crate::UndoStack)
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::VecDeque, vec::Vec};
+#[cfg(feature = "allocator_api")]
+use core::alloc::Allocator;
+
+/// A stack-like container that can hold the history of a [`HistoryStack`](crate::HistoryStack) or
+/// [`UndoStack`](crate::UndoStack).
+///
+/// Both stack types are generic over their storage with [`Vec`] as the default, implementing this
+/// trait for your own container allows the use of ring buffers, fixed size arrays, instrumented
+/// storage and so on without any changes to the stack types themselves.
+///
+/// Index `0` is always the oldest value, and [`push`](HistoryStorage::push) and
+/// [`pop`](HistoryStorage::pop) operate on the newest end of the container.
+pub trait HistoryStorage<T> {
+    /// Appends a value to the newest end of the storage
+    fn push(&mut self, value: T);
+
+    /// Removes and returns the newest value of the storage, or `None` if it is empty
+    fn pop(&mut self) -> Option<T>;
+
+    /// Returns the amount of values held in the storage
+    fn len(&self) -> usize;
+
+    /// Returns a reference to the value at `index`, or `None` if it is out of bounds
+    fn get(&self, index: usize) -> Option<&T>;
+
+    /// Returns a mutable reference to the value at `index`, or `None` if it is out of bounds
+    fn get_mut(&mut self, index: usize) -> Option<&mut T>;
+
+    /// Shortens the storage to hold only the oldest `len` values, dropping the rest. This has no
+    /// effect if `len` is greater than or equal to the current length.
+    fn truncate(&mut self, len: usize);
+
+    /// Returns `true` if the storage holds no values
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hints that at least `additional` more values are about to be pushed, so storage that can
+    /// grow may allocate for them at once. The default implementation does nothing.
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
+    /// Drops the oldest `n` values of the storage, shifting the remaining values to start at index
+    /// `0`. If `n` is greater than the current length the storage is emptied.
+    ///
+    /// The default implementation pops every value into a temporary [`Vec`] and pushes the kept
+    /// values back, storage that can remove from its oldest end directly should override this.
+    /// Without the `alloc` feature there is no default implementation.
+    #[cfg(feature = "alloc")]
+    fn remove_oldest(&mut self, n: usize) {
+        let keep = self.len().saturating_sub(n);
+
+        let mut kept = Vec::with_capacity(keep);
+
+        while kept.len() < keep {
+            // keep is at most len, so we always have a value to pop here
+            kept.extend(self.pop());
+        }
+
+        self.truncate(0);
+
+        while let Some(v) = kept.pop() {
+            self.push(v);
+        }
+    }
+
+    /// Drops the oldest `n` values of the storage, shifting the remaining values to start at index
+    /// `0`. If `n` is greater than the current length the storage is emptied.
+    #[cfg(not(feature = "alloc"))]
+    fn remove_oldest(&mut self, n: usize);
+}
+
+/// Implements [`HistoryStorage`] for `Vec`, generic over its allocator when the `allocator_api`
+/// feature is enabled
+#[cfg(feature = "alloc")]
+macro_rules! vec_storage {
+    (impl [$($generics:tt)*] for $ty:ty) => {
+        impl<$($generics)*> HistoryStorage<T> for $ty {
+            fn push(&mut self, value: T) {
+                Vec::push(self, value);
+            }
+
+            fn pop(&mut self) -> Option<T> {
+                Vec::pop(self)
+            }
+
+            fn len(&self) -> usize {
+                Vec::len(self)
+            }
+
+            fn get(&self, index: usize) -> Option<&T> {
+                <[T]>::get(self, index)
+            }
+
+            fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+                <[T]>::get_mut(self, index)
+            }
+
+            fn truncate(&mut self, len: usize) {
+                Vec::truncate(self, len);
+            }
+
+            fn reserve(&mut self, additional: usize) {
+                Vec::reserve(self, additional);
+            }
+
+            fn remove_oldest(&mut self, n: usize) {
+                self.drain(..n.min(self.len()));
+            }
+        }
+    };
+}
+
+#[cfg(all(feature = "alloc", not(feature = "allocator_api")))]
+vec_storage!(impl [T] for Vec<T>);
+
+#[cfg(feature = "allocator_api")]
+vec_storage!(impl [T, A: Allocator] for Vec<T, A>);
+
+#[cfg(feature = "alloc")]
+impl<T> HistoryStorage<T> for VecDeque<T> {
+    fn push(&mut self, value: T) {
+        self.push_back(value);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_back()
+    }
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        VecDeque::get(self, index)
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        VecDeque::get_mut(self, index)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        VecDeque::truncate(self, len);
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        VecDeque::reserve(self, additional);
+    }
+
+    fn remove_oldest(&mut self, n: usize) {
+        self.drain(..n.min(self.len()));
+    }
+}
+
+/// Fixed capacity storage holding at most `N` values inline, without allocating.
+///
+/// This is the storage to use when the `alloc` feature is disabled. Pushing to a full
+/// `ArrayStorage` panics, so the history of a stack using it should be bounded, such as by
+/// calling [`UndoStack::keep_last`](crate::UndoStack::keep_last) before saving.
+/// ```rust
+/// # use history_stack::{ArrayStorage, UndoStack};
+/// let mut undo = UndoStack::with_storage(0u8, ArrayStorage::<_, 3>::new());
+///
+/// for _ in 0..5 {
+///     undo.keep_last(2);
+///     *undo.save() += 1;
+/// }
+///
+/// assert_eq!(undo, 5);
+/// assert_eq!(undo.len(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ArrayStorage<T, const N: usize> {
+    /// The slots of the storage, the first `len` of which are occupied
+    items: [Option<T>; N],
+    /// The amount of occupied slots
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayStorage<T, N> {
+    /// Creates an empty `ArrayStorage`
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            items: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    /// Returns `true` if the storage holds `N` values, in which case the next push panics
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+}
+
+impl<T, const N: usize> Default for ArrayStorage<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> HistoryStorage<T> for ArrayStorage<T, N> {
+    /// # Panics
+    /// This will panic if the storage was full
+    fn push(&mut self, value: T) {
+        assert!(!self.is_full(), "ArrayStorage::push: storage was full");
+
+        self.items[self.len] = Some(value);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        self.items[self.len].take()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.items[..self.len].get(index)?.as_ref()
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.items[..self.len].get_mut(index)?.as_mut()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+
+    fn remove_oldest(&mut self, n: usize) {
+        let n = n.min(self.len);
+
+        // the removed values are moved past the kept ones, and dropped by truncating
+        self.items[..self.len].rotate_left(n);
+        self.truncate(self.len - n);
+    }
+}
+
+#[test]
+fn array_storage() {
+    use crate::{HistoryStack, UndoStack};
+
+    let mut undo = UndoStack::with_storage(0u8, ArrayStorage::<_, 4>::new());
+
+    for _ in 0..3 {
+        *undo.save() += 1;
+    }
+
+    assert_eq!(undo.keep_last(2), 2);
+    assert_eq!(*undo.undo().unwrap(), 2);
+    assert!(undo.undo().is_err());
+
+    let mut hist = HistoryStack::with_storage(0u8, ArrayStorage::<_, 1>::new());
+    hist.push_value(1);
+    assert_eq!(hist.pop(), Some(1));
+    assert_eq!(hist.pop(), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn vecdeque_storage() {
+    use crate::{HistoryStack, UndoStack};
+
+    let mut undo = UndoStack::with_storage(0u8, VecDeque::new());
+
+    *undo.save() += 1;
+    *undo.save() += 1;
+
+    assert_eq!(*undo.undo().unwrap(), 1);
+    assert_eq!(*undo.undo().unwrap(), 0);
+    assert!(undo.undo().is_err());
+    assert_eq!(*undo.redo().unwrap(), 1);
+
+    let mut hist = HistoryStack::with_storage(3u8, VecDeque::from([1, 2]));
+
+    assert_eq!(hist.pop(), Some(3));
+    assert_eq!(hist.pop(), Some(2));
+    assert_eq!(hist, 1);
+    assert_eq!(hist.pop(), None);
+}
+
+#[cfg(feature = "allocator_api")]
+#[test]
+fn allocator_storage() {
+    use crate::{HistoryStack, UndoStack};
+    use alloc::alloc::Global;
+
+    let mut undo = UndoStack::new_in(0u8, Global);
+    *undo.save() += 1;
+    assert_eq!(*undo.undo().unwrap(), 0);
+
+    let mut hist = HistoryStack::new_in(0u8, Global);
+    hist.push_value(1);
+    assert_eq!(hist.pop(), Some(1));
+}
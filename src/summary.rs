@@ -0,0 +1,87 @@
+//! A small copyable summary of the state of an undo stack, for user interfaces to poll
+
+use crate::{HistoryStorage, UndoStack};
+
+/// The length and dirtiness of the history of an [`UndoStack`] at one point in time, created by
+/// [`UndoStack::summary`].
+///
+/// A summary borrows nothing, so user interfaces can keep the last one around and compare it with
+/// a fresh one each frame to decide whether undo and redo buttons need to be redrawn.
+/// ```rust
+/// # use history_stack::UndoStack;
+/// let mut undo = UndoStack::new(0u8);
+/// *undo.save() = 1;
+///
+/// let before = undo.summary();
+/// assert_eq!((before.past, before.future), (1, 0));
+///
+/// undo.undo().unwrap();
+/// let after = undo.summary();
+///
+/// assert_ne!(before, after);
+/// assert_eq!((after.past, after.future), (0, 1));
+/// assert!(!after.dirty);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct HistorySummary {
+    /// The amount of states before the current value, that can be undone to
+    pub past: usize,
+    /// The amount of states after the current value, that can be redone to
+    pub future: usize,
+    /// The [`version`](UndoStack::version) of the stack
+    pub version: u64,
+    /// Whether the current value is not the state marked clean, see [`UndoStack::is_dirty`]
+    pub dirty: bool,
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns a counter that changes whenever the position or entries of history change, such as
+    /// by saving, undoing, redoing or trimming history. Edits made to the current value in place
+    /// are not counted, and the counter wraps on overflow.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns a [`HistorySummary`] of the current state of the stack
+    #[must_use]
+    pub fn summary(&self) -> HistorySummary {
+        self.invariant_ck();
+
+        HistorySummary {
+            past: self.current,
+            future: self.history.len() - self.current - 1,
+            version: self.version,
+            dirty: self.is_dirty(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn summary() {
+    let mut undo = UndoStack::new(0u8);
+    assert_eq!(undo.summary(), HistorySummary::default());
+
+    *undo.save() = 1;
+    *undo.save() = 2;
+    undo.undo().unwrap();
+
+    let summary = undo.summary();
+    assert_eq!((summary.past, summary.future), (1, 1));
+    assert!(summary.dirty);
+    assert_eq!(summary.version, 3);
+
+    // failed navigation and in place edits leave the version as is
+    undo.redo().unwrap();
+    assert!(undo.redo().is_err());
+    *undo.get_mut() = 5;
+    assert_eq!(undo.version(), 4);
+
+    assert_eq!(undo.keep_last(5), 0);
+    assert_eq!(undo.set_position(2), Ok(()));
+    assert_eq!(undo.version(), 4);
+
+    assert_eq!(undo.keep_last(1), 2);
+    assert_eq!(undo.summary().version, 5);
+}
@@ -0,0 +1,440 @@
+//! An undo stack that tracks which entries changed since it was last synchronized with a peer
+
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "deref")]
+use core::ops;
+
+use crate::UndoStack;
+
+/// The entries of a [`SyncedUndoStack`] that changed since it was last synchronized, produced by
+/// [`sync_delta`](SyncedUndoStack::sync_delta) and applied on a peer with
+/// [`apply_remote_sync`](SyncedUndoStack::apply_remote_sync).
+///
+/// Every entry before `base` is unchanged, every entry from `base` onward is replaced by
+/// `entries`, and any entry the peer held past them was invalidated.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SyncDelta<T> {
+    /// The index of the first entry that changed
+    pub base: usize,
+    /// The entries of history from `base` onward
+    pub entries: Vec<T>,
+    /// The index of the current value in the whole history
+    pub current: usize,
+    /// The revision of the stack the delta was taken from, which
+    /// [`mark_synced`](SyncedUndoStack::mark_synced) uses to tell which changes were made after it
+    pub revision: u64,
+}
+
+/// The error returned by [`SyncedUndoStack::apply_remote_sync`] when a [`SyncDelta`] cannot be
+/// applied, in which case the stack is left unchanged
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SyncError {
+    /// The delta starts past the end of the local history, so entries between them are missing,
+    /// a full sync is needed to recover
+    MissingEntries {
+        /// The index the delta starts at
+        base: usize,
+        /// The length of the local history
+        len: usize,
+    },
+    /// The current position of the delta was not within the history it describes
+    CurrentOutOfBounds,
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingEntries { base, len } => write!(
+                f,
+                "sync delta starts at entry {base} but only {len} entries are held locally"
+            ),
+            Self::CurrentOutOfBounds => {
+                f.write_str("sync delta current position was out of bounds")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SyncError {}
+
+/// An [`UndoStack`] that tracks the oldest entry that changed since it was last synchronized with
+/// a peer, so only the changed tail of history has to be sent instead of the whole history.
+///
+/// Undo and redo only move the current position, and hand out shared references so that states
+/// that are only looked at are not considered changed. Any mutable access to the current value
+/// through [`get_mut`](SyncedUndoStack::get_mut) marks it as changed.
+/// ```rust
+/// # use history_stack::SyncedUndoStack;
+/// let mut local = SyncedUndoStack::new(0u8);
+/// let mut remote = local.clone();
+///
+/// *local.save() += 1;
+/// *local.save() += 1;
+///
+/// let delta = local.sync_delta();
+/// assert_eq!(delta.base, 1);
+/// // once the peer acknowledged the delta
+/// local.mark_synced(&delta);
+///
+/// remote.apply_remote_sync(delta).unwrap();
+/// assert_eq!(*remote.get(), 2);
+///
+/// local.undo().unwrap();
+///
+/// // nothing but the position changed
+/// assert!(local.sync_delta().entries.is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SyncedUndoStack<T> {
+    /// The underlying stack
+    stack: UndoStack<T>,
+    /// The index of the oldest entry that changed since the last synchronization
+    dirty_from: usize,
+    /// Incremented every time an entry changes
+    revision: u64,
+    /// The revision of the last acknowledged delta
+    synced_revision: u64,
+    /// The changes made since the last acknowledged delta as `(revision, index)`, with indices
+    /// increasing, where each index is the oldest one that changed at or after its revision
+    changes: Vec<(u64, usize)>,
+}
+
+impl<T: Default> Default for SyncedUndoStack<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> SyncedUndoStack<T> {
+    /// Creates a new `SyncedUndoStack` with a starting value to act as the current value, which is
+    /// considered synchronized
+    pub fn new(start: T) -> Self {
+        Self::from_stack(UndoStack::new(start))
+    }
+
+    /// Wraps an existing `UndoStack`, considering its whole history synchronized.
+    ///
+    /// Any [undo](UndoStack::set_undo_limit) or [redo](UndoStack::set_redo_limit) limit or
+    /// [size budget](UndoStack::set_size_budget) of `stack` is removed, as entries are addressed
+    /// by their index in history when synchronizing, which evicting entries would shift.
+    #[must_use]
+    pub fn from_stack(mut stack: UndoStack<T>) -> Self {
+        stack.set_undo_limit(None);
+        stack.set_redo_limit(None);
+        stack.clear_size_budget();
+
+        Self {
+            dirty_from: stack.len(),
+            stack,
+            revision: 0,
+            synced_revision: 0,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Marks every entry from `index` onward as changed
+    fn mark_dirty(&mut self, index: usize) {
+        self.dirty_from = self.dirty_from.min(index);
+        self.revision += 1;
+
+        // older changes from `index` onward are covered by this one
+        while self.changes.last().map_or(false, |&(_, i)| i >= index) {
+            self.changes.pop();
+        }
+
+        self.changes.push((self.revision, index));
+    }
+
+    /// Saves the current value to history, see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        self.mark_dirty(self.stack.current + 1);
+        self.stack.save()
+    }
+
+    /// Pushes a new current value, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        self.mark_dirty(self.stack.current + 1);
+        self.stack.push(new_current)
+    }
+
+    /// Undoes to the previous state, see [`UndoStack::undo`]. Only a shared reference is returned
+    /// so that the state is not considered changed.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&T, &T> {
+        match self.stack.undo() {
+            Ok(v) => Ok(v),
+            Err(v) => Err(v),
+        }
+    }
+
+    /// Redoes to the next state, see [`UndoStack::redo`]. Only a shared reference is returned so
+    /// that the state is not considered changed.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&T, &T> {
+        match self.stack.redo() {
+            Ok(v) => Ok(v),
+            Err(v) => Err(v),
+        }
+    }
+
+    /// Returns the entries that changed since the last delta acknowledged with
+    /// [`mark_synced`](SyncedUndoStack::mark_synced), along with the current position
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn sync_delta(&self) -> SyncDelta<T>
+    where
+        T: Clone,
+    {
+        self.delta_from(self.dirty_from)
+    }
+
+    /// Returns every entry of history along with the current position, for peers that have no
+    /// history yet or failed to apply a delta
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn full_sync(&self) -> SyncDelta<T>
+    where
+        T: Clone,
+    {
+        self.delta_from(0)
+    }
+
+    /// Returns the entries from `base` onward along with the current position, for peers that
+    /// track on their own which prefix of history they already hold, such as when synchronizing
+    /// with several peers at once. `base` is clamped to the length of history.
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn delta_from(&self, base: usize) -> SyncDelta<T>
+    where
+        T: Clone,
+    {
+        let base = base.min(self.stack.len());
+
+        SyncDelta {
+            base,
+            entries: (base..self.stack.len())
+                .map(|i| self.stack.at(i).clone())
+                .collect(),
+            current: self.stack.current,
+            revision: self.revision,
+        }
+    }
+
+    /// Marks the history covered by `delta` as synchronized, this should be called once a delta
+    /// produced by this stack was acknowledged by the peer.
+    ///
+    /// Entries that changed after `delta` was produced are still considered changed, and deltas
+    /// older than the last acknowledged one are ignored.
+    pub fn mark_synced(&mut self, delta: &SyncDelta<T>) {
+        if delta.revision < self.synced_revision {
+            return;
+        }
+
+        let acked = delta.base + delta.entries.len();
+        let newer = self.changes.partition_point(|&(r, _)| r <= delta.revision);
+        self.changes.drain(..newer);
+
+        self.dirty_from = self.changes.first().map_or(acked, |&(_, i)| i.min(acked));
+        self.synced_revision = delta.revision;
+    }
+
+    /// Reconciles local history with a [`SyncDelta`] produced by a peer, replacing every entry
+    /// from its base onward and moving to its current position. Local history is considered
+    /// synchronized afterwards.
+    ///
+    /// # Errors
+    /// If the delta starts past the end of the local history, or its current position is not
+    /// within the resulting history. The stack is left unchanged on error.
+    pub fn apply_remote_sync(&mut self, delta: SyncDelta<T>) -> Result<(), SyncError> {
+        let SyncDelta {
+            base,
+            entries,
+            current,
+            ..
+        } = delta;
+
+        let len = self.stack.len();
+
+        if base > len {
+            return Err(SyncError::MissingEntries { base, len });
+        }
+
+        if current >= base + entries.len() {
+            return Err(SyncError::CurrentOutOfBounds);
+        }
+
+        self.stack.history.truncate(base);
+        self.stack.clean_truncated(base);
+        self.stack.history.extend(entries);
+        self.stack.current = current;
+        self.stack.bump_version();
+
+        // deltas produced before this are stale now
+        self.revision += 1;
+        self.synced_revision = self.revision;
+        self.changes.clear();
+        self.dirty_from = self.stack.len();
+
+        Ok(())
+    }
+
+    /// Returns the index of the oldest entry that changed since the last synchronization, this is
+    /// equal to the length of history if nothing changed
+    #[must_use]
+    pub fn dirty_from(&self) -> usize {
+        self.dirty_from
+    }
+
+    /// Returns a reference to the underlying stack
+    #[must_use]
+    pub fn stack(&self) -> &UndoStack<T> {
+        &self.stack
+    }
+
+    /// Returns a reference to the current value, unlike
+    /// [`get_mut`](SyncedUndoStack::get_mut) this does not mark it as changed
+    #[must_use]
+    pub fn get(&self) -> &T {
+        self.stack.get()
+    }
+
+    /// Returns a mutable reference to the current value, marking it as changed since the last
+    /// sync
+    pub fn get_mut(&mut self) -> &mut T {
+        self.mark_dirty(self.stack.current);
+        self.stack.get_mut()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::Deref for SyncedUndoStack<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T> ops::DerefMut for SyncedUndoStack<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn delta_sync() {
+    let mut local = SyncedUndoStack::new(0u8);
+    let mut remote = SyncedUndoStack::new(0u8);
+
+    for _ in 0..3 {
+        *local.save() += 1;
+    }
+
+    let delta = local.sync_delta();
+    local.mark_synced(&delta);
+    remote.apply_remote_sync(delta).unwrap();
+    assert_eq!(remote.stack(), local.stack());
+
+    local.undo().unwrap();
+    local.undo().unwrap();
+    *local.get_mut() += 10;
+    local.push(20);
+
+    let delta = local.sync_delta();
+    assert_eq!(delta.base, 1);
+    assert_eq!(delta.entries, [11, 20]);
+    assert_eq!(delta.current, 2);
+
+    remote.apply_remote_sync(delta).unwrap();
+    assert_eq!(*remote.get(), 20);
+    assert_eq!(remote.stack().len(), 3);
+    assert_eq!(*remote.undo().unwrap(), 11);
+
+    let mut fresh = SyncedUndoStack::new(0u8);
+    let gap = SyncDelta {
+        base: 2,
+        ..local.sync_delta()
+    };
+    assert_eq!(
+        fresh.apply_remote_sync(gap),
+        Err(SyncError::MissingEntries { base: 2, len: 1 })
+    );
+
+    fresh.apply_remote_sync(local.full_sync()).unwrap();
+    assert_eq!(fresh.stack(), local.stack());
+}
+
+#[test]
+fn edits_before_acknowledgement() {
+    let mut local = SyncedUndoStack::new(0u8);
+    for _ in 0..3 {
+        *local.save() += 1;
+    }
+
+    let first = local.sync_delta();
+    assert_eq!(first.base, 1);
+
+    // changed while the delta is in flight
+    local.undo().unwrap();
+    *local.get_mut() += 10;
+    let second = local.sync_delta();
+    local.push(20);
+
+    local.mark_synced(&first);
+    assert_eq!(local.dirty_from(), 2);
+    assert_eq!(local.sync_delta().entries, [12, 20]);
+
+    local.mark_synced(&second);
+    assert_eq!(local.dirty_from(), 3);
+
+    // stale acknowledgements are ignored
+    local.mark_synced(&first);
+    assert_eq!(local.dirty_from(), 3);
+
+    let latest = local.sync_delta();
+    local.mark_synced(&latest);
+    assert_eq!(local.dirty_from(), 4);
+
+    // peers that track what they hold on their own
+    assert_eq!(local.delta_from(1).entries, [1, 12, 20]);
+    assert_eq!(local.delta_from(9).base, 4);
+}
+
+#[test]
+fn limits_are_removed() {
+    let mut stack = UndoStack::new(0u8);
+    stack.set_undo_limit(Some(1));
+    stack.set_redo_limit(Some(0));
+    stack.set_size_budget(1, |_| 1);
+
+    let mut local = SyncedUndoStack::from_stack(stack);
+    for _ in 0..3 {
+        *local.save() += 1;
+    }
+    local.undo().unwrap();
+
+    assert_eq!(local.stack().len(), 4);
+    assert_eq!(local.stack().undo_limit(), None);
+    assert_eq!(local.stack().redo_limit(), None);
+    assert_eq!(local.stack().size_budget(), None);
+    assert_eq!(local.sync_delta().entries, [1, 2, 3]);
+}
@@ -0,0 +1,324 @@
+//! An undo stack for strings that stores its history as edit scripts instead of full copies
+
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "deref")]
+use core::ops;
+
+/// A single replacement of text, the minimal edit turning one string into another one
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+struct Splice {
+    /// Byte offset the replacement starts at
+    at: usize,
+    /// The text that is replaced
+    old: String,
+    /// The text it is replaced with
+    new: String,
+}
+
+impl Splice {
+    /// Computes the splice turning `from` into `to`, by trimming their common prefix and suffix
+    fn between(from: &str, to: &str) -> Self {
+        let (a, b) = (from.as_bytes(), to.as_bytes());
+
+        let mut prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+
+        while !(from.is_char_boundary(prefix) && to.is_char_boundary(prefix)) {
+            prefix -= 1;
+        }
+
+        let max_suffix = a.len().min(b.len()) - prefix;
+
+        let mut suffix = a
+            .iter()
+            .rev()
+            .zip(b.iter().rev())
+            .take(max_suffix)
+            .take_while(|(x, y)| x == y)
+            .count();
+
+        while !(from.is_char_boundary(a.len() - suffix) && to.is_char_boundary(b.len() - suffix)) {
+            suffix -= 1;
+        }
+
+        Self {
+            at: prefix,
+            old: String::from(&from[prefix..a.len() - suffix]),
+            new: String::from(&to[prefix..b.len() - suffix]),
+        }
+    }
+
+    /// Applies this splice to `text`, which must hold `old` at `at`
+    fn apply(&self, text: &mut String) {
+        text.replace_range(self.at..self.at + self.old.len(), &self.new);
+    }
+
+    /// Returns the splice that undoes this one
+    fn inverse(self) -> Self {
+        Self {
+            at: self.at,
+            old: self.new,
+            new: self.old,
+        }
+    }
+
+    /// Returns the amount of bytes of text held by this splice
+    fn bytes(&self) -> usize {
+        self.old.len() + self.new.len()
+    }
+}
+
+/// An undo stack specialized for [`String`], that only keeps the current value in full and stores
+/// every other entry of history as the edit turning its neighbour into it.
+///
+/// Edits are computed by trimming the common prefix and suffix of two versions, so typing,
+/// deleting, or replacing within one place of the text only stores the changed part. This makes
+/// saving on every keystroke cost memory proportional to the keystroke instead of the whole text.
+///
+/// The current value can be edited freely through [`get_mut`](StringUndoStack::get_mut), like
+/// an [`UndoStack`](crate::UndoStack). An extra copy of the current value as it was at the last
+/// operation is kept, to compute the edits it went through when history is next moved or saved.
+/// ```rust
+/// # use history_stack::StringUndoStack;
+/// let mut undo = StringUndoStack::new(String::from("hello world"));
+///
+/// undo.save().insert_str(5, ",");
+/// undo.save().push('!');
+///
+/// assert_eq!(undo.get(), "hello, world!");
+///
+/// assert_eq!(undo.undo().unwrap(), "hello, world");
+/// assert_eq!(undo.undo().unwrap(), "hello world");
+/// assert_eq!(undo.redo().unwrap(), "hello, world");
+///
+/// // only the inserted "," and "!" are kept in history
+/// assert_eq!(undo.history_bytes(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StringUndoStack {
+    /// The current value
+    value: String,
+    /// The current value as it was when every splice was last computed against it
+    anchor: String,
+    /// Splices turning each entry into the one before it, the last one applies to the current
+    /// value
+    back: Vec<Splice>,
+    /// Splices turning each entry into the one after it, the last one applies to the current
+    /// value
+    forward: Vec<Splice>,
+}
+
+impl StringUndoStack {
+    /// Creates a new `StringUndoStack` with a starting value to act as the current value
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[must_use]
+    pub fn new(start: String) -> Self {
+        Self {
+            anchor: start.clone(),
+            value: start,
+            back: Vec::new(),
+            forward: Vec::new(),
+        }
+    }
+
+    /// Recomputes the splices neighbouring the current value if it was edited since the last
+    /// operation
+    fn sync(&mut self) {
+        if self.value == self.anchor {
+            return;
+        }
+
+        for splices in [&mut self.back, &mut self.forward] {
+            if let Some(splice) = splices.pop() {
+                let mut neighbour = self.anchor.clone();
+                splice.apply(&mut neighbour);
+
+                splices.push(Splice::between(&self.value, &neighbour));
+            }
+        }
+
+        self.anchor.clone_from(&self.value);
+    }
+
+    /// Applies a splice to the current value, which must be in sync
+    fn apply(&mut self, splice: &Splice) {
+        splice.apply(&mut self.value);
+        splice.apply(&mut self.anchor);
+    }
+
+    /// Saves the current value to history and invalidates any data that may be used to redo,
+    /// returning a reference to the new current value, see
+    /// [`UndoStack::save`](crate::UndoStack::save)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut String {
+        self.sync();
+        self.forward.clear();
+
+        // the new current value is equal to the saved one until it is edited
+        self.back.push(Splice::default());
+
+        &mut self.value
+    }
+
+    /// Pushes the given value to the stack, making it the new current value and invalidating
+    /// future history, see [`UndoStack::push`](crate::UndoStack::push)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: String) -> &mut String {
+        self.sync();
+        self.forward.clear();
+
+        self.back.push(Splice::between(&new_current, &self.value));
+        self.value = new_current;
+        self.anchor.clone_from(&self.value);
+
+        &mut self.value
+    }
+
+    /// Undoes to the previous state, see [`UndoStack::undo`](crate::UndoStack::undo)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut String, &mut String> {
+        self.sync();
+
+        let Some(splice) = self.back.pop() else {
+            return Err(&mut self.value);
+        };
+
+        self.apply(&splice);
+        self.forward.push(splice.inverse());
+
+        Ok(&mut self.value)
+    }
+
+    /// Redoes to the next state, see [`UndoStack::redo`](crate::UndoStack::redo)
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut String, &mut String> {
+        self.sync();
+
+        let Some(splice) = self.forward.pop() else {
+            return Err(&mut self.value);
+        };
+
+        self.apply(&splice);
+        self.back.push(splice.inverse());
+
+        Ok(&mut self.value)
+    }
+
+    /// Returns the amount of text in bytes held by history, not counting the current value and
+    /// its copy
+    #[must_use]
+    pub fn history_bytes(&self) -> usize {
+        self.back
+            .iter()
+            .chain(&self.forward)
+            .map(Splice::bytes)
+            .sum()
+    }
+
+    /// Returns the amount of values held in history, including the current value
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.back.len() + 1 + self.forward.len()
+    }
+
+    /// Always returns `false`, as even empty text is a state of history
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in history
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.back.len()
+    }
+
+    /// Returns `true` if there is a previous state to [`undo`](StringUndoStack::undo) to
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    /// Returns `true` if there is a future state to [`redo`](StringUndoStack::redo) to
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    /// Returns the current text
+    #[must_use]
+    pub fn get(&self) -> &String {
+        &self.value
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut String {
+        &mut self.value
+    }
+}
+
+#[cfg(feature = "deref")]
+impl ops::Deref for StringUndoStack {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl ops::DerefMut for StringUndoStack {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+#[test]
+fn splice_between() {
+    let s = Splice::between("héllo", "hállo");
+    assert_eq!((s.at, &*s.old, &*s.new), (1, "é", "á"));
+
+    let s = Splice::between("aaa", "aa");
+    assert_eq!((s.at, &*s.old, &*s.new), (2, "a", ""));
+
+    let s = Splice::between("", "new");
+    assert_eq!((s.at, &*s.old, &*s.new), (0, "", "new"));
+}
+
+#[test]
+fn string_undo_stack() {
+    let mut undo = StringUndoStack::new(String::from("abc"));
+
+    undo.save().push('d');
+    undo.save().remove(0);
+    undo.push(String::from("xyz"));
+
+    assert_eq!(undo.len(), 4);
+    assert_eq!(undo.undo().unwrap(), "bcd");
+
+    // editing an entry in place keeps both of its neighbours reachable
+    undo.get_mut().push_str("!!");
+    assert_eq!(undo.undo().unwrap(), "abcd");
+    assert_eq!(undo.redo().unwrap(), "bcd!!");
+    assert_eq!(undo.redo().unwrap(), "xyz");
+    assert!(undo.redo().is_err());
+
+    undo.undo().unwrap();
+    undo.save().clear();
+    assert!(!undo.can_redo());
+    assert_eq!(undo.undo().unwrap(), "bcd!!");
+    assert_eq!(undo.undo().unwrap(), "abcd");
+    assert_eq!(undo.undo().unwrap(), "abc");
+    assert!(undo.undo().is_err());
+}
@@ -0,0 +1,94 @@
+//! Exponential thinning of old history, keeping fine granularity only for recent states
+
+use alloc::vec::Vec;
+
+use crate::UndoStack;
+
+/// Returns `true` if the state `age` entries before the current value survives thinning with
+/// `recent` entries kept at full granularity
+fn keeps(age: usize, recent: usize) -> bool {
+    // band k starts at age `offset`, spans recent * 2^k ages and keeps every 2^k-th of them, so
+    // each band keeps `recent` entries
+    let mut offset = 1;
+    let mut step = 1usize;
+
+    loop {
+        let span = recent.saturating_mul(step);
+
+        if age - offset < span {
+            return (age - offset) % step == 0;
+        }
+
+        offset += span;
+        step = step.saturating_mul(2);
+    }
+}
+
+impl<T> UndoStack<T, Vec<T>> {
+    /// Thins out the past of the stack, keeping all of the `recent` states before the current
+    /// value, then every 2nd of the `recent * 2` states before those, every 4th of the
+    /// `recent * 4` states before those and so on, returning the amount of states dropped.
+    ///
+    /// This bounds history to roughly `recent * log2(len / recent)` states while keeping a
+    /// coarse view of the whole session. The current value and everything after it are never
+    /// dropped. Thinning works on positions rather than on time, so calling it again after more
+    /// saves thins the older bands further.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(0u8);
+    /// for i in 1..=10 {
+    ///     undo.push(i);
+    /// }
+    ///
+    /// assert_eq!(undo.thin(2), 5);
+    /// assert_eq!(undo.freeze().entries(), [3, 5, 7, 8, 9, 10]);
+    /// ```
+    ///
+    /// # Panics
+    /// This will panic if `recent` is 0
+    pub fn thin(&mut self, recent: usize) -> usize {
+        assert!(recent != 0, "UndoStack::thin: recent was 0");
+
+        self.invariant_ck();
+
+        let current = self.current;
+        let before = self.history.len();
+        let keep = |i: usize| i >= current || keeps(current - i, recent);
+        let mut index = 0;
+
+        self.clean_retain(keep);
+        self.history.retain(|_| {
+            let keep = keep(index);
+            index += 1;
+            keep
+        });
+
+        let dropped = before - self.history.len();
+        self.current -= dropped;
+
+        if dropped > 0 {
+            self.bump_version();
+        }
+
+        dropped
+    }
+}
+
+#[test]
+fn thin() {
+    let mut undo = UndoStack::new(0u16);
+    for i in 1..=100 {
+        undo.push(i);
+    }
+    undo.undo().unwrap();
+
+    assert_eq!(undo.thin(4), 80);
+    assert_eq!(undo.len(), 21);
+    assert_eq!(undo, 99);
+
+    // 4 full, 4 of 8, 4 of 16, 4 of 32 and 3 of the remaining 39 states before the current value
+    assert_eq!(undo.position(), 19);
+    assert_eq!(undo.history[..19].first(), Some(&6));
+    assert_eq!(undo.history[..19].last(), Some(&98));
+    assert_eq!(undo.redo(), Ok(&mut 100));
+}
@@ -0,0 +1,406 @@
+//! The [`Clock`] trait, and an [`UndoStack`] that timestamps every entry in its history
+
+use core::ops;
+
+use crate::{HistoryHook, UndoStack};
+
+/// A source of timestamps for the entries of a [`TimedUndoStack`].
+///
+/// `Instant` can be any ordered type, such as a tick counter on embedded targets or
+/// `std::time::Instant` (see `StdClock` with the `std` feature). Any `Fn() -> I` closure is also a
+/// `Clock` if `I` can be subtracted from itself, with the result of the subtraction as its
+/// `Duration`.
+///
+/// Timestamps returned by a clock are expected to never decrease, as time based lookups rely on
+/// the history being in chronological order.
+pub trait Clock {
+    /// The timestamp type of this clock
+    type Instant: Copy + Ord;
+
+    /// The type of the time elapsed between two timestamps
+    type Duration: Copy + Ord;
+
+    /// Returns the current time
+    fn now(&self) -> Self::Instant;
+
+    /// Returns the time elapsed from `earlier` to `later`
+    fn between(&self, earlier: Self::Instant, later: Self::Instant) -> Self::Duration;
+}
+
+impl<I, F> Clock for F
+where
+    I: Copy + Ord + ops::Sub,
+    I::Output: Copy + Ord,
+    F: Fn() -> I,
+{
+    type Instant = I;
+    type Duration = I::Output;
+
+    fn now(&self) -> I {
+        self()
+    }
+
+    fn between(&self, earlier: I, later: I) -> I::Output {
+        later - earlier
+    }
+}
+
+/// A [`Clock`] using [`std::time::Instant`]
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StdClock;
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    type Instant = std::time::Instant;
+    type Duration = std::time::Duration;
+
+    fn now(&self) -> Self::Instant {
+        std::time::Instant::now()
+    }
+
+    fn between(&self, earlier: Self::Instant, later: Self::Instant) -> Self::Duration {
+        later.saturating_duration_since(earlier)
+    }
+}
+
+/// A value in history along with the time it was created at
+#[derive(Clone, Debug)]
+struct Stamped<T, I> {
+    /// The value
+    value: T,
+    /// When value was saved or pushed
+    at: I,
+}
+
+/// An [`UndoStack`] that stamps every entry of its history with the time it was created at, using
+/// a user provided [`Clock`].
+///
+/// The timestamp of an entry is the time it was saved or pushed at, mutating the current value
+/// afterwards does not change its timestamp.
+/// ```rust
+/// # use history_stack::TimedUndoStack;
+/// # use core::cell::Cell;
+/// let time = Cell::new(0u32);
+/// let mut undo = TimedUndoStack::new(0u8, || time.get());
+///
+/// time.set(10);
+/// *undo.save() = 1;
+///
+/// time.set(20);
+/// *undo.save() = 2;
+///
+/// // go back to the newest state that existed at time 15
+/// assert_eq!(*undo.undo_to(15).unwrap(), 1);
+/// assert_eq!(undo.timestamp(), 10);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TimedUndoStack<T, C: Clock> {
+    /// The underlying stack holding timestamped values
+    stack: UndoStack<Stamped<T, C::Instant>>,
+    /// The clock used to timestamp new entries
+    clock: C,
+}
+
+impl<T: Default, C: Clock + Default> Default for TimedUndoStack<T, C> {
+    fn default() -> Self {
+        Self::new(T::default(), C::default())
+    }
+}
+
+impl<T, C: Clock> TimedUndoStack<T, C> {
+    /// Creates a new `TimedUndoStack` with a starting value to act as the current value, stamped
+    /// with the current time of `clock`
+    pub fn new(start: T, clock: C) -> Self {
+        let at = clock.now();
+
+        Self {
+            stack: UndoStack::new(Stamped { value: start, at }),
+            clock,
+        }
+    }
+
+    /// Saves the current value to history, stamping the new current value with the current time,
+    /// see [`UndoStack::save`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn save(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        let at = self.clock.now();
+        let new = self.stack.save();
+        new.at = at;
+
+        &mut new.value
+    }
+
+    /// Pushes a new current value stamped with the current time, see [`UndoStack::push`]
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn push(&mut self, new_current: T) -> &mut T {
+        let at = self.clock.now();
+
+        &mut self
+            .stack
+            .push(Stamped {
+                value: new_current,
+                at,
+            })
+            .value
+    }
+
+    /// Undoes to the previous state, which keeps the timestamp it was created with, see
+    /// [`UndoStack::undo`] and [`undo_to`](TimedUndoStack::undo_to)
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo(&mut self) -> Result<&mut T, &mut T> {
+        match self.stack.undo() {
+            Ok(v) => Ok(&mut v.value),
+            Err(v) => Err(&mut v.value),
+        }
+    }
+
+    /// Redoes to the next state, which keeps the timestamp it was created with, see
+    /// [`UndoStack::redo`]
+    #[allow(clippy::missing_errors_doc)]
+    pub fn redo(&mut self) -> Result<&mut T, &mut T> {
+        match self.stack.redo() {
+            Ok(v) => Ok(&mut v.value),
+            Err(v) => Err(&mut v.value),
+        }
+    }
+
+    /// Undoes to the newest state that was created at or before `instant`, returning `Ok(&mut T)`
+    /// of the new current value. If the current value is already that state it is left as is and
+    /// returned in `Ok`.
+    ///
+    /// Returns `Err(&mut T)` of the unchanged current value if every state up to the current value
+    /// was created after `instant`. This never redoes, states after the current value are not
+    /// considered.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn undo_to(&mut self, instant: C::Instant) -> Result<&mut T, &mut T> {
+        let current = self.stack.current;
+
+        // history is in chronological order, so this finds the amount of states at or before
+        // instant
+        let count = self.stack.history[..=current].partition_point(|v| v.at <= instant);
+
+        match count.checked_sub(1) {
+            Some(index) => {
+                self.stack.move_to(index);
+
+                Ok(&mut self.stack.get_mut().value)
+            }
+            None => Err(&mut self.stack.history[current].value),
+        }
+    }
+
+    /// Returns the time the current value was saved or pushed at
+    pub fn timestamp(&self) -> C::Instant {
+        self.stack.get().at
+    }
+
+    /// Returns the time the value at `index` in history was saved or pushed at, or `None` if
+    /// `index` is out of bounds
+    pub fn timestamp_at(&self, index: usize) -> Option<C::Instant> {
+        self.stack.history.get(index).map(|v| v.at)
+    }
+
+    /// Returns the amount of values held in history, including the current value
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Always returns `false`, as the current value always counts towards
+    /// [`len`](TimedUndoStack::len)
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the index of the current value in history
+    pub fn position(&self) -> usize {
+        self.stack.position()
+    }
+
+    /// Returns a reference to the clock
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Returns a reference to the current value, without its
+    /// [`timestamp`](TimedUndoStack::timestamp)
+    #[must_use]
+    pub fn get(&self) -> &T {
+        &self.stack.get().value
+    }
+
+    /// Returns a mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.stack.get_mut().value
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, C: Clock> ops::Deref for TimedUndoStack<T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+#[cfg(feature = "deref")]
+impl<T, C: Clock> ops::DerefMut for TimedUndoStack<T, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.get_mut()
+    }
+}
+
+/// A [`HistoryHook`] that makes saves happening in quick succession act as a single undo step, as
+/// text editors do while typing, as measured by a user provided [`Clock`].
+///
+/// A save made within the window of the save or push that created the current value does not
+/// create a new entry, and instead keeps editing the current value. The window is measured from
+/// the save that created the current value, so a continuous burst of saves is split into steps of
+/// at most the window each.
+///
+/// Saves only coalesce into the newest entry right after it was created, they never coalesce after
+/// undoing or redoing. The very first entry of the history is also never coalesced into, so a save
+/// on a new stack always creates a step that can be undone.
+/// ```rust
+/// # use history_stack::{CoalesceWindow, HookedUndoStack};
+/// # use core::cell::Cell;
+/// let time = Cell::new(0u32);
+/// let mut undo = HookedUndoStack::new(String::new(), CoalesceWindow::new(|| time.get(), 300));
+///
+/// for (t, c) in [(0, 'h'), (100, 'i'), (500, '!')] {
+///     time.set(t);
+///     undo.save().push(c);
+/// }
+///
+/// assert_eq!(undo.undo(), Ok(&mut String::from("hi")));
+/// assert_eq!(undo.undo(), Ok(&mut String::new()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CoalesceWindow<C: Clock> {
+    /// The clock used to measure the time between saves
+    clock: C,
+    /// Saves made within this duration of the current value being created do not create a new
+    /// entry
+    window: C::Duration,
+    /// When the current value was created, `None` if it was moved to instead
+    created: Option<C::Instant>,
+}
+
+impl<C: Clock> CoalesceWindow<C> {
+    /// Creates a new `CoalesceWindow` that coalesces saves made within `window` of the current
+    /// value being created
+    pub fn new(clock: C, window: C::Duration) -> Self {
+        Self {
+            clock,
+            window,
+            created: None,
+        }
+    }
+
+    /// Sets the coalescing window, which also applies to the current value
+    pub fn set_window(&mut self, window: C::Duration) {
+        self.window = window;
+    }
+
+    /// Returns the coalescing window
+    pub fn window(&self) -> C::Duration {
+        self.window
+    }
+}
+
+impl<T, C: Clock> HistoryHook<T> for CoalesceWindow<C> {
+    fn coalesce_save(&mut self, stack: &UndoStack<T>) -> bool {
+        let now = self.clock.now();
+
+        self.created.map_or(false, |at| {
+            stack.can_undo() && !stack.can_redo() && self.clock.between(at, now) < self.window
+        })
+    }
+
+    fn on_save(&mut self, _: &UndoStack<T>) {
+        self.created = Some(self.clock.now());
+    }
+
+    fn on_push(&mut self, _: &UndoStack<T>) {
+        self.created = Some(self.clock.now());
+    }
+
+    fn on_undo(&mut self, _: &UndoStack<T>) {
+        self.created = None;
+    }
+
+    fn on_redo(&mut self, _: &UndoStack<T>) {
+        self.created = None;
+    }
+}
+
+#[test]
+fn timed_undo_stack() {
+    use core::cell::Cell;
+
+    let time = Cell::new(5u32);
+    let mut undo = TimedUndoStack::new(0u8, || time.get());
+
+    assert!(undo.undo_to(4).is_err());
+
+    for i in 1..=3 {
+        time.set(5 + u32::from(i) * 10);
+        *undo.save() = i;
+    }
+
+    assert_eq!(undo.timestamp_at(2), Some(25));
+    assert_eq!(*undo.undo_to(100).unwrap(), 3);
+    assert_eq!(*undo.undo_to(24).unwrap(), 1);
+    assert_eq!(undo.timestamp(), 15);
+
+    // states after the current are never considered
+    assert_eq!(*undo.undo_to(100).unwrap(), 1);
+    assert_eq!(*undo.undo_to(5).unwrap(), 0);
+    assert!(undo.undo_to(0).is_err());
+    assert_eq!(*undo.redo().unwrap(), 1);
+}
+
+#[test]
+fn coalescing() {
+    use core::cell::Cell;
+
+    use crate::HookedUndoStack;
+
+    let time = Cell::new(0u32);
+    let mut undo = HookedUndoStack::new(0u8, CoalesceWindow::new(|| time.get(), 300));
+
+    // the first save is never coalesced
+    *undo.save() += 1;
+
+    for t in [100, 200, 299] {
+        time.set(t);
+        *undo.save() += 1;
+    }
+
+    time.set(300);
+    *undo.save() += 1;
+
+    assert_eq!(undo.stack().len(), 3);
+    assert_eq!(*undo.get(), 5);
+    assert_eq!(*undo.undo().unwrap(), 4);
+
+    // saves after moving through history are never coalesced
+    time.set(301);
+    *undo.save() += 1;
+    assert_eq!(undo.stack().len(), 3);
+    assert_eq!(*undo.undo().unwrap(), 4);
+
+    undo.hook_mut().set_window(0);
+    assert_eq!(undo.hook().window(), 0);
+    assert_eq!(*undo.redo().unwrap(), 5);
+    *undo.save() += 1;
+    assert_eq!(undo.stack().len(), 4);
+}
@@ -0,0 +1,76 @@
+//! A readable rendering of the whole timeline of an undo stack, for logging and bug reports
+
+use core::fmt;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// A view of every entry of an [`UndoStack`] that formats them oldest first, one per line along
+/// with their index, marking the current value with `>`, created by [`UndoStack::timeline`].
+///
+/// [`Display`](fmt::Display) formats entries with their `Display` impl and [`Debug`](fmt::Debug)
+/// with their `Debug` impl, which respects the alternate `{:#?}` flag.
+/// ```rust
+/// # use history_stack::UndoStack;
+/// let mut undo = UndoStack::new(1u8);
+/// *undo.save() = 2;
+/// *undo.save() = 3;
+/// undo.undo().unwrap();
+///
+/// assert_eq!(undo.timeline().to_string(), "  0: 1\n> 1: 2\n  2: 3\n");
+/// ```
+pub struct Timeline<'a, T, S> {
+    /// The stack being formatted
+    stack: &'a UndoStack<T, S>,
+}
+
+impl<T, S: HistoryStorage<T>> Timeline<'_, T, S> {
+    /// Writes every entry on its own line, formatting values with `entry`
+    fn write(
+        &self,
+        f: &mut fmt::Formatter,
+        entry: impl Fn(&T, &mut fmt::Formatter) -> fmt::Result,
+    ) -> fmt::Result {
+        for i in 0..self.stack.len() {
+            let marker = if i == self.stack.current { '>' } else { ' ' };
+
+            write!(f, "{marker} {i}: ")?;
+            entry(self.stack.at(i), f)?;
+            f.write_str("\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: fmt::Display, S: HistoryStorage<T>> fmt::Display for Timeline<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, fmt::Display::fmt)
+    }
+}
+
+impl<T: fmt::Debug, S: HistoryStorage<T>> fmt::Debug for Timeline<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, fmt::Debug::fmt)
+    }
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns a [`Timeline`] that formats every entry of history with the current value
+    /// highlighted
+    #[must_use]
+    pub fn timeline(&self) -> Timeline<'_, T, S> {
+        Timeline { stack: self }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn timeline() {
+    use alloc::format;
+
+    let mut undo = UndoStack::new("a");
+    *undo.save() = "b";
+
+    assert_eq!(format!("{:?}", undo.timeline()), "  0: \"a\"\n> 1: \"b\"\n");
+    assert_eq!(format!("{}", undo.timeline()), "  0: a\n> 1: b\n");
+}
@@ -0,0 +1,210 @@
+//! Closure based, nestable transactions over a history stack
+
+use crate::{HistoryStack, HistoryStorage};
+
+/// What a [`HistoryStack`] does when a panic unwinds through one of its
+/// [transactions](HistoryStack::transaction), set with
+/// [`set_unwind_policy`](HistoryStack::set_unwind_policy)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UnwindPolicy {
+    /// Marks the stack as poisoned and leaves the current value as the panic left it, this is
+    /// the default
+    #[default]
+    Poison,
+    /// Rolls back to the savepoint of the transaction, as if it had returned `Err`, and does not
+    /// poison the stack
+    Rollback,
+}
+
+/// Applies the unwind policy of the stack it borrows if it is dropped before the transaction
+/// finished, which only happens when a panic unwinds through the transaction
+struct Transaction<'a, T, S: HistoryStorage<T>> {
+    /// The stack the transaction runs on
+    stack: &'a mut HistoryStack<T, S>,
+    /// The length of the stack before the savepoint of the transaction was pushed
+    depth: usize,
+    /// Whether the closure of the transaction returned
+    finished: bool,
+}
+
+impl<T, S: HistoryStorage<T>> Transaction<'_, T, S> {
+    /// Drops anything pushed during the transaction and restores its savepoint
+    fn rollback(&mut self) {
+        let stack = &mut *self.stack;
+
+        // the last pop restores the savepoint itself
+        while stack.stack.len() > self.depth + 1 {
+            stack.stack.pop();
+        }
+
+        stack.pop();
+    }
+}
+
+impl<T, S: HistoryStorage<T>> Drop for Transaction<'_, T, S> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        match self.stack.unwind {
+            UnwindPolicy::Poison => self.stack.poisoned = true,
+            UnwindPolicy::Rollback => self.rollback(),
+        }
+    }
+}
+
+impl<T, S: HistoryStorage<T>> HistoryStack<T, S> {
+    /// Runs `f` as a transaction, pushing a savepoint of the current value first. If `f` returns
+    /// `Err` the stack is rolled back to the savepoint, dropping anything `f` pushed, and if it
+    /// returns `Ok` the savepoint is kept, so the state before the transaction can be popped back
+    /// to.
+    ///
+    /// Transactions nest, a transaction started within `f` creates its own savepoint and an `Err`
+    /// from it only rolls back to that savepoint, leaving the outer transaction running. If a panic
+    /// unwinds through a transaction the stack is marked as poisoned or rolled back, depending on
+    /// its [`UnwindPolicy`].
+    /// ```rust
+    /// # use history_stack::HistoryStack;
+    /// let mut history = HistoryStack::new(0u8);
+    ///
+    /// let outer = history.transaction(|h| {
+    ///     *h.get_mut() = 1;
+    ///
+    ///     let inner = h.transaction(|h| {
+    ///         *h.get_mut() = 2;
+    ///         Err::<(), _>("inner failed")
+    ///     });
+    ///
+    ///     // only the inner transaction was rolled back
+    ///     assert_eq!(inner, Err("inner failed"));
+    ///     assert_eq!(*h.get(), 1);
+    ///
+    ///     Ok::<_, ()>(())
+    /// });
+    ///
+    /// assert!(outer.is_ok());
+    /// assert_eq!(history, 1);
+    /// assert_eq!(history.pop(), Some(1));
+    /// assert_eq!(history, 0);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the error of `f`, after rolling back
+    ///
+    /// # Panics
+    /// This will panic if allocation failed
+    pub fn transaction<R, E>(&mut self, f: impl FnOnce(&mut Self) -> Result<R, E>) -> Result<R, E>
+    where
+        T: Clone,
+    {
+        let depth = self.stack.len();
+        self.push();
+
+        let mut transaction = Transaction {
+            stack: self,
+            depth,
+            finished: false,
+        };
+
+        let result = f(transaction.stack);
+        transaction.finished = true;
+
+        if result.is_err() {
+            transaction.rollback();
+        }
+
+        result
+    }
+
+    /// Sets what happens when a panic unwinds through a transaction, see [`UnwindPolicy`]
+    pub fn set_unwind_policy(&mut self, policy: UnwindPolicy) {
+        self.unwind = policy;
+    }
+
+    /// Returns what happens when a panic unwinds through a transaction
+    #[must_use]
+    pub fn unwind_policy(&self) -> UnwindPolicy {
+        self.unwind
+    }
+
+    /// Returns `true` if a panic unwound through a [`transaction`](HistoryStack::transaction)
+    /// while the unwind policy was [`UnwindPolicy::Poison`], which may have left the current value
+    /// half modified
+    #[must_use]
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Clears the poisoned flag, after the current value was checked or restored
+    pub fn clear_poison(&mut self) {
+        self.poisoned = false;
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn nested_transactions() {
+    let mut history = HistoryStack::new(0u8);
+
+    let result: Result<(), ()> = history.transaction(|h| {
+        *h.get_mut() += 1;
+
+        h.transaction(|h| {
+            *h.get_mut() += 1;
+            h.push();
+            *h.get_mut() += 1;
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+
+        assert_eq!(*h.get(), 3);
+        Err(())
+    });
+
+    assert!(result.is_err());
+    assert_eq!(history, 0);
+    assert_eq!(history.pop(), None);
+    assert!(!history.is_poisoned());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn poisoned_on_unwind() {
+    let mut history = HistoryStack::new(0u8);
+
+    let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        history.transaction(|h| -> Result<(), ()> {
+            *h.get_mut() = 5;
+            panic!("transaction panicked");
+        })
+    }));
+
+    assert!(unwound.is_err());
+    assert!(history.is_poisoned());
+
+    history.clear_poison();
+    assert!(!history.is_poisoned());
+
+    history.set_unwind_policy(UnwindPolicy::Rollback);
+
+    let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        history.transaction(|h| -> Result<(), ()> {
+            *h.get_mut() = 6;
+            h.transaction(|h| -> Result<(), ()> {
+                h.push();
+                panic!("nested transaction panicked");
+            })
+        })
+    }));
+
+    // both transactions rolled back while unwinding
+    assert!(unwound.is_err());
+    assert!(!history.is_poisoned());
+    assert_eq!(history, 5);
+
+    // the savepoint of the poisoned transaction is still held, so it can be recovered
+    assert_eq!(history.pop(), Some(5));
+    assert_eq!(history, 0);
+}
@@ -0,0 +1,126 @@
+//! Read only views over the past and the future of an undo stack
+
+use core::fmt;
+
+use crate::{HistoryStorage, UndoStack};
+
+/// Defines a read only view over a range of the history of an [`UndoStack`]
+macro_rules! history_view {
+    ($($(#[$attr:meta])* $name:ident;)*) => {$(
+        $(#[$attr])*
+        pub struct $name<'a, T, S> {
+            /// The stack being viewed
+            stack: &'a UndoStack<T, S>,
+            /// The index in history of the first entry of the view
+            start: usize,
+            /// The index in history after the last entry of the view
+            end: usize,
+        }
+
+        impl<T, S> Clone for $name<'_, T, S> {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<T, S> Copy for $name<'_, T, S> {}
+
+        impl<'a, T, S: HistoryStorage<T>> $name<'a, T, S> {
+            /// Returns the amount of entries in the view
+            #[must_use]
+            pub fn len(&self) -> usize {
+                self.end - self.start
+            }
+
+            /// Returns `true` if the view holds no entries
+            #[must_use]
+            pub fn is_empty(&self) -> bool {
+                self.start == self.end
+            }
+
+            /// Returns the entry at `index` within the view, oldest first
+            #[must_use]
+            pub fn get(&self, index: usize) -> Option<&'a T> {
+                (index < self.len()).then(|| self.stack.at(self.start + index))
+            }
+
+            /// Returns an iterator over the entries of the view, oldest first
+            pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'a T> + ExactSizeIterator {
+                let stack = self.stack;
+
+                (self.start..self.end).map(move |i| stack.at(i))
+            }
+        }
+
+        impl<T: fmt::Debug, S: HistoryStorage<T>> fmt::Debug for $name<'_, T, S> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+    )*};
+}
+
+history_view! {
+    /// A read only view of the entries before the current value of an [`UndoStack`], created by
+    /// [`UndoStack::past_view`]. It can be handed to rendering code without letting it change the
+    /// stack.
+    /// ```rust
+    /// # use history_stack::UndoStack;
+    /// let mut undo = UndoStack::new(0u8);
+    /// *undo.save() = 1;
+    /// *undo.save() = 2;
+    /// undo.undo().unwrap();
+    ///
+    /// assert_eq!(undo.past_view().iter().collect::<Vec<_>>(), [&0]);
+    /// assert_eq!(undo.future_view().get(0), Some(&2));
+    /// ```
+    PastView;
+    /// A read only view of the entries after the current value of an [`UndoStack`] that can be
+    /// redone to, created by [`UndoStack::future_view`]
+    FutureView;
+}
+
+impl<T, S: HistoryStorage<T>> UndoStack<T, S> {
+    /// Returns a [`PastView`] of every entry before the current value
+    #[must_use]
+    pub fn past_view(&self) -> PastView<'_, T, S> {
+        PastView {
+            stack: self,
+            start: 0,
+            end: self.current,
+        }
+    }
+
+    /// Returns a [`FutureView`] of every entry after the current value
+    #[must_use]
+    pub fn future_view(&self) -> FutureView<'_, T, S> {
+        FutureView {
+            stack: self,
+            start: self.current + 1,
+            end: self.history.len(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn history_views() {
+    let mut undo = UndoStack::new(0u8);
+    for i in 1..=4 {
+        undo.push(i);
+    }
+    undo.undo().unwrap();
+    undo.undo().unwrap();
+
+    let past = undo.past_view();
+    assert_eq!(past.len(), 2);
+    assert_eq!(past.iter().next_back(), Some(&1));
+    assert_eq!(past.get(2), None);
+
+    let future = undo.future_view();
+    assert!(future.iter().eq([&3, &4]));
+
+    undo.redo().unwrap();
+    undo.redo().unwrap();
+    assert!(undo.future_view().is_empty());
+}